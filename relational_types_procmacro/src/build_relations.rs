@@ -0,0 +1,652 @@
+use quote::*;
+use std::collections::HashMap;
+
+const SUPPORTED_ATTRIBUTES: &str =
+    "supported attributes: `chain`, `sink`, `source`, `backward`, `forward`";
+
+pub fn impl_build_relations(ast: &syn::DeriveInput) -> quote::Tokens {
+    let fields = if let syn::Body::Struct(syn::VariantData::Struct(ref fields)) = ast.body {
+        fields
+    } else {
+        return quote!();
+    };
+    let name = &ast.ident;
+    let mut errors = Vec::new();
+    let (collections, parallel) = struct_attributes(&ast.attrs, &mut errors);
+    let mut base_relations = Vec::new();
+    let mut partial_relations = Vec::new();
+    let mut shortcuts = Vec::new();
+    let mut field_inits = Vec::new();
+    let mut verifications = Vec::new();
+    let mut base_fields = Vec::new();
+    let mut coverages = Vec::new();
+    let mut endpoints = HashMap::new();
+    let mut shortcut_fields = Vec::new();
+    let mut parallel_bases = Vec::new();
+    let mut parallel_shortcuts = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named fields");
+        let ident_name = ident.as_ref();
+        let mut split = ident_name.split("_to_");
+        let collections_names = match (split.next(), split.next(), split.next()) {
+            (Some(from_collection), Some(to_collection), None) => {
+                Some((from_collection, to_collection))
+            }
+            _ => None,
+        };
+        let (from_collection, to_collection) = match collections_names {
+            Some(names) => names,
+            None => {
+                field_inits.push(quote! { #ident: Default::default() });
+                continue;
+            }
+        };
+        field_inits.push(quote! { #ident });
+        match shortcut(field, &mut errors) {
+            Some(((kind, r1, r2), storage)) => {
+                let constructor: quote::Ident = format!("from_relations_{}", kind).as_str().into();
+                shortcut_fields.push((ident, kind, r1.clone(), r2.clone(), storage));
+                let dependencies = (r1.clone(), r2.clone());
+                let r1: quote::Ident = r1.as_str().into();
+                let r2: quote::Ident = r2.as_str().into();
+                let build = match storage {
+                    Some(storage) => {
+                        let storage: quote::Ident = storage.into();
+                        quote!(#storage::from(::relational_types::ManyToMany::#constructor(&#r1, &#r2)))
+                    }
+                    None => quote!(::relational_types::ManyToMany::#constructor(&#r1, &#r2)),
+                };
+                shortcuts.push(quote! {
+                    let #ident = #build;
+                });
+                parallel_shortcuts.push((ident, dependencies, build));
+                verifications.push(quote! {
+                    let expected = ::relational_types::ManyToMany::#constructor(&self.#r1, &self.#r2);
+                    divergences.extend(::relational_types::analysis::ShortcutDivergence::new(
+                        #ident_name,
+                        &self.#ident,
+                        &expected,
+                    ));
+                });
+            }
+            None => {
+                if relation_type(&field.ty) != Some("OneToMany") {
+                    errors.push(format!(
+                        "`{}` must be a `OneToMany` or have a `build_relations` attribute, \
+                         e.g. `#[build_relations(chain = \"a_to_b, b_to_c\")]`",
+                        ident_name
+                    ));
+                    continue;
+                }
+                base_fields.push(field);
+                endpoints.insert(ident_name.to_owned(), (from_collection, to_collection));
+                let from_collection: quote::Ident = from_collection.into();
+                let to_collection: quote::Ident = to_collection.into();
+                coverages.push(quote! {
+                    gaps.extend(::relational_types::analysis::CoverageGap::new(
+                        #ident_name,
+                        &self.#ident,
+                        &collections.#to_collection,
+                    ));
+                });
+                base_relations.push(quote! {
+                    let #ident = ::relational_types::OneToMany::new(
+                        &collections.#from_collection,
+                        &collections.#to_collection,
+                        #ident_name,
+                    )?;
+                });
+                parallel_bases.push((
+                    ident,
+                    quote! {
+                        ::relational_types::OneToMany::new(
+                            &collections.#from_collection,
+                            &collections.#to_collection,
+                            #ident_name,
+                        )
+                    },
+                ));
+                partial_relations.push(quote! {
+                    let #ident = ::relational_types::OneToMany::new(
+                        &collections.#from_collection,
+                        &collections.#to_collection,
+                        #ident_name,
+                    )
+                    .unwrap_or_else(|error| {
+                        report.failures.push((#ident_name, error));
+                        ::relational_types::OneToMany::default()
+                    });
+                });
+            }
+        }
+    }
+    // all the errors are reported at once
+    let collections = match collections {
+        Some(collections) if errors.is_empty() => collections,
+        _ => return crate::compile_errors(&errors),
+    };
+    let rebuilds = base_fields.iter().map(|field| {
+        let ident = field.ident.as_ref().expect("named fields");
+        let ident_name = ident.as_ref();
+        let method: quote::Ident = format!("rebuild_{}", ident_name).as_str().into();
+        let delta_method: quote::Ident = format!("apply_{}_delta", ident_name).as_str().into();
+        let (_, from, to) = crate::relation_types(&field.ty).expect("base relations are OneToMany");
+        let doc = format!(
+            "Rebuilds the `{}` base relation from the given collections, \
+             e.g. after they changed. The shortcuts depending on it are \
+             updated incrementally, the other relations are left untouched.",
+            ident_name
+        );
+        quote! {
+            #[doc = #doc]
+            pub fn #method(
+                &mut self,
+                one: &::relational_types::typed_index_collection::CollectionWithId<#from>,
+                many: &::relational_types::typed_index_collection::CollectionWithId<#to>,
+            ) -> std::result::Result<(), ::relational_types::Error> {
+                let relation = ::relational_types::OneToMany::new(one, many, #ident_name)?;
+                self.#delta_method(::relational_types::Delta::between(&self.#ident, &relation));
+                Ok(())
+            }
+        }
+    });
+    let matches = base_fields.iter().map(|field| {
+        let ident_name = field.ident.as_ref().expect("named fields").as_ref();
+        let (_, from, to) = crate::relation_types(&field.ty).expect("base relations are OneToMany");
+        quote! {
+            if one.is::<::relational_types::typed_index_collection::CollectionWithId<#from>>() && many.is::<::relational_types::typed_index_collection::CollectionWithId<#to>>() {
+                matching.push(#ident_name.to_owned());
+            }
+        }
+    });
+    let rebuilds_by_types = base_fields.iter().map(|field| {
+        let ident_name = field.ident.as_ref().expect("named fields").as_ref();
+        let method: quote::Ident = format!("rebuild_{}", ident_name).as_str().into();
+        let (_, from, to) = crate::relation_types(&field.ty).expect("base relations are OneToMany");
+        quote! {
+            if let (Some(one), Some(many)) = (
+                one.downcast_ref::<::relational_types::typed_index_collection::CollectionWithId<#from>>(),
+                many.downcast_ref::<::relational_types::typed_index_collection::CollectionWithId<#to>>(),
+            ) {
+                return self.#method(one, many);
+            }
+        }
+    });
+    let rebuild_impl = quote! {
+        impl #name {
+            #(#rebuilds)*
+
+            /// Rebuilds the base relation from `T` to `U` from the given
+            /// collections, e.g. after they changed. The shortcuts
+            /// depending on it are updated incrementally, the other
+            /// relations are left untouched. Fails if several base
+            /// relations link `T` to `U`: the generated
+            /// `rebuild_<relation>` methods must be used instead.
+            pub fn rebuild_relation<T: 'static, U: 'static>(
+                &mut self,
+                one: &::relational_types::typed_index_collection::CollectionWithId<T>,
+                many: &::relational_types::typed_index_collection::CollectionWithId<U>,
+            ) -> std::result::Result<(), ::relational_types::Error> {
+                let one: &dyn std::any::Any = one;
+                let many: &dyn std::any::Any = many;
+                let mut matching: Vec<String> = Vec::new();
+                #(#matches)*
+                if matching.len() > 1 {
+                    return Err(::relational_types::Error::AmbiguousRelation(
+                        std::any::type_name::<T>().to_owned(),
+                        std::any::type_name::<U>().to_owned(),
+                        matching,
+                    ));
+                }
+                #(#rebuilds_by_types)*
+                Err(::relational_types::Error::UnknownRelation(
+                    std::any::type_name::<T>().to_owned(),
+                    std::any::type_name::<U>().to_owned(),
+                ))
+            }
+        }
+    };
+    let delta_impls = base_fields
+        .iter()
+        .map(|field| make_delta_impl(name, field, &shortcut_fields));
+    let verify_impl = quote! {
+        impl #name {
+            /// Recomputes every shortcut from its base relations, and
+            /// returns the shortcuts diverging from the recomputed ones,
+            /// e.g. after a mutation of a base relation.
+            pub fn verify_shortcuts(&self) -> Vec<::relational_types::analysis::ShortcutDivergence> {
+                let mut divergences = Vec::new();
+                #(#verifications)*
+                divergences
+            }
+        }
+    };
+    let covers_impl = quote! {
+        impl #name {
+            /// Returns, for each base relation, the objects of its
+            /// target collection it does not contain, e.g. because they
+            /// were pushed into the collection after the relations were
+            /// built.
+            pub fn coverage_gaps(&self, collections: &#collections) -> Vec<::relational_types::analysis::CoverageGap> {
+                let mut gaps = Vec::new();
+                #(#coverages)*
+                gaps
+            }
+
+            /// Panics if a base relation does not contain every object
+            /// of its target collection, or contains unknown objects.
+            pub fn assert_covers(&self, collections: &#collections) {
+                let gaps = self.coverage_gaps(collections);
+                if !gaps.is_empty() {
+                    panic!("the relations do not cover the collections: {:?}", gaps);
+                }
+            }
+        }
+    };
+    resolve_endpoints(&mut endpoints, &shortcut_fields);
+    let mut endpoints: Vec<_> = endpoints.into_iter().collect();
+    endpoints.sort();
+    let link_ids = endpoints
+        .iter()
+        .map(|(relation, (from_collection, to_collection))| {
+            let from_collection: quote::Ident = (*from_collection).into();
+            let to_collection: quote::Ident = (*to_collection).into();
+            quote! {
+                #relation => Some((
+                    ::relational_types::analysis::object_id(&self.#from_collection, from)?,
+                    ::relational_types::analysis::object_id(&self.#to_collection, to)?,
+                )),
+            }
+        });
+    let link_changes = endpoints
+        .iter()
+        .map(|(relation, (from_collection, to_collection))| {
+            let from_collection: quote::Ident = (*from_collection).into();
+            let to_collection: quote::Ident = (*to_collection).into();
+            quote! {
+                #relation => {
+                    let not_found = |id: &str| {
+                        ::relational_types::Error::IdentifierNotFound(id.to_owned(), relation.to_owned())
+                    };
+                    let from_idx = self
+                        .#from_collection
+                        .get_idx(from)
+                        .ok_or_else(|| not_found(from))?;
+                    let to_idx = self.#to_collection.get_idx(to).ok_or_else(|| not_found(to))?;
+                    Ok(::relational_types::LoggedChange::new(#relation, ::relational_types::LinkOp::Added, ::relational_types::Link(from_idx, to_idx)))
+                }
+            }
+        });
+    let link_ids_impl = quote! {
+        impl ::relational_types::LinkIds for #collections {
+            fn link_ids(&self, relation: &str, (from, to): (usize, usize)) -> Option<(String, String)> {
+                match relation {
+                    #(#link_ids)*
+                    _ => None,
+                }
+            }
+        }
+        impl ::relational_types::LinkIndices for #collections {
+            fn link_change(
+                &self,
+                relation: &str,
+                (from, to): (&str, &str),
+            ) -> std::result::Result<::relational_types::LoggedChange, ::relational_types::Error> {
+                match relation {
+                    #(#link_changes)*
+                    _ => Err(::relational_types::Error::UnloggableRelation(relation.to_owned())),
+                }
+            }
+        }
+    };
+    // opt-in, as it requires the collections to be `Sync` and the
+    // relations to be `Send`
+    let parallel_impl = if parallel {
+        make_parallel_impl(
+            name,
+            &collections,
+            &parallel_bases,
+            &parallel_shortcuts,
+            &field_inits,
+        )
+    } else {
+        quote!()
+    };
+    // used by both constructors
+    let field_inits = &field_inits;
+    let shortcuts = &shortcuts;
+    quote! {
+        impl #name {
+            /// Builds every relation from the given collections.
+            pub fn try_build(collections: &#collections) -> std::result::Result<Self, ::relational_types::Error> {
+                #(#base_relations)*
+                #(#shortcuts)*
+                Ok(#name {
+                    #(#field_inits),*
+                })
+            }
+
+            /// Builds every relation from the given collections, the
+            /// relations which can't be built being left empty, and
+            /// returns the errors of all of them.
+            pub fn build_partial(collections: &#collections) -> (Self, ::relational_types::WorldBuildReport) {
+                let mut report = ::relational_types::WorldBuildReport::default();
+                #(#partial_relations)*
+                #(#shortcuts)*
+                let relations = #name {
+                    #(#field_inits),*
+                };
+                (relations, report)
+            }
+        }
+        #parallel_impl
+        #verify_impl
+        #covers_impl
+        #link_ids_impl
+        #rebuild_impl
+        #(#delta_impls)*
+    }
+}
+
+/// Adds to `endpoints`, the collections linked by each base relation,
+/// the collections linked by the shortcuts, deduced from the ones of
+/// their relations, themselves possibly shortcuts.
+fn resolve_endpoints<'a>(
+    endpoints: &mut HashMap<String, (&'a str, &'a str)>,
+    shortcut_fields: &[ShortcutField<'_>],
+) {
+    loop {
+        let mut resolved = false;
+        for &(ident, kind, ref r1, ref r2, _) in shortcut_fields {
+            if endpoints.contains_key(ident.as_ref()) {
+                continue;
+            }
+            let (e1, e2) = match (endpoints.get(r1), endpoints.get(r2)) {
+                (Some(&e1), Some(&e2)) => (e1, e2),
+                _ => continue,
+            };
+            let linked = match kind {
+                "chain" => (e1.0, e2.1),
+                "sink" => (e1.0, e2.0),
+                _ => (e1.1, e2.1),
+            };
+            endpoints.insert(ident.as_ref().to_owned(), linked);
+            resolved = true;
+        }
+        if !resolved {
+            return;
+        }
+    }
+}
+
+/// Generates the `try_build_parallel` constructor, with the `parallel`
+/// attribute of the struct, building the base relations in parallel,
+/// then the shortcuts in successive waves, each one building in
+/// parallel the shortcuts depending only on the relations of the
+/// previous waves.
+fn make_parallel_impl(
+    name: &syn::Ident,
+    collections: &syn::Ty,
+    bases: &[(&syn::Ident, quote::Tokens)],
+    shortcuts: &[(&syn::Ident, (String, String), quote::Tokens)],
+    field_inits: &[quote::Tokens],
+) -> quote::Tokens {
+    // the wave of a relation is the number of shortcuts it depends on,
+    // directly or not, the shortcuts depending on the previous ones
+    let mut waves: HashMap<&str, usize> = HashMap::new();
+    let mut shortcut_waves: Vec<Vec<(&syn::Ident, &quote::Tokens)>> = Vec::new();
+    for &(ident, (ref r1, ref r2), ref build) in shortcuts {
+        let wave_of = |relation: &String| waves.get(relation.as_str()).map_or(0, |&wave| wave);
+        let wave = 1 + std::cmp::max(wave_of(r1), wave_of(r2));
+        waves.insert(ident.as_ref(), wave);
+        if shortcut_waves.len() < wave {
+            shortcut_waves.resize(wave, Vec::new());
+        }
+        shortcut_waves[wave - 1].push((ident, build));
+    }
+    let base_idents: Vec<_> = bases.iter().map(|&(ident, _)| ident).collect();
+    let base_idents = &base_idents;
+    let base_builds = bases.iter().map(|&(_, ref build)| build);
+    let shortcut_waves = shortcut_waves.iter().map(|wave| {
+        let idents: Vec<_> = wave.iter().map(|&(ident, _)| ident).collect();
+        let idents = &idents;
+        let builds = wave.iter().map(|&(_, build)| build);
+        quote! {
+            let (#(#idents,)*) = std::thread::scope(|scope| {
+                #(let #idents = scope.spawn(|| #builds);)*
+                (#(#idents.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)),)*)
+            });
+        }
+    });
+    quote! {
+        impl #name {
+            /// Builds every relation from the given collections, as
+            /// `try_build`, the relations not depending on each other
+            /// being built in parallel.
+            pub fn try_build_parallel(collections: &#collections) -> std::result::Result<Self, ::relational_types::Error> {
+                let (#(#base_idents,)*) = std::thread::scope(|scope| {
+                    #(let #base_idents = scope.spawn(|| #base_builds);)*
+                    (#(#base_idents.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)),)*)
+                });
+                #(let #base_idents = #base_idents?;)*
+                #(#shortcut_waves)*
+                Ok(#name {
+                    #(#field_inits),*
+                })
+            }
+        }
+    }
+}
+
+/// Generates the method applying a delta to the base relation
+/// `field`, and propagating it to the shortcuts depending on it,
+/// directly or through other shortcuts.
+fn make_delta_impl(
+    name: &syn::Ident,
+    field: &syn::Field,
+    shortcut_fields: &[ShortcutField<'_>],
+) -> quote::Tokens {
+    let ident = field.ident.as_ref().expect("named fields");
+    let ty = &field.ty;
+    let method: quote::Ident = format!("apply_{}_delta", ident.as_ref()).as_str().into();
+    let delta_of =
+        |relation: &str| -> quote::Ident { format!("delta_{}", relation).as_str().into() };
+    let mut changed = vec![ident.as_ref().to_owned()];
+    let mut propagations = Vec::new();
+    // the shortcuts are declared after the relations they depend on
+    for &(shortcut, kind, ref r1, ref r2, storage) in shortcut_fields {
+        if !changed.contains(r1) && !changed.contains(r2) {
+            continue;
+        }
+        let shortcut_delta = delta_of(shortcut.as_ref());
+        if let Some(storage) = storage {
+            // updating a shortcut incrementally needs both its forward
+            // and backward links, thus the other ones are recomputed
+            let storage: quote::Ident = storage.into();
+            let constructor: quote::Ident = format!("from_relations_{}", kind).as_str().into();
+            let r1: quote::Ident = r1.as_str().into();
+            let r2: quote::Ident = r2.as_str().into();
+            propagations.push(quote! {
+                let #shortcut_delta = {
+                    let expected = ::relational_types::ManyToMany::#constructor(&self.#r1, &self.#r2);
+                    let delta = ::relational_types::Delta::between(&self.#shortcut, &expected);
+                    self.#shortcut = #storage::from(expected);
+                    delta
+                };
+            });
+            changed.push(shortcut.as_ref().to_owned());
+            continue;
+        }
+        let delta = |relation: &String| {
+            if changed.contains(relation) {
+                let delta = delta_of(relation);
+                quote!(&#delta)
+            } else {
+                quote!(&::relational_types::Delta::default())
+            }
+        };
+        let (delta1, delta2) = (delta(r1), delta(r2));
+        let update: quote::Ident = format!("update_{}", kind).as_str().into();
+        let r1: quote::Ident = r1.as_str().into();
+        let r2: quote::Ident = r2.as_str().into();
+        propagations.push(quote! {
+            let #shortcut_delta = self.#shortcut.#update(&self.#r1, &self.#r2, #delta1, #delta2);
+        });
+        changed.push(shortcut.as_ref().to_owned());
+    }
+    let delta = delta_of(ident.as_ref());
+    quote! {
+        impl #name {
+            /// Applies `delta` to the relation, and updates the
+            /// shortcuts depending on it.
+            #[allow(unused_variables)]
+            pub fn #method(
+                &mut self,
+                delta: ::relational_types::Delta<<#ty as ::relational_types::Relation>::From, <#ty as ::relational_types::Relation>::To>,
+            ) {
+                let #delta = self.#ident.apply_delta(delta);
+                #(#propagations)*
+            }
+        }
+    }
+}
+
+/// Returns the type of the collections given by the `collections`
+/// attribute of the struct, and `true` if it has the `parallel`
+/// attribute.
+fn struct_attributes(
+    attrs: &[syn::Attribute],
+    errors: &mut Vec<String>,
+) -> (Option<syn::Ty>, bool) {
+    use syn::MetaItem::*;
+    use syn::NestedMetaItem::MetaItem;
+
+    let mut collections = None;
+    let mut parallel = false;
+    for mi in attrs.iter().flat_map(|attr| match attr.value {
+        List(ref i, ref v) if i == "build_relations" => v.as_slice(),
+        _ => &[],
+    }) {
+        match *mi {
+            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _))) if i == "collections" => {
+                match syn::parse_type(l) {
+                    Ok(ty) => collections = Some(ty),
+                    Err(_) => errors.push(format!(
+                        "`collections` attribute must be a type, found \"{}\"",
+                        l
+                    )),
+                }
+            }
+            MetaItem(Word(ref i)) if i == "parallel" => parallel = true,
+            _ => errors.push(format!(
+                "`{}` is not a valid attribute of the struct, \
+                 supported attributes: `collections = \"Type\"`, `parallel`",
+                quote!(#mi)
+            )),
+        }
+    }
+    if collections.is_none() {
+        errors.push("`#[build_relations(collections = \"...\")]` attribute is required".into());
+    }
+    (collections, parallel)
+}
+
+/// Kind of shortcut, i.e. `chain`, `sink` or `source`, and names of
+/// the 2 relations it is built from.
+type Shortcut = (&'static str, String, String);
+
+/// Field of a shortcut, with its `Shortcut` and its relation type if
+/// it stores its links in one direction only, e.g.
+/// `ForwardManyToMany`.
+type ShortcutField<'a> = (
+    &'a syn::Ident,
+    &'static str,
+    String,
+    String,
+    Option<&'static str>,
+);
+
+/// Returns the shortcut of the field, if any, and its relation type
+/// if it stores its links in one direction only.
+fn shortcut(
+    field: &syn::Field,
+    errors: &mut Vec<String>,
+) -> Option<(Shortcut, Option<&'static str>)> {
+    use syn::MetaItem::*;
+    use syn::NestedMetaItem::MetaItem;
+
+    let ident = field.ident.as_ref().expect("named fields");
+    let mut shortcut = None;
+    let mut storage = None;
+    for mi in field.attrs.iter().flat_map(|attr| match attr.value {
+        List(ref i, ref v) if i == "build_relations" => v.as_slice(),
+        _ => &[],
+    }) {
+        let parsed = match *mi {
+            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _)))
+                if i == "backward" || i == "forward" =>
+            {
+                match (i.as_ref(), l.as_str()) {
+                    ("backward", "lazy") => storage = Some("ForwardManyToMany"),
+                    ("forward", "computed") => storage = Some("BackwardManyToMany"),
+                    _ => errors.push(format!(
+                        "`{}`: `{}` attribute is not supported, \
+                         use `backward = \"lazy\"` or `forward = \"computed\"`",
+                        ident,
+                        quote!(#mi)
+                    )),
+                }
+                continue;
+            }
+            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _))) => parse_shortcut(i, l),
+            _ => Err(format!(
+                "only `key = \"value\"` attributes are supported, found `{}`, {}",
+                quote!(#mi),
+                SUPPORTED_ATTRIBUTES
+            )),
+        };
+        match parsed {
+            Ok(parsed) => shortcut = Some(parsed),
+            Err(error) => errors.push(format!("`{}`: {}", ident, error)),
+        }
+    }
+    if storage.is_some() && shortcut.is_none() {
+        errors.push(format!(
+            "`{}`: `backward` and `forward` attributes are only supported by the shortcuts, \
+             e.g. `#[build_relations(chain = \"a_to_b, b_to_c\", backward = \"lazy\")]`",
+            ident
+        ));
+    }
+    shortcut.map(|shortcut| (shortcut, storage))
+}
+
+fn parse_shortcut(attribute: &syn::Ident, relations: &str) -> Result<Shortcut, String> {
+    let kind = match attribute.as_ref() {
+        "chain" => "chain",
+        "sink" => "sink",
+        "source" => "source",
+        _ => {
+            return Err(format!(
+                "`{}` is not a valid attribute, {}",
+                attribute, SUPPORTED_ATTRIBUTES
+            ))
+        }
+    };
+    let split: Vec<_> = relations.split(',').map(str::trim).collect();
+    match split.as_slice() {
+        [r1, r2] => Ok((kind, (*r1).to_owned(), (*r2).to_owned())),
+        _ => Err(format!(
+            "`{}` attribute must contain 2 relations, found \"{}\", \
+             e.g. `{} = \"a_to_b, b_to_c\"`",
+            attribute, relations, attribute
+        )),
+    }
+}
+
+fn relation_type(ty: &syn::Ty) -> Option<&str> {
+    if let syn::Ty::Path(_, ref path) = *ty {
+        path.segments.last().map(|segment| segment.ident.as_ref())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,78 @@
+use crate::Edge;
+use quote::*;
+
+/// Generates the `merge_indices` method, folding duplicated objects
+/// into a canonical one in the relations which are not delegated and
+/// whose type is known to be mutable.
+pub fn make_dedup_impl(name: &syn::Ident, edges: &[Edge]) -> quote::Tokens {
+    let merges = edges
+        .iter()
+        .filter(|e| !e.delegated && (e.kind == "OneToMany" || e.kind == "ManyToMany"))
+        .map(|e| {
+            let ident: quote::Ident = e.ident.as_str().into();
+            let (from, to) = (&e.from, &e.to);
+            let (fold_sources, fold_targets) = if e.kind == "OneToMany" {
+                (
+                    quote! {
+                        let mut folded = ::relational_types::OneToMany::default();
+                        folded.extend_links(self.#ident.links().map(|link| {
+                            ::relational_types::Link(fold(link.from()), link.to())
+                        }));
+                        self.#ident = folded;
+                    },
+                    // the canonical child keeps its parent, or takes the
+                    // one of its first duplicate having one
+                    quote! {
+                        for &duplicate in duplicates.iter().filter(|&&idx| idx != canonical) {
+                            if let Some(parent) = self.#ident.parent_of(duplicate) {
+                                self.#ident.remove_link(::relational_types::Link(parent, duplicate));
+                                if self.#ident.parent_of(canonical).is_none() {
+                                    self.#ident.add_link(::relational_types::Link(parent, canonical));
+                                }
+                            }
+                        }
+                    },
+                )
+            } else {
+                (
+                    quote!(self.#ident = self.#ident.map_sources(fold);),
+                    quote!(self.#ident = self.#ident.map_targets(fold);),
+                )
+            };
+            quote! {
+                if let (Some(&canonical), Some(duplicates)) = (
+                    canonical.downcast_ref::<::relational_types::typed_index_collection::Idx<#from>>(),
+                    duplicates.downcast_ref::<::relational_types::IdxSet<#from>>(),
+                ) {
+                    let fold = |idx: ::relational_types::typed_index_collection::Idx<#from>| {
+                        if duplicates.contains(&idx) { canonical } else { idx }
+                    };
+                    #fold_sources
+                }
+                if let (Some(&canonical), Some(duplicates)) = (
+                    canonical.downcast_ref::<::relational_types::typed_index_collection::Idx<#to>>(),
+                    duplicates.downcast_ref::<::relational_types::IdxSet<#to>>(),
+                ) {
+                    let fold = |idx: ::relational_types::typed_index_collection::Idx<#to>| {
+                        if duplicates.contains(&idx) { canonical } else { idx }
+                    };
+                    #fold_targets
+                }
+            }
+        });
+    quote! {
+        impl #name {
+            /// Folds the `duplicates` into `canonical` in every relation
+            /// linking `T` objects, e.g. when deduplicating objects
+            /// giving the same entity, the links of the duplicates
+            /// becoming links of `canonical`. The delegated relations
+            /// are left untouched.
+            #[allow(unused_variables)]
+            pub fn merge_indices<T: 'static>(&mut self, canonical: ::relational_types::typed_index_collection::Idx<T>, duplicates: &::relational_types::IdxSet<T>) {
+                let canonical: &dyn std::any::Any = &canonical;
+                let duplicates: &dyn std::any::Any = duplicates;
+                #(#merges)*
+            }
+        }
+    }
+}
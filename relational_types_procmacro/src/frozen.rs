@@ -0,0 +1,101 @@
+use crate::{traversed_edge, Edge, Node};
+use quote::*;
+
+/// Generates the frozen counterpart of the model, storing every
+/// relation in a `FrozenRelation` and only able to run traversals,
+/// along the given `routes` between every implemented pair of types,
+/// and the `freeze` method building it.
+pub fn make_frozen_impl(
+    vis: &syn::Visibility,
+    name: &syn::Ident,
+    edges: &[Edge],
+    routes: &[Vec<Vec<&Node>>],
+) -> quote::Tokens {
+    let frozen: quote::Ident = format!("Frozen{}", name).as_str().into();
+    let doc = format!(
+        "The relations of a `{}`, frozen by `{}::freeze`, whose traversals \
+         are faster but which can't be modified.",
+        name, name
+    );
+    let fields = edges.iter().map(|e| {
+        let ident: quote::Ident = e.ident.as_str().into();
+        let (from, to) = (&e.from, &e.to);
+        quote!(#ident: ::relational_types::FrozenRelation<#from, #to>)
+    });
+    let freezes = edges.iter().map(|e| {
+        let ident: quote::Ident = e.ident.as_str().into();
+        let relation = e.relation_ref(quote!(self));
+        quote!(#ident: ::relational_types::FrozenRelation::new(#relation))
+    });
+    let traverse_impls = routes.iter().map(|routes| {
+        let (from, to) = (routes[0][0], routes[0][routes[0].len() - 1]);
+        let traversal = if routes.len() == 1 {
+            traversal(edges, &routes[0])
+        } else {
+            let traversals = routes.iter().map(|route| traversal(edges, route));
+            quote! {
+                let mut result = ::relational_types::IdxSet::default();
+                #(result.extend(#traversals);)*
+                result
+            }
+        };
+        quote! {
+            impl ::relational_types::Traverse<#from, #to> for #frozen {
+                fn traverse(&self, from: &::relational_types::IdxSet<#from>) -> ::relational_types::IdxSet<#to> {
+                    #traversal
+                }
+            }
+        }
+    });
+    quote! {
+        #[doc = #doc]
+        #vis struct #frozen {
+            #(#fields),*
+        }
+        impl #name {
+            /// Returns a frozen copy of the relations of the model, e.g.
+            /// to serve the traversals once the model is built.
+            pub fn freeze(&self) -> #frozen {
+                #frozen {
+                    #(#freezes),*
+                }
+            }
+        }
+        impl #frozen {
+            /// Returns the set of `U` indices corresponding to the `from` set.
+            pub fn get_corresponding<T, U>(&self, from: &::relational_types::IdxSet<T>) -> ::relational_types::IdxSet<U>
+            where
+                Self: ::relational_types::Traverse<T, U>
+            {
+                self.traverse(from)
+            }
+            /// Returns the set of `U` indices corresponding to the `from` index.
+            pub fn get_corresponding_from_idx<T, U>(&self, from: ::relational_types::typed_index_collection::Idx<T>) -> ::relational_types::IdxSet<U>
+            where
+                Self: ::relational_types::Traverse<T, U>
+            {
+                self.traverse(&Some(from).into_iter().collect())
+            }
+        }
+        #(#traverse_impls)*
+    }
+}
+
+/// Returns the traversal of the frozen relations along `route`, from
+/// the `from` set.
+fn traversal(edges: &[Edge], route: &[&Node]) -> quote::Tokens {
+    if route.len() == 1 {
+        return quote!(from.clone());
+    }
+    let mut set = quote!(*from);
+    for pair in route.windows(2) {
+        let (edge, forward) = traversed_edge(edges, pair[0], pair[1]);
+        let ident: quote::Ident = edge.ident.as_str().into();
+        set = if forward {
+            quote!(::relational_types::Relation::get_corresponding_forward(&self.#ident, &#set))
+        } else {
+            quote!(::relational_types::Relation::get_corresponding_backward(&self.#ident, &#set))
+        };
+    }
+    set
+}
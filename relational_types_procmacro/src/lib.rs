@@ -1,6 +1,6 @@
 #![deny(missing_docs)]
 
-//! Custom derive for GetCorresponding.  See `relational_types` for the documentation.
+//! Custom derives for GetCorresponding and BuildRelations.  See `relational_types` for the documentation.
 
 #![recursion_limit = "128"]
 
@@ -10,6 +10,16 @@ use quote::*;
 use proc_macro::TokenStream;
 use std::collections::{HashMap, HashSet};
 
+mod build_relations;
+mod dedup;
+mod frozen;
+mod planner;
+mod replay;
+mod schema;
+mod smoke_tests;
+mod union;
+mod unreachable;
+
 /// Generation of the `GetCorresponding` trait implementation.
 #[proc_macro_derive(GetCorresponding, attributes(get_corresponding))]
 pub fn get_corresponding(input: TokenStream) -> TokenStream {
@@ -19,69 +29,317 @@ pub fn get_corresponding(input: TokenStream) -> TokenStream {
     gen.parse().unwrap()
 }
 
+/// Generation of the `try_build` constructor of the relations.
+#[proc_macro_derive(BuildRelations, attributes(build_relations))]
+pub fn build_relations(input: TokenStream) -> TokenStream {
+    let s = input.to_string();
+    let ast = syn::parse_derive_input(&s).unwrap();
+    let gen = build_relations::impl_build_relations(&ast);
+    gen.parse().unwrap()
+}
+
 fn impl_get_corresponding(ast: &syn::DeriveInput) -> quote::Tokens {
     if let syn::Body::Struct(syn::VariantData::Struct(ref fields)) = ast.body {
         let name = &ast.ident;
-        let edges: Vec<_> = fields.iter().filter_map(to_edge).collect();
-        let next = floyd_warshall(&edges);
+        let mut errors = Vec::new();
+        let mut edges: Vec<_> = fields
+            .iter()
+            .filter_map(|field| to_edge(field, &mut errors))
+            .collect();
+        let options = options(&ast.attrs, &mut errors);
+        // all the errors are reported at once
+        if !errors.is_empty() {
+            return compile_errors(&errors);
+        }
+        choose_followed_edges(&mut edges, options.tie_break_names);
+        let (mut next, dist) = floyd_warshall(&edges);
+        if options.tie_break_names {
+            break_ties_by_names(&edges, &dist, &mut next);
+        }
+        let required = match options.only {
+            Some(ref pairs) => match required_pairs(&next, pairs, options.flatten) {
+                Ok(required) => Some(required),
+                Err(error) => return compile_errors(&[error]),
+            },
+            None => None,
+        };
+        let implemented = next.iter().filter(|&(pair, _)| match required {
+            Some(ref required) => required.contains(pair),
+            None => true,
+        });
+        let tests_impl = if options.generate_tests {
+            let pairs: Vec<_> = implemented.clone().map(|(&pair, _)| pair).collect();
+            smoke_tests::make_tests_impl(name, &pairs)
+        } else {
+            quote!()
+        };
+        let frozen_routes: Vec<_> = implemented
+            .clone()
+            .map(|(&(from, to), _)| {
+                let routes = if options.ambiguous_union && from != to {
+                    union::shortest_routes(&edges, &dist, from, to)
+                } else {
+                    Vec::new()
+                };
+                if routes.len() > 1 {
+                    routes
+                } else {
+                    vec![route(&next, from, to)]
+                }
+            })
+            .collect();
+        let frozen_impl = frozen::make_frozen_impl(&ast.vis, name, &edges, &frozen_routes);
         let edge_to_impl = make_edge_to_get_corresponding(name, &edges);
-        let edges_impls = next.iter().map(|(&(from, to), &node)| {
+        let edges_impls = implemented.map(|(&(from, to), &node)| {
+            let routes = if options.ambiguous_union && from != to {
+                union::shortest_routes(&edges, &dist, from, to)
+            } else {
+                Vec::new()
+            };
             if from == to {
                 quote! {
-                    impl GetCorresponding<#to> for IdxSet<#from> {
-                        fn get_corresponding(&self, _: &#name) -> IdxSet<#to> {
+                    impl GetCorresponding<#to> for ::relational_types::IdxSet<#from> {
+                        fn get_corresponding(&self, _: &#name) -> ::relational_types::IdxSet<#to> {
                             self.clone()
                         }
                     }
                 }
+            } else if routes.len() > 1 {
+                let hops = routes[0].len() - 1;
+                let traversal = traced(from, to, hops, union::union_traversal(&edges, &routes, false));
+                let traversal_within =
+                    traced(from, to, hops, union::union_traversal(&edges, &routes, true));
+                quote! {
+                    impl GetCorresponding<#to> for ::relational_types::IdxSet<#from> {
+                        fn get_corresponding(&self, pt_objects: &#name) -> ::relational_types::IdxSet<#to> {
+                            #traversal
+                        }
+                        fn get_corresponding_within(
+                            &self,
+                            pt_objects: &#name,
+                            allowed: &::relational_types::IdxSet<#to>,
+                        ) -> ::relational_types::IdxSet<#to> {
+                            #traversal_within
+                        }
+                    }
+                }
             } else if to == node {
                 edge_to_impl[&(from, to)].clone()
             } else {
-                quote! {
-                    impl GetCorresponding<#to> for IdxSet<#from> {
-                        fn get_corresponding(&self, pt_objects: &#name) -> IdxSet<#to> {
-                            let tmp: IdxSet<#node> = self.get_corresponding(pt_objects);
+                let hops = route(&next, from, to).len() - 1;
+                let (traversal, traversal_within) = if options.flatten {
+                    (
+                        flattened(&edges, &next, from, to, false),
+                        flattened(&edges, &next, from, to, true),
+                    )
+                } else {
+                    (
+                        quote! {
+                            let tmp: ::relational_types::IdxSet<#node> = self.get_corresponding(pt_objects);
                             tmp.get_corresponding(pt_objects)
+                        },
+                        quote! {
+                            let tmp: ::relational_types::IdxSet<#node> = self.get_corresponding(pt_objects);
+                            tmp.get_corresponding_within(pt_objects, allowed)
+                        },
+                    )
+                };
+                let traversal = traced(from, to, hops, traversal);
+                let traversal_within = traced(from, to, hops, traversal_within);
+                quote! {
+                    impl GetCorresponding<#to> for ::relational_types::IdxSet<#from> {
+                        fn get_corresponding(&self, pt_objects: &#name) -> ::relational_types::IdxSet<#to> {
+                            #traversal
+                        }
+                        fn get_corresponding_within(
+                            &self,
+                            pt_objects: &#name,
+                            allowed: &::relational_types::IdxSet<#to>,
+                        ) -> ::relational_types::IdxSet<#to> {
+                            #traversal_within
                         }
                     }
                 }
             }
         });
-        quote! {
+        let known_objects = known_objects(&edges);
+        let report_stats = edges.iter().map(|e| {
+            let ident_name = e.ident.as_str();
+            let relation = e.relation_ref(quote!(self));
+            let known_from = known(&edges, &e.from);
+            quote! {
+                ::relational_types::analysis::RelationStats::new(#ident_name, #relation, #known_from.len())
+            }
+        });
+        let emptiness_checks = edges.iter().map(|e| {
+            let ident_name = e.ident.as_str();
+            let relation = e.relation_ref(quote!(self));
+            quote! {
+                if ::relational_types::Relation::iter_from(#relation).next().is_none() {
+                    empty.push(#ident_name);
+                }
+            }
+        });
+        let report_impl = quote! {
+            impl #name {
+                /// Returns the statistics of every relation of the model,
+                /// the source objects of a relation being the objects of
+                /// its source type linked by any relation of the model.
+                pub fn relation_report(&self) -> ::relational_types::analysis::RelationReport {
+                    #known_objects
+                    ::relational_types::analysis::RelationReport {
+                        relations: vec![#(#report_stats),*],
+                    }
+                }
+                /// Returns the names of the relations without any link,
+                /// in the order of declaration, e.g. to detect a
+                /// misconfigured import, the traversals through them
+                /// giving empty sets.
+                pub fn empty_relations(&self) -> Vec<&'static str> {
+                    let mut empty = Vec::new();
+                    #(#emptiness_checks)*
+                    empty
+                }
+            }
+        };
+        let shrinks = edges.iter().filter(|e| !e.delegated).map(|e| {
+            let ident: quote::Ident = e.ident.as_str().into();
+            quote! {
+                self.#ident.shrink();
+            }
+        });
+        let shrink_impl = quote! {
+            impl #name {
+                /// Reclaims the memory of every relation of the model,
+                /// except the delegated ones.
+                pub fn shrink_all(&mut self) {
+                    #(#shrinks)*
+                }
+            }
+        };
+        let merges = edges.iter().filter(|e| !e.delegated).map(|e| {
+            let ident: quote::Ident = e.ident.as_str().into();
+            quote! {
+                self.#ident.merge(other.#ident, remaps);
+            }
+        });
+        let merge_impl = quote! {
+            impl #name {
+                /// Merges the relations of `other` into the relations of
+                /// the model, after remapping the indices of `other` with
+                /// `remaps`. The delegated relations and the other fields
+                /// are kept from `self`.
+                pub fn merge(mut self, other: Self, remaps: &::relational_types::IdxRemaps) -> Self {
+                    #(#merges)*
+                    self
+                }
+            }
+        };
+        let within_impl = quote! {
+            impl #name {
+                /// Returns the set of `U` indices corresponding to the `from`
+                /// set and belonging to `allowed`, filtering the last step of
+                /// the path while traversing it.
+                pub fn get_corresponding_within<T, U>(
+                    &self,
+                    from: &::relational_types::IdxSet<T>,
+                    allowed: &::relational_types::IdxSet<U>,
+                ) -> ::relational_types::IdxSet<U>
+                where
+                    ::relational_types::IdxSet<T>: GetCorresponding<U>
+                {
+                    from.get_corresponding_within(self, allowed)
+                }
+            }
+        };
+        let world_impl = make_world_impl(name, &edges);
+        let replay_impl = replay::make_replay_impl(name, &edges);
+        let dedup_impl = dedup::make_dedup_impl(name, &edges);
+        let stats_impl = make_stats_impl(name, &edges, &next);
+        let path_cost_impl = make_path_cost_impl(name, &next, &dist);
+        let fuzz_impl = make_fuzz_impl(name, &edges);
+        let relation_between_impl = make_relation_between_impl(name, &edges);
+        let relation_field_impl = make_relation_field_impl(&ast.vis, name, &edges);
+        let try_get_impl = make_try_get_impl(name, &edges);
+        let schema_impl = schema::make_schema_impl(name, &edges, &next);
+        let unreachable_impl = unreachable::make_unreachable_impl(name, &edges);
+        let planner_impl = if options.planner {
+            planner::make_planner_impl(name, &edges, &next)
+        } else {
+            quote!()
+        };
+        let gen = quote! {
             /// A trait that returns a set of objects corresponding to
             /// a given type.
             pub trait GetCorresponding<T: Sized> {
                 /// For the given self, returns the set of
                 /// corresponding `T` indices.
-                fn get_corresponding(&self, model: &#name) -> IdxSet<T>;
+                fn get_corresponding(&self, model: &#name) -> ::relational_types::IdxSet<T>;
+                /// For the given self, returns the set of
+                /// corresponding `T` indices belonging to `allowed`.
+                fn get_corresponding_within(&self, model: &#name, allowed: &::relational_types::IdxSet<T>) -> ::relational_types::IdxSet<T> {
+                    self.get_corresponding(model).intersection(allowed).cloned().collect()
+                }
             }
             impl #name {
                 /// Returns the set of `U` indices corresponding to the `from` set.
-                pub fn get_corresponding<T, U>(&self, from: &IdxSet<T>) -> IdxSet<U>
+                pub fn get_corresponding<T, U>(&self, from: &::relational_types::IdxSet<T>) -> ::relational_types::IdxSet<U>
                 where
-                    IdxSet<T>: GetCorresponding<U>
+                    ::relational_types::IdxSet<T>: GetCorresponding<U>
                 {
                     from.get_corresponding(self)
                 }
                 /// Returns the set of `U` indices corresponding to the `from` index.
-                pub fn get_corresponding_from_idx<T, U>(&self, from: Idx<T>) -> IdxSet<U>
+                pub fn get_corresponding_from_idx<T, U>(&self, from: ::relational_types::typed_index_collection::Idx<T>) -> ::relational_types::IdxSet<U>
                 where
-                    IdxSet<T>: GetCorresponding<U>
+                    ::relational_types::IdxSet<T>: GetCorresponding<U>
                 {
                     self.get_corresponding(&Some(from).into_iter().collect())
                 }
             }
+            impl #name {
+                /// Returns the model built by `init` on first use, e.g. to
+                /// store it in a `static`.
+                pub const fn lazy(init: fn() -> Self) -> ::relational_types::LazyWorld<Self> {
+                    ::relational_types::LazyWorld::new(init)
+                }
+            }
+            #within_impl
+            #report_impl
+            #shrink_impl
+            #merge_impl
+            #world_impl
+            #replay_impl
+            #dedup_impl
+            #stats_impl
+            #path_cost_impl
+            #schema_impl
+            #unreachable_impl
+            #fuzz_impl
+            #relation_between_impl
+            #relation_field_impl
+            #try_get_impl
+            #planner_impl
+            #frozen_impl
+            #tests_impl
             #(#edges_impls)*
+        };
+        if let Some(ref dir) = options.dump_dir {
+            dump(dir, name, &next, &dist, &gen);
         }
+        gen
     } else {
         quote!()
     }
 }
 
-fn to_edge(field: &syn::Field) -> Option<Edge> {
+const SUPPORTED_ATTRIBUTES: &str = "supported attributes: `weight`, `delegate`";
+
+/// Returns the edge of a relation field, pushing the errors found in
+/// its attributes to `errors`.
+fn to_edge(field: &syn::Field, errors: &mut Vec<String>) -> Option<Edge> {
     use syn::MetaItem::*;
     use syn::NestedMetaItem::MetaItem;
-    use syn::PathParameters::AngleBracketed;
 
     let ident = field.ident.as_ref()?.as_ref();
     let mut split = ident.split("_to_");
@@ -90,44 +348,106 @@ fn to_edge(field: &syn::Field) -> Option<Edge> {
     if split.next().is_some() {
         return None;
     }
-    let segment = if let syn::Ty::Path(_, ref path) = field.ty {
+    let mut weight = 1.;
+    let mut delegate = None;
+    for mi in field.attrs.iter().flat_map(|attr| match attr.value {
+        List(ref i, ref v) if i == "get_corresponding" => v.as_slice(),
+        _ => &[],
+    }) {
+        let error = match *mi {
+            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _))) => match i.as_ref() {
+                "weight" => parse_weight(l).map(|w| weight = w).err(),
+                "delegate" => match syn::parse_type(l) {
+                    Ok(ty) => {
+                        delegate = Some(ty);
+                        None
+                    }
+                    Err(_) => Some(format!(
+                        "`delegate` attribute must be a relation type, found \"{}\", \
+                         e.g. `delegate = \"OneToMany<From, To>\"`",
+                        l
+                    )),
+                },
+                _ => Some(format!(
+                    "`{}` is not a valid attribute, {}",
+                    i, SUPPORTED_ATTRIBUTES
+                )),
+            },
+            _ => Some(format!(
+                "only `key = \"value\"` attributes are supported, found `{}`, {}",
+                quote!(#mi),
+                SUPPORTED_ATTRIBUTES
+            )),
+        };
+        if let Some(error) = error {
+            errors.push(format!("`{}`: {}", ident, error));
+        }
+    }
+    let (kind, from_ty, to_ty) = relation_types(delegate.as_ref().unwrap_or(&field.ty))?;
+
+    Edge {
+        ident: ident.into(),
+        kind: kind.as_ref().into(),
+        from: from_ty.clone(),
+        to: to_ty.clone(),
+        weight,
+        delegated: delegate.is_some(),
+        followed: true,
+    }
+    .into()
+}
+
+/// Parses the `weight` attribute. The weights are needed to choose
+/// the paths while expanding the derive, so they must be literals:
+/// constants can't be evaluated at this time.
+fn parse_weight(weight: &str) -> Result<f64, String> {
+    if weight.contains(',') && weight.replace(',', ".").parse::<f64>().is_ok() {
+        return Err(format!(
+            "`weight` attribute must use a dot as decimal separator, found \"{}\"",
+            weight
+        ));
+    }
+    if weight.contains("::") && syn::parse_type(weight).is_ok() {
+        return Err(format!(
+            "`weight` attribute must be a float literal, the constant `{}` can't be \
+             evaluated by the derive",
+            weight
+        ));
+    }
+    let parsed = weight.parse::<f64>().map_err(|_| {
+        format!(
+            "`weight` attribute must be convertible to f64, found \"{}\", \
+             e.g. `weight = \"1.9\"`",
+            weight
+        )
+    })?;
+    if !parsed.is_finite() {
+        return Err(format!(
+            "`weight` attribute must be a finite number, found \"{}\"",
+            weight
+        ));
+    }
+    Ok(parsed)
+}
+
+/// Returns the name of the relation type, e.g. `OneToMany`, and the
+/// types it links.
+fn relation_types(ty: &syn::Ty) -> Option<(&syn::Ident, &syn::Ty, &syn::Ty)> {
+    use syn::PathParameters::AngleBracketed;
+
+    let segment = if let syn::Ty::Path(_, ref path) = *ty {
         path.segments.last()
     } else {
         None
     }?;
-    let (from_ty, to_ty) = if let AngleBracketed(ref data) = segment.parameters {
+    if let AngleBracketed(ref data) = segment.parameters {
         match (data.types.get(0), data.types.get(1), data.types.get(2)) {
-            (Some(from_ty), Some(to_ty), None) => Some((from_ty, to_ty)),
+            (Some(from_ty), Some(to_ty), None) => Some((&segment.ident, from_ty, to_ty)),
             _ => None,
         }
     } else {
         None
-    }?;
-    let weight = field
-        .attrs
-        .iter()
-        .flat_map(|attr| match attr.value {
-            List(ref i, ref v) if i == "get_corresponding" => v.as_slice(),
-            _ => &[],
-        })
-        .map(|mi| match *mi {
-            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _))) => {
-                assert_eq!(i, "weight", "{} is not a valid attribute", i);
-                l.parse::<f64>()
-                    .expect("`weight` attribute must be convertible to f64")
-            }
-            _ => panic!("Only `key = \"value\"` attributes supported."),
-        })
-        .last()
-        .unwrap_or(1.);
-
-    Edge {
-        ident: ident.into(),
-        from: from_ty.clone(),
-        to: to_ty.clone(),
-        weight,
     }
-    .into()
 }
 
 fn make_edge_to_get_corresponding<'a>(
@@ -135,16 +455,23 @@ fn make_edge_to_get_corresponding<'a>(
     edges: &'a [Edge],
 ) -> HashMap<(&'a syn::Ty, &'a syn::Ty), quote::Tokens> {
     let mut res = HashMap::default();
-    for e in edges {
-        let ident: quote::Ident = e.ident.as_str().into();
+    for e in edges.iter().filter(|e| e.followed) {
+        let relation = e.relation_ref(quote!(pt_objects));
         let from = &e.from;
         let to = &e.to;
         res.insert(
             (from, to),
             quote! {
-                impl GetCorresponding<#to> for IdxSet<#from> {
-                    fn get_corresponding(&self, pt_objects: &#name) -> IdxSet<#to> {
-                        pt_objects.#ident.get_corresponding_forward(self)
+                impl GetCorresponding<#to> for ::relational_types::IdxSet<#from> {
+                    fn get_corresponding(&self, pt_objects: &#name) -> ::relational_types::IdxSet<#to> {
+                        ::relational_types::Relation::get_corresponding_forward(#relation, self)
+                    }
+                    fn get_corresponding_within(
+                        &self,
+                        pt_objects: &#name,
+                        allowed: &::relational_types::IdxSet<#to>,
+                    ) -> ::relational_types::IdxSet<#to> {
+                        ::relational_types::Relation::get_corresponding_forward_within(#relation, self, allowed)
                     }
                 }
             },
@@ -152,9 +479,16 @@ fn make_edge_to_get_corresponding<'a>(
         res.insert(
             (to, from),
             quote! {
-                impl GetCorresponding<#from> for IdxSet<#to> {
-                    fn get_corresponding(&self, pt_objects: &#name) -> IdxSet<#from> {
-                        pt_objects.#ident.get_corresponding_backward(self)
+                impl GetCorresponding<#from> for ::relational_types::IdxSet<#to> {
+                    fn get_corresponding(&self, pt_objects: &#name) -> ::relational_types::IdxSet<#from> {
+                        ::relational_types::Relation::get_corresponding_backward(#relation, self)
+                    }
+                    fn get_corresponding_within(
+                        &self,
+                        pt_objects: &#name,
+                        allowed: &::relational_types::IdxSet<#from>,
+                    ) -> ::relational_types::IdxSet<#from> {
+                        ::relational_types::Relation::get_corresponding_backward_within(#relation, self, allowed)
                     }
                 }
             },
@@ -163,16 +497,746 @@ fn make_edge_to_get_corresponding<'a>(
     res
 }
 
-fn floyd_warshall(edges: &[Edge]) -> HashMap<(&Node, &Node), &Node> {
+/// Returns the types linked by the edges, in the order of first
+/// appearance.
+fn nodes(edges: &[Edge]) -> Vec<&Node> {
+    let mut nodes = Vec::<&Node>::new();
+    for e in edges {
+        for node in vec![&e.from, &e.to] {
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+    }
+    nodes
+}
+
+/// Returns the name of the variable holding the objects of type
+/// `node` known by the model, see `known_objects`.
+fn known(edges: &[Edge], node: &Node) -> quote::Ident {
+    let position = nodes(edges).iter().position(|&n| n == node).unwrap();
+    format!("known_{}", position).as_str().into()
+}
+
+/// Collects the objects of each type linked by a relation of the model,
+/// in the variables named by `known`, the model giving no other way to
+/// count its objects.
+fn known_objects(edges: &[Edge]) -> quote::Tokens {
+    let known_sets = nodes(edges).into_iter().map(|node| {
+        let known = known(edges, node);
+        quote! {
+            let mut #known: ::relational_types::IdxSet<#node> = ::relational_types::IdxSet::default();
+        }
+    });
+    let known_extensions = edges.iter().map(|e| {
+        let relation = e.relation_ref(quote!(self));
+        let known_from = known(edges, &e.from);
+        let known_to = known(edges, &e.to);
+        quote! {
+            #known_from.extend(::relational_types::Relation::iter_from(#relation));
+            #known_to.extend(::relational_types::Relation::iter_to(#relation));
+        }
+    });
+    quote! {
+        #(#known_sets)*
+        #(#known_extensions)*
+    }
+}
+
+fn make_stats_impl(name: &syn::Ident, edges: &[Edge], next: &Next) -> quote::Tokens {
+    let known_objects = known_objects(edges);
+    let relations = edges.iter().map(|e| {
+        let ident_name = e.ident.as_str();
+        let relation = e.relation_ref(quote!(self));
+        let known_from = known(edges, &e.from);
+        let known_to = known(edges, &e.to);
+        quote! {
+            ::relational_types::analysis::RelationCoverage::new(
+                #ident_name,
+                #relation,
+                #known_from.len(),
+                #known_to.len(),
+            )
+        }
+    });
+    let routes: Vec<Vec<_>> = routes(next)
+        .into_iter()
+        .map(|route| route.into_iter().map(type_name).collect())
+        .collect();
+    let paths = routes.iter().map(|route| {
+        let from = &route[0];
+        let to = &route[route.len() - 1];
+        quote! {
+            ::relational_types::analysis::PathStats {
+                from: #from.to_owned(),
+                to: #to.to_owned(),
+                route: vec![#(#route.to_owned()),*],
+            }
+        }
+    });
+    let stats_json = if cfg!(feature = "serde") {
+        quote! {
+            /// Returns the statistics of the model, serialized in JSON.
+            pub fn stats_json(&self) -> String {
+                self.stats().to_json()
+            }
+        }
+    } else {
+        quote!()
+    };
+    quote! {
+        impl #name {
+            /// Returns the coverage of every relation of the model and
+            /// the path chosen between every pair of types.
+            pub fn stats(&self) -> ::relational_types::analysis::WorldStats {
+                #known_objects
+                ::relational_types::analysis::WorldStats {
+                    relations: vec![#(#relations),*],
+                    paths: vec![#(#paths),*],
+                }
+            }
+            #stats_json
+        }
+    }
+}
+
+/// Returns the types traversed by every path between 2 different
+/// types, sorted by names.
+fn make_world_impl(name: &syn::Ident, edges: &[Edge]) -> quote::Tokens {
+    let infos = edges.iter().map(|e| {
+        let ident_name = e.ident.as_str();
+        let from = type_name(&e.from);
+        let to = type_name(&e.to);
+        quote! {
+            ::relational_types::RelationInfo {
+                name: #ident_name,
+                from: #from,
+                to: #to,
+            }
+        }
+    });
+    let links = edges.iter().map(|e| {
+        let ident_name = e.ident.as_str();
+        let relation = e.relation_ref(quote!(self));
+        quote! {
+            #ident_name => {
+                ::relational_types::Relation::for_each_link(#relation, |from_idx, to_idx| f(from_idx.get(), to_idx.get()));
+                true
+            }
+        }
+    });
+    quote! {
+        impl ::relational_types::RelationalWorld for #name {
+            fn relations(&self) -> &'static [::relational_types::RelationInfo] {
+                &[#(#infos),*]
+            }
+            fn links_of(&self, name: &str) -> Option<Vec<(usize, usize)>> {
+                let mut links = Vec::new();
+                if ::relational_types::RelationalWorld::for_each_link_of(self, name, &mut |from, to| links.push((from, to))) {
+                    Some(links)
+                } else {
+                    None
+                }
+            }
+            // `f` is not called by a model without relations
+            #[allow(unused_variables)]
+            fn for_each_link_of(&self, name: &str, f: &mut dyn FnMut(usize, usize)) -> bool {
+                match name {
+                    #(#links)*
+                    _ => false,
+                }
+            }
+        }
+        impl<T, U> ::relational_types::Traverse<T, U> for #name
+        where
+            ::relational_types::IdxSet<T>: GetCorresponding<U>
+        {
+            fn traverse(&self, from: &::relational_types::IdxSet<T>) -> ::relational_types::IdxSet<U> {
+                from.get_corresponding(self)
+            }
+        }
+    }
+}
+
+/// With the `fuzz` feature, generates the `apply_random_ops` method.
+fn make_fuzz_impl(name: &syn::Ident, edges: &[Edge]) -> quote::Tokens {
+    if !cfg!(feature = "fuzz") {
+        return quote!();
+    }
+    let arms: Vec<_> = edges
+        .iter()
+        .filter(|e| !e.delegated)
+        .enumerate()
+        .map(|(i, e)| {
+            let ident: quote::Ident = e.ident.as_str().into();
+            quote! {
+                #i => {
+                    ::relational_types::Fuzz::apply_random_op(&mut self.#ident, rng);
+                    ::relational_types::Fuzz::assert_invariants(&self.#ident);
+                }
+            }
+        })
+        .collect();
+    let body = if arms.is_empty() {
+        quote! {
+            let _ = (rng, n);
+        }
+    } else {
+        let nb_relations = arms.len();
+        quote! {
+            for _ in 0..n {
+                match rng.gen_range(0..#nb_relations) {
+                    #(#arms)*
+                    _ => unreachable!(),
+                }
+            }
+        }
+    };
+    quote! {
+        impl #name {
+            /// Applies `n` random additions or removals of links to the
+            /// relations of the model, except the delegated ones, and
+            /// panics if a mutated relation becomes inconsistent.
+            pub fn apply_random_ops<R: ::relational_types::rand::Rng + ?Sized>(&mut self, rng: &mut R, n: usize) {
+                #body
+            }
+        }
+    }
+}
+
+fn make_path_cost_impl(name: &syn::Ident, next: &Next, dist: &Dist) -> quote::Tokens {
+    let mut pairs: Vec<_> = next.keys().collect();
+    pairs.sort_by_key(|&&(from, to)| (type_name(from), type_name(to)));
+    let costs = pairs.into_iter().map(|&(from, to)| {
+        let cost = if from == to { 0. } else { dist[&(from, to)] };
+        quote! {
+            if types == (std::any::TypeId::of::<#from>(), std::any::TypeId::of::<#to>()) {
+                return Some(#cost);
+            }
+        }
+    });
+    quote! {
+        impl #name {
+            /// Returns the total weight of the path chosen from `T` to
+            /// `U`, or `None` if `U` is not reachable from `T`.
+            pub fn path_cost<T: 'static, U: 'static>() -> Option<f64> {
+                let types = (std::any::TypeId::of::<T>(), std::any::TypeId::of::<U>());
+                #(#costs)*
+                None
+            }
+        }
+    }
+}
+
+/// Generates the `relation_between` and `relation_field_name`
+/// methods, returning the relation followed between 2 types, i.e. the
+/// one used by the traversals.
+fn make_relation_between_impl(name: &syn::Ident, edges: &[Edge]) -> quote::Tokens {
+    let matches = |e: &Edge| {
+        let from = &e.from;
+        let to = &e.to;
+        quote! {
+            types == (std::any::TypeId::of::<#from>(), std::any::TypeId::of::<#to>())
+                || types == (std::any::TypeId::of::<#to>(), std::any::TypeId::of::<#from>())
+        }
+    };
+    let relations = edges.iter().filter(|e| e.followed).map(|e| {
+        let condition = matches(e);
+        let relation = e.relation_ref(quote!(self));
+        quote! {
+            if #condition {
+                return Some(#relation as &dyn ::relational_types::DynRelation);
+            }
+        }
+    });
+    let field_names = edges.iter().filter(|e| e.followed).map(|e| {
+        let condition = matches(e);
+        let ident_name = e.ident.as_str();
+        quote! {
+            if #condition {
+                return Some(#ident_name);
+            }
+        }
+    });
+    quote! {
+        impl #name {
+            /// Returns the relation directly linking `T` and `U`, in
+            /// either direction, if any.
+            pub fn relation_between<T: 'static, U: 'static>(&self) -> Option<&dyn ::relational_types::DynRelation> {
+                let types = (std::any::TypeId::of::<T>(), std::any::TypeId::of::<U>());
+                #(#relations)*
+                None
+            }
+            /// Returns the name of the field of the relation directly
+            /// linking `T` and `U`, in either direction, if any.
+            pub fn relation_field_name<T: 'static, U: 'static>() -> Option<&'static str> {
+                let types = (std::any::TypeId::of::<T>(), std::any::TypeId::of::<U>());
+                #(#field_names)*
+                None
+            }
+        }
+    }
+}
+
+/// Generates the `RelationField` enum, with a variant named after
+/// each relation, and the `relation` method returning the relation of
+/// a variant.
+fn make_relation_field_impl(
+    vis: &syn::Visibility,
+    name: &syn::Ident,
+    edges: &[Edge],
+) -> quote::Tokens {
+    let variant = |e: &Edge| -> quote::Ident {
+        let camel_case: String = e
+            .ident
+            .split('_')
+            .flat_map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .into_iter()
+                    .flat_map(char::to_uppercase)
+                    .chain(chars)
+            })
+            .collect();
+        camel_case.as_str().into()
+    };
+    let variants: Vec<_> = edges.iter().map(variant).collect();
+    let variants = &variants;
+    let names: Vec<_> = edges.iter().map(|e| e.ident.as_str()).collect();
+    let names = &names;
+    let variants_docs = edges.iter().map(|e| format!("The `{}` relation.", e.ident));
+    let relations = edges.iter().map(|e| e.relation_ref(quote!(self)));
+    let doc = format!("The relations of a `{}`.", name);
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #vis enum RelationField {
+            #(
+                #[doc = #variants_docs]
+                #variants,
+            )*
+        }
+        impl RelationField {
+            /// Every relation, in the order of declaration.
+            pub const ALL: &'static [RelationField] = &[#(RelationField::#variants),*];
+
+            /// Returns the name of the field of the relation.
+            pub fn name(self) -> &'static str {
+                match self {
+                    #(RelationField::#variants => #names,)*
+                }
+            }
+
+            /// Returns the relation whose field is named `name`, if any.
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    #(#names => Some(RelationField::#variants),)*
+                    _ => None,
+                }
+            }
+        }
+        impl #name {
+            /// Returns the relation of the given field.
+            pub fn relation(&self, field: RelationField) -> &dyn ::relational_types::DynRelation {
+                match field {
+                    #(RelationField::#variants => #relations as &dyn ::relational_types::DynRelation,)*
+                }
+            }
+        }
+    }
+}
+
+/// Generates the `try_get_corresponding_from_idx` and
+/// `get_corresponding_with` methods, failing or resolving otherwise
+/// instead of returning an empty set when an index is linked by no
+/// relation.
+fn make_try_get_impl(name: &syn::Ident, edges: &[Edge]) -> quote::Tokens {
+    let lookups = edges.iter().map(|e| {
+        let from = &e.from;
+        let to = &e.to;
+        let relation = e.relation_ref(quote!(self));
+        // a keyed lookup, `get_corresponding_with` checking each index
+        // of its seed
+        quote! {
+            if let Some(&from_idx) = idx.downcast_ref::<::relational_types::typed_index_collection::Idx<#from>>() {
+                let from_indices: ::relational_types::IdxSet<#from> = std::iter::once(from_idx).collect();
+                if !::relational_types::Relation::get_corresponding_forward(#relation, &from_indices).is_empty() {
+                    return true;
+                }
+            }
+            if let Some(&to_idx) = idx.downcast_ref::<::relational_types::typed_index_collection::Idx<#to>>() {
+                let to_indices: ::relational_types::IdxSet<#to> = std::iter::once(to_idx).collect();
+                if !::relational_types::Relation::get_corresponding_backward(#relation, &to_indices).is_empty() {
+                    return true;
+                }
+            }
+        }
+    });
+    quote! {
+        impl #name {
+            /// Returns `true` if `idx` is linked by at least one
+            /// relation.
+            pub fn is_linked<T: 'static>(&self, idx: ::relational_types::typed_index_collection::Idx<T>) -> bool {
+                // not used by a model without relations
+                #[allow(unused_variables)]
+                let idx: &dyn std::any::Any = &idx;
+                #(#lookups)*
+                false
+            }
+            /// Returns the set of `U` indices corresponding to the `from`
+            /// index, or an error if `from` is linked by no relation,
+            /// e.g. an index of another collection, unlike
+            /// `get_corresponding_from_idx` returning an empty set.
+            pub fn try_get_corresponding_from_idx<T: 'static, U>(
+                &self,
+                from: ::relational_types::typed_index_collection::Idx<T>,
+            ) -> std::result::Result<::relational_types::IdxSet<U>, ::relational_types::UnknownIndex>
+            where
+                ::relational_types::IdxSet<T>: GetCorresponding<U>
+            {
+                if !self.is_linked(from) {
+                    return Err(::relational_types::UnknownIndex {
+                        index: from.get(),
+                        type_name: std::any::type_name::<T>(),
+                    });
+                }
+                Ok(self.get_corresponding_from_idx(from))
+            }
+            /// Returns the set of `U` indices corresponding to the `from`
+            /// set, the indices linked by no relation being handled as
+            /// told by `missing`.
+            pub fn get_corresponding_with<T: 'static, U>(
+                &self,
+                from: &::relational_types::IdxSet<T>,
+                missing: ::relational_types::MissingIndex<'_, T, U>,
+            ) -> std::result::Result<::relational_types::IdxSet<U>, ::relational_types::UnknownIndex>
+            where
+                ::relational_types::IdxSet<T>: GetCorresponding<U>
+            {
+                let (linked, unknown): (::relational_types::IdxSet<T>, ::relational_types::IdxSet<T>) =
+                    from.iter().copied().partition(|&idx| self.is_linked(idx));
+                let mut result = ::relational_types::IdxSet::default();
+                for idx in unknown {
+                    match missing {
+                        ::relational_types::MissingIndex::Ignore => {}
+                        ::relational_types::MissingIndex::Error => {
+                            return Err(::relational_types::UnknownIndex {
+                                index: idx.get(),
+                                type_name: std::any::type_name::<T>(),
+                            });
+                        }
+                        ::relational_types::MissingIndex::Fallback(resolve) => result.extend(resolve(idx)),
+                    }
+                }
+                result.extend(self.get_corresponding(&linked));
+                Ok(result)
+            }
+        }
+    }
+}
+
+fn routes<'a>(next: &Next<'a>) -> Vec<Vec<&'a Node>> {
+    let mut routes: Vec<_> = next
+        .keys()
+        .filter(|&&(from, to)| from != to)
+        .map(|&(from, to)| route(next, from, to))
+        .collect();
+    routes.sort_by_key(|route| {
+        route
+            .iter()
+            .map(|&node| type_name(node))
+            .collect::<Vec<_>>()
+    });
+    routes
+}
+
+/// Route chosen from `from` to `to`, including both of them.
+fn route<'a>(next: &Next<'a>, from: &'a Node, to: &'a Node) -> Vec<&'a Node> {
+    let mut route = vec![from];
+    let mut current = from;
+    while current != to {
+        current = next[&(current, to)];
+        route.push(current);
+    }
+    route
+}
+
+/// Options given by the attributes of the struct.
+#[derive(Default)]
+struct Options {
+    dump_dir: Option<std::path::PathBuf>,
+    planner: bool,
+    flatten: bool,
+    generate_tests: bool,
+    ambiguous_union: bool,
+    tie_break_names: bool,
+    only: Option<Vec<(Node, Node)>>,
+}
+
+fn options(attrs: &[syn::Attribute], errors: &mut Vec<String>) -> Options {
+    use syn::MetaItem::*;
+    use syn::NestedMetaItem::MetaItem;
+
+    let mut options = Options::default();
+    for mi in attrs.iter().flat_map(|attr| match attr.value {
+        List(ref i, ref v) if i == "get_corresponding" => v.as_slice(),
+        _ => &[],
+    }) {
+        match *mi {
+            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _))) if i == "dump_to" => {
+                // the directory can be given through an environment
+                // variable, e.g. `OUT_DIR`
+                options.dump_dir = std::env::var_os(l)
+                    .map(std::path::PathBuf::from)
+                    .or_else(|| Some(l.into()));
+            }
+            MetaItem(Word(ref i)) if i == "planner" => options.planner = true,
+            MetaItem(Word(ref i)) if i == "flatten" => options.flatten = true,
+            MetaItem(Word(ref i)) if i == "generate_tests" => options.generate_tests = true,
+            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _))) if i == "ambiguous" => {
+                if l == "union" {
+                    options.ambiguous_union = true;
+                } else {
+                    errors.push(format!(
+                        "`ambiguous` attribute must be \"union\", found \"{}\"",
+                        l
+                    ));
+                }
+            }
+            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _))) if i == "tie_break" => {
+                match l.as_str() {
+                    "declaration" => options.tie_break_names = false,
+                    "field_names" => options.tie_break_names = true,
+                    _ => errors.push(format!(
+                        "`tie_break` attribute must be \"declaration\" or \"field_names\", \
+                         found \"{}\"",
+                        l
+                    )),
+                }
+            }
+            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _))) if i == "cost_fn" => {
+                // like the constant weights, a function of the crate
+                // being compiled can't be called during the expansion
+                errors.push(format!(
+                    "`cost_fn` attribute is not supported, the function `{}` can't be called \
+                     by the derive, the costs being needed to choose the paths while \
+                     expanding it: use `weight` attributes on the fields instead",
+                    l
+                ))
+            }
+            MetaItem(NameValue(ref i, syn::Lit::Str(ref l, _))) if i == "only" => {
+                match parse_pairs(l) {
+                    Ok(pairs) => options.only = Some(pairs),
+                    Err(error) => errors.push(error),
+                }
+            }
+            _ => errors.push(format!(
+                "`{}` is not a valid attribute of the struct, \
+                 supported attributes: `dump_to = \"DIRECTORY\"`, `planner`, \
+                 `flatten`, `generate_tests`, `ambiguous = \"union\"`, \
+                 `tie_break = \"field_names\"`, \
+                 `only = \"From -> To, ...\"`",
+                quote!(#mi)
+            )),
+        }
+    }
+    options
+}
+
+/// Parses the `only` attribute, a list of pairs of types like
+/// `"Line -> StopPoint, StopPoint -> Line"`.
+fn parse_pairs(pairs: &str) -> Result<Vec<(Node, Node)>, String> {
+    pairs
+        .split(',')
+        .map(|pair| {
+            let mut split = pair.split("->").map(|ty| syn::parse_type(ty.trim()));
+            match (split.next(), split.next(), split.next()) {
+                (Some(Ok(from)), Some(Ok(to)), None) => Ok((from, to)),
+                _ => Err(format!(
+                    "`only` attribute must contain pairs of types, found \"{}\", \
+                     e.g. `only = \"A -> B, B -> A\"`",
+                    pair.trim()
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Reports the errors found in the attributes as `compile_error!`
+/// invocations, syn 0.11 giving no span to point at the attributes.
+fn compile_errors(errors: &[String]) -> quote::Tokens {
+    let errors = errors.iter();
+    quote! {
+        #(compile_error!(#errors);)*
+    }
+}
+
+/// Returns the pairs of types whose implementation is needed by the
+/// given `pairs`. Unless the traversals are `flattened`, a path from
+/// `A` to `C` through `B` is implemented with the implementations
+/// from `A` to `B` and from `B` to `C`.
+fn required_pairs<'a>(
+    next: &Next<'a>,
+    pairs: &[(Node, Node)],
+    flattened: bool,
+) -> Result<HashSet<(&'a Node, &'a Node)>, String> {
+    let mut required = HashSet::default();
+    let mut to_visit = Vec::new();
+    for (from, to) in pairs {
+        match next.get_key_value(&(from, to)) {
+            Some((&pair, _)) => to_visit.push(pair),
+            None => {
+                return Err(format!(
+                    "`only` attribute: there is no path from `{}` to `{}`",
+                    type_name(from),
+                    type_name(to)
+                ))
+            }
+        }
+    }
+    while let Some((from, to)) = to_visit.pop() {
+        if !required.insert((from, to)) {
+            continue;
+        }
+        let node = next[&(from, to)];
+        if !flattened && from != to && node != to {
+            to_visit.push((from, node));
+            to_visit.push((node, to));
+        }
+    }
+    Ok(required)
+}
+
+fn dump(dir: &std::path::Path, name: &syn::Ident, next: &Next, dist: &Dist, gen: &quote::Tokens) {
+    let mut content = format!(
+        "// Code generated by the GetCorresponding derive of `{}`.\n//\n",
+        name
+    );
+    for route in routes(next) {
+        let names: Vec<_> = route.iter().map(|&node| type_name(node)).collect();
+        let cost = dist[&(route[0], route[route.len() - 1])];
+        content.push_str(&format!("// {} (cost {})\n", names.join(" -> "), cost));
+    }
+    content.push('\n');
+    content.push_str(gen.as_str());
+    content.push('\n');
+    let path = dir.join(format!("{}_get_corresponding.rs", name));
+    std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&path, content))
+        .unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+}
+
+/// Returns the traversal from `from` to `to` as a single expression
+/// following the relations of the path, the last one being filtered
+/// by `allowed` if `within`.
+fn flattened(edges: &[Edge], next: &Next, from: &Node, to: &Node, within: bool) -> quote::Tokens {
+    let route = route(next, from, to);
+    let mut set = quote!(self);
+    for (i, pair) in route.windows(2).enumerate() {
+        let (edge, forward) = traversed_edge(edges, pair[0], pair[1]);
+        let relation = edge.relation_ref(quote!(pt_objects));
+        let last = i + 2 == route.len();
+        set = match (forward, within && last) {
+            (true, false) => {
+                quote!(::relational_types::Relation::get_corresponding_forward(#relation, &#set))
+            }
+            (false, false) => {
+                quote!(::relational_types::Relation::get_corresponding_backward(#relation, &#set))
+            }
+            (true, true) => {
+                quote!(::relational_types::Relation::get_corresponding_forward_within(#relation, &#set, allowed))
+            }
+            (false, true) => {
+                quote!(::relational_types::Relation::get_corresponding_backward_within(#relation, &#set, allowed))
+            }
+        };
+    }
+    set
+}
+
+/// Chooses the edge followed among parallel edges, i.e. edges linking
+/// the same 2 nodes: the last declared one, or with the
+/// `tie_break = "field_names"` attribute, the one of smallest name
+/// among the ones of minimal weight. The other parallel edges are
+/// ignored by the paths.
+fn choose_followed_edges(edges: &mut [Edge], by_names: bool) {
+    let parallel = |e1: &Edge, e2: &Edge| {
+        (e1.from == e2.from && e1.to == e2.to) || (e1.from == e2.to && e1.to == e2.from)
+    };
+    for i in 0..edges.len() {
+        let edge = &edges[i];
+        let followed = edges.iter().enumerate().all(|(j, other)| {
+            if j == i || !parallel(edge, other) {
+                true
+            } else if by_names {
+                (edge.weight, edge.ident.as_str()) < (other.weight, other.ident.as_str())
+            } else {
+                j < i
+            }
+        });
+        edges[i].followed = followed;
+    }
+}
+
+/// Returns the edge traversed between `a` and `b`, i.e. the followed
+/// one, and whether it is traversed forward.
+fn traversed_edge<'a>(edges: &'a [Edge], a: &Node, b: &Node) -> (&'a Edge, bool) {
+    edges
+        .iter()
+        .filter(|e| e.followed)
+        .find_map(|e| {
+            if &e.from == a && &e.to == b {
+                Some((e, true))
+            } else if &e.to == a && &e.from == b {
+                Some((e, false))
+            } else {
+                None
+            }
+        })
+        .expect("consecutive nodes of a route are linked by an edge")
+}
+
+/// With the `tracing` feature, wraps the `traversal` from `from` to
+/// `to` in a span recording the sizes of the sets.
+fn traced(from: &Node, to: &Node, hops: usize, traversal: quote::Tokens) -> quote::Tokens {
+    if !cfg!(feature = "tracing") {
+        return traversal;
+    }
+    let from_name = type_name(from);
+    let to_name = type_name(to);
+    quote! {
+        let span = ::relational_types::tracing::debug_span!(
+            "get_corresponding",
+            from = #from_name,
+            to = #to_name,
+            seed_len = self.len(),
+            hops = #hops,
+            result_len = ::relational_types::tracing::field::Empty,
+        );
+        let _entered = span.enter();
+        let result: ::relational_types::IdxSet<#to> = { #traversal };
+        span.record("result_len", &result.len());
+        result
+    }
+}
+
+fn type_name(ty: &Node) -> String {
+    quote!(#ty).to_string().replace(' ', "")
+}
+
+fn floyd_warshall(edges: &[Edge]) -> (Next, Dist) {
     use std::f64::INFINITY;
-    let mut v = HashSet::<&Node>::default();
+    // the nodes are visited in the order of declaration, so that the
+    // paths chosen among paths of the same cost don't change from one
+    // compilation to another, see `break_ties_by_names` to make them
+    // independent of the order of the fields
+    let v = nodes(edges);
     let mut dist = HashMap::<(&Node, &Node), f64>::default();
     let mut next = HashMap::default();
-    for e in edges {
+    for e in edges.iter().filter(|e| e.followed) {
         let from = &e.from;
         let to = &e.to;
-        v.insert(from);
-        v.insert(to);
         dist.insert((from, to), e.weight);
         dist.insert((to, from), e.weight);
         next.insert((from, to), to);
@@ -198,14 +1262,83 @@ fn floyd_warshall(edges: &[Edge]) -> HashMap<(&Node, &Node), &Node> {
             }
         }
     }
-    next
+    (next, dist)
+}
+
+/// Chooses, among the paths of the same cost, the one whose list of
+/// relation names is the first in lexicographic order, for the
+/// `tie_break = "field_names"` attribute, so that reordering the
+/// fields doesn't change the paths.
+///
+/// The names of the relations leaving a node being distinct, this
+/// path follows, from each node, the relation of smallest name
+/// leading to a path of minimal cost, hence the paths are still
+/// given by `next`.
+fn break_ties_by_names<'a>(edges: &'a [Edge], dist: &Dist<'a>, next: &mut Next<'a>) {
+    let pairs: Vec<_> = next
+        .keys()
+        .cloned()
+        .filter(|&(from, to)| from != to)
+        .collect();
+    for (from, to) in pairs {
+        let total = dist[&(from, to)];
+        let remaining = |node: &'a Node| {
+            if node == to {
+                Some(0.)
+            } else {
+                dist.get(&(node, to)).cloned()
+            }
+        };
+        let chosen = edges
+            .iter()
+            .filter(|e| e.followed)
+            .filter_map(|e| {
+                let neighbour = if &e.from == from {
+                    &e.to
+                } else if &e.to == from {
+                    &e.from
+                } else {
+                    return None;
+                };
+                let cost = e.weight + remaining(neighbour)?;
+                if neighbour != from && (cost - total).abs() <= 1e-9 * total.max(1.) {
+                    Some((e.ident.as_str(), neighbour))
+                } else {
+                    None
+                }
+            })
+            .min_by_key(|&(ident, _)| ident);
+        if let Some((_, neighbour)) = chosen {
+            next.insert((from, to), neighbour);
+        }
+    }
 }
 
 struct Edge {
     ident: String,
+    kind: String,
     from: Node,
     to: Node,
     weight: f64,
+    delegated: bool,
+    /// Whether the edge is the one followed among the parallel edges
+    /// linking its 2 nodes, see `choose_followed_edges`.
+    followed: bool,
+}
+
+impl Edge {
+    /// Returns the expression borrowing the relation of the edge
+    /// from the given `model`.
+    fn relation_ref(&self, model: quote::Tokens) -> quote::Tokens {
+        let ident: quote::Ident = self.ident.as_str().into();
+        if self.delegated {
+            quote!((&*#model.#ident))
+        } else {
+            quote!((&#model.#ident))
+        }
+    }
 }
 
 type Node = syn::Ty;
+type Next<'a> = HashMap<(&'a Node, &'a Node), &'a Node>;
+type Dist<'a> = HashMap<(&'a Node, &'a Node), f64>;
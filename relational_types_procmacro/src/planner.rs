@@ -0,0 +1,123 @@
+use crate::{route, traversed_edge, Edge, Next, Node};
+use quote::*;
+
+/// Generates the `get_corresponding_planned` method, choosing at
+/// runtime among several candidate paths the one going through the
+/// smallest relations.
+pub fn make_planner_impl(name: &syn::Ident, edges: &[Edge], next: &Next) -> quote::Tokens {
+    let impls = next.keys().map(|&(from, to)| {
+        if from == to {
+            return quote! {
+                impl GetCorrespondingPlanned<#to> for ::relational_types::IdxSet<#from> {
+                    fn get_corresponding_planned(&self, _: &#name) -> ::relational_types::IdxSet<#to> {
+                        self.clone()
+                    }
+                }
+            };
+        }
+        let candidates = candidate_routes(edges, next, from, to);
+        let traversals: Vec<_> = candidates
+            .iter()
+            .map(|route| traversal(edges, route))
+            .collect();
+        let body = if traversals.len() == 1 {
+            traversals[0].clone()
+        } else {
+            let costs = candidates.iter().map(|route| cost(edges, route));
+            let indices = 0..traversals.len();
+            quote! {
+                let costs = [#(#costs),*];
+                let best = (0..costs.len()).min_by_key(|&i| costs[i]).unwrap_or(0);
+                match best {
+                    #(#indices => #traversals,)*
+                    _ => unreachable!(),
+                }
+            }
+        };
+        quote! {
+            impl GetCorrespondingPlanned<#to> for ::relational_types::IdxSet<#from> {
+                fn get_corresponding_planned(&self, model: &#name) -> ::relational_types::IdxSet<#to> {
+                    #body
+                }
+            }
+        }
+    });
+    quote! {
+        /// A trait that returns a set of objects corresponding to a
+        /// given type, choosing the path at runtime.
+        pub trait GetCorrespondingPlanned<T: Sized> {
+            /// For the given self, returns the set of corresponding `T`
+            /// indices.
+            fn get_corresponding_planned(&self, model: &#name) -> ::relational_types::IdxSet<T>;
+        }
+        impl #name {
+            /// Returns the set of `U` indices corresponding to the `from`
+            /// set, following among the candidate paths the one going
+            /// through the smallest relations, according to their
+            /// `len_hint`.
+            pub fn get_corresponding_planned<T, U>(&self, from: &::relational_types::IdxSet<T>) -> ::relational_types::IdxSet<U>
+            where
+                ::relational_types::IdxSet<T>: GetCorrespondingPlanned<U>
+            {
+                from.get_corresponding_planned(self)
+            }
+        }
+        #(#impls)*
+    }
+}
+
+/// Returns the route chosen by the weights, followed by the simple
+/// routes starting by another neighbour of `from`.
+fn candidate_routes<'a>(
+    edges: &'a [Edge],
+    next: &Next<'a>,
+    from: &'a Node,
+    to: &'a Node,
+) -> Vec<Vec<&'a Node>> {
+    let mut candidates = vec![route(next, from, to)];
+    for e in edges {
+        let neighbour = if &e.from == from {
+            &e.to
+        } else if &e.to == from {
+            &e.from
+        } else {
+            continue;
+        };
+        let candidate = if neighbour == to {
+            vec![from, to]
+        } else if next.contains_key(&(neighbour, to)) {
+            let mut candidate = vec![from];
+            candidate.extend(route(next, neighbour, to));
+            candidate
+        } else {
+            continue;
+        };
+        if !candidate[1..].contains(&from) && !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+fn traversal(edges: &[Edge], route: &[&Node]) -> quote::Tokens {
+    let mut set = quote!(self);
+    for pair in route.windows(2) {
+        let (edge, forward) = traversed_edge(edges, pair[0], pair[1]);
+        let relation = edge.relation_ref(quote!(model));
+        set = if forward {
+            quote!(::relational_types::Relation::get_corresponding_forward(#relation, &#set))
+        } else {
+            quote!(::relational_types::Relation::get_corresponding_backward(#relation, &#set))
+        };
+    }
+    set
+}
+
+fn cost(edges: &[Edge], route: &[&Node]) -> quote::Tokens {
+    let len_hints = route.windows(2).map(|pair| {
+        let (edge, _) = traversed_edge(edges, pair[0], pair[1]);
+        let relation = edge.relation_ref(quote!(model));
+        quote!(::relational_types::Relation::len_hint(#relation))
+    });
+    quote!(0 #(+ #len_hints)*)
+}
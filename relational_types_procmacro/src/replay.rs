@@ -0,0 +1,39 @@
+use crate::Edge;
+use quote::*;
+
+/// Generates the implementation of `ReplayLog`, changing the
+/// relations which are not delegated and whose type is known to be
+/// mutable.
+pub fn make_replay_impl(name: &syn::Ident, edges: &[Edge]) -> quote::Tokens {
+    let arms = edges
+        .iter()
+        .filter(|e| !e.delegated && (e.kind == "OneToMany" || e.kind == "ManyToMany"))
+        .map(|e| {
+            let ident_name = e.ident.as_str();
+            let ident: quote::Ident = e.ident.as_str().into();
+            let (from, to) = (&e.from, &e.to);
+            quote! {
+                #ident_name => {
+                    let link = change.link::<#from, #to>().ok_or_else(unloggable)?;
+                    match change.op() {
+                        ::relational_types::LinkOp::Added => self.#ident.add_link(link),
+                        ::relational_types::LinkOp::Removed => {
+                            self.#ident.remove_link(link);
+                        }
+                    }
+                    Ok(())
+                }
+            }
+        });
+    quote! {
+        impl ::relational_types::ReplayLog for #name {
+            fn apply_change(&mut self, change: &::relational_types::LoggedChange) -> std::result::Result<(), ::relational_types::Error> {
+                let unloggable = || ::relational_types::Error::UnloggableRelation(change.relation().to_owned());
+                match change.relation() {
+                    #(#arms)*
+                    _ => Err(unloggable()),
+                }
+            }
+        }
+    }
+}
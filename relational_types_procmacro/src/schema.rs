@@ -0,0 +1,60 @@
+use crate::{nodes, route, traversed_edge, type_name, Edge, Next};
+use quote::*;
+
+/// Generates the `schema` method, describing the types, the
+/// relations and the paths of the model.
+pub fn make_schema_impl(name: &syn::Ident, edges: &[Edge], next: &Next) -> quote::Tokens {
+    let types = nodes(edges).into_iter().map(type_name);
+    let relations = edges.iter().map(|e| {
+        let ident_name = e.ident.as_str();
+        let kind = e.kind.as_str();
+        let from = type_name(&e.from);
+        let to = type_name(&e.to);
+        let weight = e.weight;
+        quote! {
+            ::relational_types::RelationSchema {
+                name: #ident_name,
+                kind: #kind,
+                from: #from,
+                to: #to,
+                weight: #weight,
+            }
+        }
+    });
+    let mut pairs: Vec<_> = next.keys().filter(|&&(from, to)| from != to).collect();
+    pairs.sort_by_key(|&&(from, to)| (type_name(from), type_name(to)));
+    let queries = pairs.into_iter().map(|&(from, to)| {
+        let hops = route(next, from, to).windows(2).map(|pair| {
+            let (edge, forward) = traversed_edge(edges, pair[0], pair[1]);
+            let relation = edge.ident.as_str();
+            quote! {
+                ::relational_types::HopSchema {
+                    relation: #relation,
+                    forward: #forward,
+                }
+            }
+        });
+        let from = type_name(from);
+        let to = type_name(to);
+        quote! {
+            ::relational_types::QuerySchema {
+                from: #from,
+                to: #to,
+                hops: vec![#(#hops),*],
+            }
+        }
+    });
+    quote! {
+        impl #name {
+            /// Returns the description of the types, the relations and
+            /// the paths of the model.
+            pub fn schema() -> ::relational_types::WorldSchema {
+                ::relational_types::WorldSchema {
+                    types: vec![#(#types),*],
+                    relations: vec![#(#relations),*],
+                    queries: vec![#(#queries),*],
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{type_name, Node};
+use quote::*;
+
+/// Generates, for the `generate_tests` attribute, a test module
+/// checking the traversals between the given pairs on an empty
+/// model, and the consistency of the paths described by the schema.
+///
+/// The empty model is built with `Default`, which the model must
+/// implement, the error naming this requirement otherwise.
+pub fn make_tests_impl(name: &syn::Ident, pairs: &[(&Node, &Node)]) -> quote::Tokens {
+    let module: quote::Ident = format!("{}_get_corresponding_tests", name.as_ref().to_lowercase())
+        .as_str()
+        .into();
+    let mut pairs = pairs.to_vec();
+    pairs.sort_by_key(|&(from, to)| (type_name(from), type_name(to)));
+    let traversals = pairs.iter().map(|&(from, to)| {
+        let message = format!("{} -> {}", type_name(from), type_name(to));
+        quote! {
+            let to: ::relational_types::IdxSet<#to> = model.get_corresponding(&::relational_types::IdxSet::<#from>::default());
+            assert!(to.is_empty(), "{} on an empty model", #message);
+            assert!(#name::path_cost::<#from, #to>().is_some(), "no path for {}", #message);
+        }
+    });
+    quote! {
+        #[cfg(test)]
+        mod #module {
+            use super::*;
+
+            fn generate_tests_requires_the_model_to_implement_default<T: Default>() -> T {
+                T::default()
+            }
+
+            #[test]
+            fn traversals_on_empty_model() {
+                let model: #name = generate_tests_requires_the_model_to_implement_default();
+                #(#traversals)*
+            }
+
+            #[test]
+            fn consistent_paths() {
+                let schema = #name::schema();
+                for query in &schema.queries {
+                    let mut current = query.from;
+                    for hop in &query.hops {
+                        let relation = schema
+                            .relations
+                            .iter()
+                            .find(|relation| relation.name == hop.relation)
+                            .expect("the hops follow relations of the model");
+                        let (from, to) = if hop.forward {
+                            (relation.from, relation.to)
+                        } else {
+                            (relation.to, relation.from)
+                        };
+                        assert_eq!(current, from, "{} -> {} is not a path", query.from, query.to);
+                        current = to;
+                    }
+                    assert_eq!(query.to, current, "{} -> {} is not a path", query.from, query.to);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{traversed_edge, type_name, Dist, Edge, Node};
+use quote::*;
+
+/// Returns every route of minimal cost from `from` to `to`, for the
+/// `ambiguous = "union"` attribute.
+pub fn shortest_routes<'a>(
+    edges: &'a [Edge],
+    dist: &Dist<'a>,
+    from: &'a Node,
+    to: &'a Node,
+) -> Vec<Vec<&'a Node>> {
+    let total = match dist.get(&(from, to)) {
+        Some(&total) => total,
+        None => return Vec::new(),
+    };
+    let remaining = |node: &'a Node| {
+        if node == to {
+            Some(0.)
+        } else {
+            dist.get(&(node, to)).cloned()
+        }
+    };
+    let mut routes = Vec::new();
+    let mut stack = vec![(vec![from], 0.)];
+    while let Some((route, cost)) = stack.pop() {
+        let current = route[route.len() - 1];
+        if current == to {
+            if !routes.contains(&route) {
+                routes.push(route);
+            }
+            continue;
+        }
+        for e in edges {
+            let neighbour = if &e.from == current {
+                &e.to
+            } else if &e.to == current {
+                &e.from
+            } else {
+                continue;
+            };
+            if route.contains(&neighbour) {
+                continue;
+            }
+            // the edge followed between 2 nodes is the one weighting
+            // them in the path table
+            let cost = cost + traversed_edge(edges, current, neighbour).0.weight;
+            match remaining(neighbour) {
+                Some(remaining) if (cost + remaining - total).abs() <= 1e-9 * total.max(1.) => {
+                    let mut route = route.clone();
+                    route.push(neighbour);
+                    stack.push((route, cost));
+                }
+                _ => {}
+            }
+        }
+    }
+    routes.sort_by_key(|route| {
+        route
+            .iter()
+            .map(|&node| type_name(node))
+            .collect::<Vec<_>>()
+    });
+    routes
+}
+
+/// Returns the union of the traversals of the `routes`. With `within`,
+/// the last step of each route is restricted to the `allowed` set.
+pub fn union_traversal(edges: &[Edge], routes: &[Vec<&Node>], within: bool) -> quote::Tokens {
+    let traversals = routes.iter().map(|route| {
+        let mut set = quote!(self);
+        let last = route.len() - 2;
+        for (i, pair) in route.windows(2).enumerate() {
+            let (edge, forward) = traversed_edge(edges, pair[0], pair[1]);
+            let relation = edge.relation_ref(quote!(pt_objects));
+            set = match (forward, within && i == last) {
+                (true, false) => {
+                    quote!(::relational_types::Relation::get_corresponding_forward(#relation, &#set))
+                }
+                (false, false) => {
+                    quote!(::relational_types::Relation::get_corresponding_backward(#relation, &#set))
+                }
+                (true, true) => quote!(
+                    ::relational_types::Relation::get_corresponding_forward_within(#relation, &#set, allowed)
+                ),
+                (false, true) => quote!(
+                    ::relational_types::Relation::get_corresponding_backward_within(#relation, &#set, allowed)
+                ),
+            };
+        }
+        set
+    });
+    quote! {
+        let mut result = ::relational_types::IdxSet::default();
+        #(result.extend(#traversals);)*
+        result
+    }
+}
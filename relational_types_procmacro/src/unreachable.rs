@@ -0,0 +1,94 @@
+use crate::{nodes, Edge};
+use quote::*;
+
+/// Generates the `unreachable_from` method, exploring the relations
+/// from a set of roots, through every path, whatever its cost, and
+/// returning the objects left unexplored.
+pub fn make_unreachable_impl(name: &syn::Ident, edges: &[Edge]) -> quote::Tokens {
+    let nodes = nodes(edges);
+    let var =
+        |prefix: &str, i: usize| -> quote::Ident { format!("{}_{}", prefix, i).as_str().into() };
+    let position = |node| nodes.iter().position(|&n| n == node).expect("known node");
+    let inits = nodes.iter().enumerate().map(|(i, node)| {
+        let (reached, frontier, next) = (var("reached", i), var("frontier", i), var("next", i));
+        quote! {
+            let mut #reached: ::relational_types::IdxSet<#node> = roots
+                .downcast_ref::<::relational_types::IdxSet<#node>>()
+                .cloned()
+                .unwrap_or_default();
+            let mut #frontier = #reached.clone();
+            let mut #next = ::relational_types::IdxSet::default();
+        }
+    });
+    let explorations = edges.iter().map(|e| {
+        let relation = e.relation_ref(quote!(self));
+        let (f, t) = (position(&e.from), position(&e.to));
+        let (reached_from, frontier_from, next_from) =
+            (var("reached", f), var("frontier", f), var("next", f));
+        let (reached_to, frontier_to, next_to) =
+            (var("reached", t), var("frontier", t), var("next", t));
+        quote! {
+            for idx in ::relational_types::Relation::get_corresponding_forward(#relation, &#frontier_from) {
+                if #reached_to.insert(idx) {
+                    #next_to.insert(idx);
+                    explored = true;
+                }
+            }
+            for idx in ::relational_types::Relation::get_corresponding_backward(#relation, &#frontier_to) {
+                if #reached_from.insert(idx) {
+                    #next_from.insert(idx);
+                    explored = true;
+                }
+            }
+        }
+    });
+    let swaps = (0..nodes.len()).map(|i| {
+        let (frontier, next) = (var("frontier", i), var("next", i));
+        quote!(#frontier = std::mem::take(&mut #next);)
+    });
+    let objects = nodes.iter().enumerate().map(|(i, node)| {
+        let all = var("all", i);
+        quote!(let mut #all: Vec<::relational_types::typed_index_collection::Idx<#node>> = Vec::new();)
+    });
+    let collects = edges.iter().map(|e| {
+        let relation = e.relation_ref(quote!(self));
+        let all_from = var("all", position(&e.from));
+        let all_to = var("all", position(&e.to));
+        quote! {
+            #all_from.extend(::relational_types::Relation::iter_from(#relation));
+            #all_to.extend(::relational_types::Relation::iter_to(#relation));
+        }
+    });
+    let types = (0..nodes.len()).map(|i| {
+        let (all, reached) = (var("all", i), var("reached", i));
+        quote!(::relational_types::analysis::UnreachableObjects::new(#all.into_iter(), &#reached))
+    });
+    quote! {
+        impl #name {
+            /// Returns, for each type, the objects of the relations not
+            /// reachable from `roots` through any path, e.g. to remove
+            /// them from the collections after filtering the roots.
+            #[allow(unused_variables, unused_mut)]
+            pub fn unreachable_from<Root: 'static>(
+                &self,
+                roots: &::relational_types::IdxSet<Root>,
+            ) -> ::relational_types::analysis::UnreachableReport {
+                let roots: &dyn std::any::Any = roots;
+                #(#inits)*
+                // the objects found at an iteration are explored at the
+                // next one
+                let mut explored = true;
+                while explored {
+                    explored = false;
+                    #(#explorations)*
+                    #(#swaps)*
+                }
+                #(#objects)*
+                #(#collects)*
+                ::relational_types::analysis::UnreachableReport {
+                    types: vec![#(#types),*],
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use relational_types::*;
+use typed_index_collection::*;
+
+pub struct Animal {
+    pub id: String,
+}
+impl_id!(Animal);
+
+pub struct Feline {
+    pub id: String,
+}
+impl_id!(Feline);
+
+pub struct Cat {
+    pub id: String,
+}
+impl_id!(Cat);
+
+#[derive(Default, GetCorresponding)]
+#[get_corresponding(generate_tests)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+}
@@ -61,4 +61,9 @@ fn main() {
 
     let animal_indexes = model.get_corresponding_from_idx(cat_1_idx);
     assert!(animal_indexes.contains(&animal_idx));
+
+    let allowed: IdxSet<Cat> = vec![cat_2_idx, cat_4_idx].into_iter().collect();
+    let animal_indexes: IdxSet<Animal> = Some(animal_idx).into_iter().collect();
+    let cat_indexes = model.get_corresponding_within(&animal_indexes, &allowed);
+    assert_eq!(allowed, cat_indexes);
 }
@@ -0,0 +1,197 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+}
+
+fn empty_model() -> Model {
+    Model {
+        animals_to_felines: OneToMany::default(),
+        felines_to_cats: OneToMany::default(),
+    }
+}
+
+static EMPTY_MODEL: LazyWorld<Model> = Model::lazy(empty_model);
+
+fn main() {
+    let animal = Animal {
+        id: String::from("animal_id"),
+    };
+    let feline1 = Feline {
+        id: String::from("feline_id_1"),
+        animal_id: String::from("animal_id"),
+    };
+    let feline2 = Feline {
+        id: String::from("feline_id_2"),
+        animal_id: String::from("animal_id"),
+    };
+    let cat = Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id_1"),
+    };
+    let animals = CollectionWithId::from(animal);
+    let felines = CollectionWithId::new(vec![feline1, feline2]).unwrap();
+    let cats = CollectionWithId::from(cat);
+    let mut model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: OneToMany::new(&felines, &cats, "felines_to_cats").unwrap(),
+    };
+
+    let histogram = analysis::degree_histogram(&model.animals_to_felines);
+    assert_eq!(Some(&1), histogram.get(&2));
+    assert_eq!(1, histogram.len());
+
+    let report = model.relation_report();
+    assert_eq!(2, report.relations.len());
+    let animals_to_felines = &report.relations[0];
+    assert_eq!("animals_to_felines", animals_to_felines.name);
+    assert_eq!(2, animals_to_felines.links);
+    assert_eq!(0, animals_to_felines.empty_parents);
+    assert_eq!(2, animals_to_felines.max_forward_degree);
+    assert_eq!(1, animals_to_felines.max_backward_degree);
+    let felines_to_cats = &report.relations[1];
+    assert_eq!("felines_to_cats", felines_to_cats.name);
+    assert_eq!(1, felines_to_cats.links);
+    // `feline_id_2` has no cat
+    assert_eq!(1, felines_to_cats.empty_parents);
+    assert_eq!(1, felines_to_cats.max_forward_degree);
+
+    let stats = model.stats();
+    assert_eq!(100., stats.relations[0].from_coverage);
+    assert_eq!(50., stats.relations[1].from_coverage);
+    assert_eq!(100., stats.relations[1].to_coverage);
+    assert_eq!(6, stats.paths.len());
+    let animal_to_cat = stats
+        .paths
+        .iter()
+        .find(|path| path.from == "Animal" && path.to == "Cat")
+        .unwrap();
+    assert_eq!(vec!["Animal", "Feline", "Cat"], animal_to_cat.route);
+
+    assert_eq!(Some(2.), Model::path_cost::<Animal, Cat>());
+    assert_eq!(Some(1.), Model::path_cost::<Cat, Feline>());
+    assert_eq!(Some(0.), Model::path_cost::<Cat, Cat>());
+    assert_eq!(None, Model::path_cost::<Animal, String>());
+
+    assert_eq!(
+        vec!["animals_to_felines", "felines_to_cats"],
+        model.relation_names()
+    );
+    assert_eq!("Feline", model.relations()[1].from);
+    assert_eq!(Some(vec![(0, 0)]), model.links_of("felines_to_cats"));
+    assert_eq!(None, model.links_of("cats_to_felines"));
+    let mut links = Vec::new();
+    assert!(model.for_each_link_of("felines_to_cats", &mut |from, to| links.push((from, to))));
+    assert_eq!(vec![(0, 0)], links);
+    assert!(!model.for_each_link_of("cats_to_felines", &mut |_, _| panic!("no link")));
+
+    let relation = model.relation_between::<Cat, Feline>().unwrap();
+    assert!(relation.source_type_name().ends_with("Feline"));
+    assert_eq!(vec![(0, 0)], relation.raw_links());
+    assert!(relation
+        .as_any()
+        .downcast_ref::<OneToMany<Feline, Cat>>()
+        .is_some());
+    assert!(model.relation_between::<Animal, Cat>().is_none());
+    assert_eq!(
+        Some("animals_to_felines"),
+        Model::relation_field_name::<Animal, Feline>()
+    );
+    assert_eq!(None, Model::relation_field_name::<Cat, Cat>());
+
+    let feline2_idx = felines.get_idx("feline_id_2").unwrap();
+    let cats_of_feline2: IdxSet<Cat> = model.try_get_corresponding_from_idx(feline2_idx).unwrap();
+    assert!(cats_of_feline2.is_empty());
+    let other_cats = CollectionWithId::new(vec![
+        Cat {
+            id: String::from("cat_id"),
+            feline_id: String::from("feline_id_1"),
+        },
+        Cat {
+            id: String::from("other_cat_id"),
+            feline_id: String::from("feline_id_2"),
+        },
+    ])
+    .unwrap();
+    let other_cat_idx = other_cats.get_idx("other_cat_id").unwrap();
+    assert!(!model.is_linked(other_cat_idx));
+    let error = model
+        .try_get_corresponding_from_idx::<Cat, Animal>(other_cat_idx)
+        .unwrap_err();
+    assert_eq!(1, error.index);
+    assert!(error.type_name.ends_with("Cat"));
+    let cats: IdxSet<Cat> = Some(other_cat_idx).into_iter().collect();
+    let ignored: IdxSet<Animal> = model
+        .get_corresponding_with(&cats, MissingIndex::Ignore)
+        .unwrap();
+    assert!(ignored.is_empty());
+    assert!(model
+        .get_corresponding_with::<Cat, Animal>(&cats, MissingIndex::Error)
+        .is_err());
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let fallback = |_: Idx<Cat>| Some(animal_idx).into_iter().collect();
+    let resolved: IdxSet<Animal> = model
+        .get_corresponding_with(&cats, MissingIndex::Fallback(&fallback))
+        .unwrap();
+    assert!(resolved.contains(&animal_idx));
+
+    let schema = Model::schema();
+    assert_eq!(vec!["Animal", "Feline", "Cat"], schema.types);
+    assert_eq!("OneToMany", schema.relations[1].kind);
+    assert_eq!("Cat", schema.relations[1].to);
+    assert_eq!(1., schema.relations[1].weight);
+    assert_eq!(6, schema.queries.len());
+    let cat_to_animal = schema
+        .queries
+        .iter()
+        .find(|query| query.from == "Cat" && query.to == "Animal")
+        .unwrap();
+    assert_eq!(
+        vec![
+            HopSchema {
+                relation: "felines_to_cats",
+                forward: false,
+            },
+            HopSchema {
+                relation: "animals_to_felines",
+                forward: false,
+            },
+        ],
+        cat_to_animal.hops
+    );
+
+    assert!(!EMPTY_MODEL.is_initialized());
+    assert_eq!(None, EMPTY_MODEL.links_of("cats_to_felines"));
+    assert_eq!(Some(Vec::new()), EMPTY_MODEL.links_of("felines_to_cats"));
+    assert_eq!(
+        vec!["animals_to_felines", "felines_to_cats"],
+        EMPTY_MODEL.empty_relations()
+    );
+    assert!(model.empty_relations().is_empty());
+    assert!(EMPTY_MODEL.is_initialized());
+
+    model.shrink_all();
+    assert_eq!(report, model.relation_report());
+
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let world = InstrumentedWorld::new(model);
+    let cats: IdxSet<Cat> = world.get_corresponding_from_idx(animal_idx);
+    assert_eq!(1, cats.len());
+    let _: IdxSet<Cat> = world.get_corresponding_from_idx(animal_idx);
+    let metrics = world.metrics_snapshot();
+    assert_eq!(1, metrics.len());
+    assert!(metrics[0].from.ends_with("Animal"));
+    assert!(metrics[0].to.ends_with("Cat"));
+    assert_eq!(2, metrics[0].calls);
+    assert_eq!(2, metrics[0].seed_len);
+    assert_eq!(2, metrics[0].result_len);
+    assert_eq!(2, world.relation_report().relations.len());
+    world.reset_metrics();
+    assert!(world.metrics_snapshot().is_empty());
+}
@@ -0,0 +1,145 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+pub struct Collections {
+    animals: CollectionWithId<Animal>,
+    felines: CollectionWithId<Feline>,
+    cats: CollectionWithId<Cat>,
+}
+
+#[derive(GetCorresponding, BuildRelations)]
+#[build_relations(collections = "Collections", parallel)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+    #[build_relations(chain = "animals_to_felines, felines_to_cats")]
+    animals_to_cats: ManyToMany<Animal, Cat>,
+}
+
+fn main() {
+    let animal = Animal {
+        id: String::from("animal_id"),
+    };
+    let feline = Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    };
+    let cat = Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id"),
+    };
+    let collections = Collections {
+        animals: CollectionWithId::from(animal),
+        felines: CollectionWithId::from(feline),
+        cats: CollectionWithId::from(cat),
+    };
+    let mut model = Model::try_build(&collections).unwrap();
+
+    let animal_idx = collections.animals.get_idx("animal_id").unwrap();
+    let cat_idx = collections.cats.get_idx("cat_id").unwrap();
+    assert_eq!(1, model.animals_to_felines.iter_to().count());
+    assert_eq!(1, model.felines_to_cats.iter_to().count());
+    let cats = model
+        .animals_to_cats
+        .get_corresponding_forward(&Some(animal_idx).into_iter().collect());
+    assert!(cats.contains(&cat_idx));
+
+    assert!(model.verify_shortcuts().is_empty());
+    let feline_idx = collections.felines.get_idx("feline_id").unwrap();
+    model.apply_felines_to_cats_delta(Delta {
+        added: Vec::new(),
+        removed: vec![Link(feline_idx, cat_idx)],
+    });
+    assert_eq!(0, model.animals_to_cats.iter_to().count());
+    assert!(model.verify_shortcuts().is_empty());
+    model.apply_felines_to_cats_delta(Delta {
+        added: vec![Link(feline_idx, cat_idx)],
+        removed: Vec::new(),
+    });
+    assert_eq!(1, model.animals_to_cats.iter_to().count());
+    assert!(model.verify_shortcuts().is_empty());
+    let invariants = [
+        invariants::Invariant::each_target_has_source::<Cat, Feline>(),
+        invariants::Invariant::at_most_one_source::<Cat, Animal>(),
+        invariants::Invariant::acyclic::<Cat>(),
+    ];
+    assert!(model.check_invariants(&invariants).is_empty());
+
+    model.felines_to_cats.remove_link(Link(feline_idx, cat_idx));
+    let divergences = model.verify_shortcuts();
+    assert_eq!(1, divergences.len());
+    assert_eq!("animals_to_cats", divergences[0].name);
+    assert_eq!(0, divergences[0].missing_links);
+    assert_eq!(1, divergences[0].unexpected_links);
+    let report = model.check_invariants(&invariants);
+    assert_eq!(1, report.violations.len());
+    assert_eq!(invariants[0], report.violations[0].invariant);
+    assert_eq!(vec![0], report.violations[0].objects);
+
+    let gaps = model.coverage_gaps(&collections);
+    assert_eq!(1, gaps.len());
+    assert_eq!("felines_to_cats", gaps[0].name);
+    assert_eq!(vec![String::from("cat_id")], gaps[0].missing_ids);
+    assert!(gaps[0].out_of_bounds.is_empty());
+    model.felines_to_cats.add_link(Link(feline_idx, cat_idx));
+    model.assert_covers(&collections);
+
+    let expected = Model::try_build(&collections).unwrap();
+    assert_relations_eq!(expected, model, collections);
+    let parallel = Model::try_build_parallel(&collections).unwrap();
+    assert_relations_eq!(expected, parallel, collections);
+    model.felines_to_cats.remove_link(Link(feline_idx, cat_idx));
+    let diffs = analysis::relation_diffs(&expected, &model);
+    assert_eq!(1, diffs.len());
+    assert_eq!("felines_to_cats", diffs[0].name);
+    assert_eq!(vec![(0, 0)], diffs[0].missing_links);
+    assert!(diffs[0].unexpected_links.is_empty());
+    assert_eq!(
+        "relation felines_to_cats: 1 missing links, 0 unexpected links\n- (feline_id, cat_id)",
+        diffs[0].describe(Some(&collections))
+    );
+    assert_eq!(
+        "digraph \"felines_to_cats\" {\n    source_0 [label=\"feline_id\"];\n    \
+         target_0 [label=\"cat_id\"];\n    source_0 -> target_0 [color=red];\n}",
+        diffs[0].to_dot(Some(&collections))
+    );
+    assert_eq!(
+        Some((String::from("animal_id"), String::from("cat_id"))),
+        collections.link_ids("animals_to_cats", (0, 0))
+    );
+    let change = collections
+        .link_change("felines_to_cats", ("feline_id", "cat_id"))
+        .unwrap();
+    assert_eq!((0, 0), change.raw_link());
+    assert!(collections
+        .link_change("felines_to_cats", ("feline_id", "unknown_id"))
+        .is_err());
+    assert!(collections
+        .link_change("cats_to_felines", ("cat_id", "feline_id"))
+        .is_err());
+
+    model
+        .rebuild_relation(&collections.felines, &collections.cats)
+        .unwrap();
+    assert_relations_eq!(expected, model, collections);
+    assert!(model
+        .rebuild_relation(&collections.cats, &collections.felines)
+        .is_err());
+
+    let collections = Collections {
+        animals: CollectionWithId::default(),
+        felines: collections.felines,
+        cats: collections.cats,
+    };
+    assert!(Model::try_build(&collections).is_err());
+    assert!(Model::try_build_parallel(&collections).is_err());
+    // the other relations are built anyway
+    let (model, report) = Model::build_partial(&collections);
+    assert_eq!(1, report.failures.len());
+    assert_eq!("animals_to_felines", report.failures[0].0);
+    assert_eq!(0, model.animals_to_felines.len_hint());
+    assert_eq!(1, model.felines_to_cats.len_hint());
+}
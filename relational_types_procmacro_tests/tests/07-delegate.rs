@@ -0,0 +1,51 @@
+mod test_utils;
+
+use relational_types::*;
+use std::ops::Deref;
+use test_utils::*;
+use typed_index_collection::*;
+
+pub struct FelinesToCats(OneToMany<Feline, Cat>);
+
+impl Deref for FelinesToCats {
+    type Target = OneToMany<Feline, Cat>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[derive(GetCorresponding)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    #[get_corresponding(delegate = "OneToMany<Feline, Cat>")]
+    felines_to_cats: FelinesToCats,
+}
+
+fn main() {
+    let animal = Animal {
+        id: String::from("animal_id"),
+    };
+    let feline = Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    };
+    let cat = Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id"),
+    };
+    let animals = CollectionWithId::from(animal);
+    let felines = CollectionWithId::from(feline);
+    let cats = CollectionWithId::from(cat);
+    let model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: FelinesToCats(OneToMany::new(&felines, &cats, "felines_to_cats").unwrap()),
+    };
+
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let cat_idx = cats.get_idx("cat_id").unwrap();
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(animal_idx);
+    assert!(cat_indexes.contains(&cat_idx));
+    let animal_indexes: IdxSet<Animal> = model.get_corresponding_from_idx(cat_idx);
+    assert!(animal_indexes.contains(&animal_idx));
+    assert_eq!(2, model.relation_report().relations.len());
+}
@@ -0,0 +1,75 @@
+mod test_utils;
+
+use relational_types::*;
+use std::collections::HashMap;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+pub struct Model {
+    felines_to_cats: OneToMany<Feline, Cat>,
+    cats_to_felines: ManyToOne<Cat, Feline>,
+}
+
+impl Model {
+    fn new(felines: &CollectionWithId<Feline>, cats: &CollectionWithId<Cat>) -> Self {
+        let felines_to_cats = OneToMany::new(felines, cats, "felines_to_cats").unwrap();
+        Model {
+            cats_to_felines: felines_to_cats.invert(),
+            felines_to_cats,
+        }
+    }
+}
+
+fn feline(id: &str) -> Feline {
+    Feline {
+        id: String::from(id),
+        animal_id: String::from("animal_id"),
+    }
+}
+
+fn cat(id: &str, feline_id: &str) -> Cat {
+    Cat {
+        id: String::from(id),
+        feline_id: String::from(feline_id),
+    }
+}
+
+fn main() {
+    let felines1 = CollectionWithId::from(feline("lion"));
+    let cats1 = CollectionWithId::from(cat("simba", "lion"));
+    let felines2 = CollectionWithId::from(feline("tiger"));
+    let cats2 = CollectionWithId::from(cat("shere_khan", "tiger"));
+    let model1 = Model::new(&felines1, &cats1);
+    let model2 = Model::new(&felines2, &cats2);
+
+    let felines = CollectionWithId::new(vec![feline("lion"), feline("tiger")]).unwrap();
+    let cats =
+        CollectionWithId::new(vec![cat("simba", "lion"), cat("shere_khan", "tiger")]).unwrap();
+    let feline_table: HashMap<_, _> = felines2
+        .iter()
+        .map(|(idx, obj)| (idx, felines.get_idx(&obj.id).unwrap()))
+        .collect();
+    let cat_table: HashMap<_, _> = cats2
+        .iter()
+        .map(|(idx, obj)| (idx, cats.get_idx(&obj.id).unwrap()))
+        .collect();
+    let mut remaps = IdxRemaps::default();
+    remaps.insert_table(feline_table).insert_table(cat_table);
+    let mut model = model1.merge(model2, &remaps);
+    model.shrink_all();
+
+    let tiger_idx = felines.get_idx("tiger").unwrap();
+    let shere_khan_idx = cats.get_idx("shere_khan").unwrap();
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(tiger_idx);
+    assert_eq!(Some(&shere_khan_idx), cat_indexes.iter().next());
+    assert_eq!(2, model.felines_to_cats.iter_from().count());
+    assert_eq!(2, model.felines_to_cats.iter_to().count());
+    assert_eq!(2, model.cats_to_felines.iter_from().count());
+    assert_eq!(
+        IdxSet::from([tiger_idx]),
+        model
+            .cats_to_felines
+            .get_corresponding_forward(&IdxSet::from([shere_khan_idx]))
+    );
+}
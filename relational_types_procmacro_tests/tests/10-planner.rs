@@ -0,0 +1,45 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+#[get_corresponding(planner)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+    #[get_corresponding(weight = "2.1")]
+    animals_to_cats: ManyToMany<Animal, Cat>,
+}
+
+fn main() {
+    let animal = Animal {
+        id: String::from("animal_id"),
+    };
+    let feline = Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    };
+    let cat = Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id"),
+    };
+    let animals = CollectionWithId::from(animal);
+    let felines = CollectionWithId::from(feline);
+    let cats = CollectionWithId::from(cat);
+    let model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: OneToMany::new(&felines, &cats, "felines_to_cats").unwrap(),
+        animals_to_cats: ManyToMany::default(),
+    };
+
+    let animal_indexes: IdxSet<Animal> = animals.iter().map(|(idx, _)| idx).collect();
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding(&animal_indexes);
+    assert_eq!(1, cat_indexes.len());
+    // the empty shortcut is the cheapest path
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_planned(&animal_indexes);
+    assert!(cat_indexes.is_empty());
+    let feline_indexes: IdxSet<Feline> = model.get_corresponding_planned(&animal_indexes);
+    assert_eq!(1, feline_indexes.len());
+}
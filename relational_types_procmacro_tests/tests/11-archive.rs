@@ -0,0 +1,55 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+}
+
+fn main() {
+    let animals = CollectionWithId::from(Animal {
+        id: String::from("animal_id"),
+    });
+    let felines = CollectionWithId::from(Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    });
+    let model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+    };
+
+    // version 1 of the model named the relation `animal_felines`
+    let mut archive = RelationsArchive::from_world(&model, 1);
+    archive.relations[0].name = String::from("animal_felines");
+    let mut bytes = Vec::new();
+    archive.write(&mut bytes).unwrap();
+
+    let mut migrations = Migrations::new(2);
+    migrations.migrate_from(1, |archive| {
+        for relation in &mut archive.relations {
+            relation.name = relation
+                .name
+                .replace("animal_felines", "animals_to_felines");
+        }
+        Ok(())
+    });
+    let archive = RelationsArchive::read(bytes.as_slice(), &migrations).unwrap();
+    assert_eq!(2, archive.version);
+    let relation = archive
+        .relation("animals_to_felines")
+        .unwrap()
+        .to_many_to_many(&animals, &felines)
+        .unwrap();
+    assert_eq!(1, relation.iter_to().count());
+
+    assert!(RelationsArchive::read(bytes.as_slice(), &Migrations::new(0)).is_err());
+    assert!(RelationsArchive::read(bytes.as_slice(), &Migrations::new(3)).is_err());
+    let corrupted = String::from_utf8(bytes).unwrap().replace("\n0 0", "\n0 1");
+    match RelationsArchive::read(corrupted.as_bytes(), &Migrations::new(1)) {
+        Err(Error::CorruptedArchive(name)) => assert_eq!("animal_felines", name),
+        _ => panic!("the corruption must be detected"),
+    }
+}
@@ -0,0 +1,41 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+#[get_corresponding(only = "Animal -> Cat, Cat -> Cat")]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+}
+
+fn main() {
+    let animals = CollectionWithId::from(Animal {
+        id: String::from("animal_id"),
+    });
+    let felines = CollectionWithId::from(Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    });
+    let cats = CollectionWithId::from(Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id"),
+    });
+    let model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: OneToMany::new(&felines, &cats, "felines_to_cats").unwrap(),
+    };
+
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let cat_idx = cats.get_idx("cat_id").unwrap();
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(animal_idx);
+    assert!(cat_indexes.contains(&cat_idx));
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(cat_idx);
+    assert!(cat_indexes.contains(&cat_idx));
+    // needed by the path from `Animal` to `Cat`
+    let feline_idx = felines.get_idx("feline_id").unwrap();
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(feline_idx);
+    assert!(cat_indexes.contains(&cat_idx));
+}
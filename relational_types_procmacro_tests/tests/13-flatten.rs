@@ -0,0 +1,42 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+#[get_corresponding(flatten, only = "Animal -> Cat, Cat -> Animal")]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+}
+
+fn main() {
+    let animals = CollectionWithId::from(Animal {
+        id: String::from("animal_id"),
+    });
+    let felines = CollectionWithId::from(Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    });
+    let cats = CollectionWithId::from(Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id"),
+    });
+    let model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: OneToMany::new(&felines, &cats, "felines_to_cats").unwrap(),
+    };
+
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let cat_idx = cats.get_idx("cat_id").unwrap();
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(animal_idx);
+    assert!(cat_indexes.contains(&cat_idx));
+    let animal_indexes: IdxSet<Animal> = model.get_corresponding_from_idx(cat_idx);
+    assert!(animal_indexes.contains(&animal_idx));
+    let cat_indexes = model.get_corresponding_within(
+        &Some(animal_idx).into_iter().collect(),
+        &IdxSet::<Cat>::default(),
+    );
+    assert!(cat_indexes.is_empty());
+}
@@ -0,0 +1,17 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+
+#[derive(Default, GetCorresponding)]
+#[get_corresponding(generate_tests)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+}
+
+fn main() {
+    let model = Model::default();
+    let cats: IdxSet<Cat> = model.get_corresponding(&IdxSet::<Animal>::default());
+    assert!(cats.is_empty());
+}
@@ -0,0 +1,12 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+
+#[derive(GetCorresponding)]
+#[get_corresponding(cost_fn = "crate::routing::cost")]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+}
+
+fn main() {}
@@ -0,0 +1,59 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+#[get_corresponding(ambiguous = "union")]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+    // as costly as the path through the felines
+    #[get_corresponding(weight = "2")]
+    animals_to_cats: ManyToMany<Animal, Cat>,
+}
+
+fn main() {
+    let animals = CollectionWithId::from(Animal {
+        id: String::from("animal_id"),
+    });
+    let felines = CollectionWithId::from(Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    });
+    let cats = CollectionWithId::new(vec![
+        Cat {
+            id: String::from("cat_id_1"),
+            feline_id: String::from("feline_id"),
+        },
+        Cat {
+            id: String::from("cat_id_2"),
+            feline_id: String::from("feline_id"),
+        },
+    ])
+    .unwrap();
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let feline_idx = felines.get_idx("feline_id").unwrap();
+    let cat_idx_1 = cats.get_idx("cat_id_1").unwrap();
+    let cat_idx_2 = cats.get_idx("cat_id_2").unwrap();
+    let mut model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: OneToMany::default(),
+        animals_to_cats: ManyToMany::default(),
+    };
+    model.felines_to_cats.add_link(Link(feline_idx, cat_idx_1));
+    model.animals_to_cats.add_link(Link(animal_idx, cat_idx_2));
+
+    // the results of both paths are returned
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(animal_idx);
+    assert_eq!(2, cat_indexes.len());
+    let animal_indexes: IdxSet<Animal> = model.get_corresponding_from_idx(cat_idx_2);
+    assert!(animal_indexes.contains(&animal_idx));
+
+    // both paths are restricted to the allowed cats
+    let from: IdxSet<Animal> = Some(animal_idx).into_iter().collect();
+    let allowed: IdxSet<Cat> = Some(cat_idx_2).into_iter().collect();
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_within(&from, &allowed);
+    assert_eq!(allowed, cat_indexes);
+}
@@ -0,0 +1,47 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+}
+
+fn main() {
+    let animals = CollectionWithId::from(Animal {
+        id: String::from("animal_id"),
+    });
+    let felines = CollectionWithId::from(Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    });
+    let cats = CollectionWithId::new(vec![
+        Cat {
+            id: String::from("cat_id_1"),
+            feline_id: String::from("feline_id"),
+        },
+        Cat {
+            id: String::from("cat_id_2"),
+            feline_id: String::from("feline_id"),
+        },
+    ])
+    .unwrap();
+    let model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: OneToMany::new(&felines, &cats, "felines_to_cats").unwrap(),
+    };
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let cat_idx = cats.get_idx("cat_id_2").unwrap();
+
+    let frozen: FrozenModel = model.freeze();
+    let cat_indexes: IdxSet<Cat> = frozen.get_corresponding_from_idx(animal_idx);
+    assert_eq!(cat_indexes, model.get_corresponding_from_idx(animal_idx));
+    assert_eq!(2, cat_indexes.len());
+    let animal_indexes: IdxSet<Animal> = frozen.get_corresponding_from_idx(cat_idx);
+    assert!(animal_indexes.contains(&animal_idx));
+    let same_cats: IdxSet<Cat> = frozen.get_corresponding(&cat_indexes);
+    assert_eq!(cat_indexes, same_cats);
+}
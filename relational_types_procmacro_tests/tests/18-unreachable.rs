@@ -0,0 +1,63 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+}
+
+fn main() {
+    let animals = CollectionWithId::new(vec![
+        Animal {
+            id: String::from("animal_id_1"),
+        },
+        Animal {
+            id: String::from("animal_id_2"),
+        },
+    ])
+    .unwrap();
+    let felines = CollectionWithId::new(vec![
+        Feline {
+            id: String::from("feline_id_1"),
+            animal_id: String::from("animal_id_1"),
+        },
+        Feline {
+            id: String::from("feline_id_2"),
+            animal_id: String::from("animal_id_2"),
+        },
+    ])
+    .unwrap();
+    let cats = CollectionWithId::new(vec![
+        Cat {
+            id: String::from("cat_id_1"),
+            feline_id: String::from("feline_id_1"),
+        },
+        Cat {
+            id: String::from("cat_id_2"),
+            feline_id: String::from("feline_id_2"),
+        },
+    ])
+    .unwrap();
+    let model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: OneToMany::new(&felines, &cats, "felines_to_cats").unwrap(),
+    };
+    let cat_idx_1 = cats.get_idx("cat_id_1").unwrap();
+    let cat_idx_2 = cats.get_idx("cat_id_2").unwrap();
+    let animal_idx_2 = animals.get_idx("animal_id_2").unwrap();
+
+    // the objects of the other branch are unreachable from a cat
+    let report = model.unreachable_from(&Some(cat_idx_1).into_iter().collect());
+    assert_eq!(3, report.types.len());
+    assert!(report.types.iter().all(|objects| objects.indices.len() == 1));
+    assert!(report.is_unreachable(cat_idx_2));
+    assert!(report.is_unreachable(animal_idx_2));
+    assert!(!report.is_unreachable(cat_idx_1));
+
+    let all_cats: IdxSet<Cat> = cats.iter().map(|(idx, _)| idx).collect();
+    assert!(model.unreachable_from(&all_cats).is_empty());
+}
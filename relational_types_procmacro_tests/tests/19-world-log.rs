@@ -0,0 +1,60 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(Default, GetCorresponding)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: ManyToMany<Feline, Cat>,
+}
+
+fn main() {
+    let animals = CollectionWithId::from(Animal {
+        id: String::from("animal_id"),
+    });
+    let felines = CollectionWithId::from(Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    });
+    let cats = CollectionWithId::from(Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id"),
+    });
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let feline_idx = felines.get_idx("feline_id").unwrap();
+    let cat_idx = cats.get_idx("cat_id").unwrap();
+
+    let mut model = Model::default();
+    let mut log = WorldLog::default();
+    log.apply(
+        &mut model,
+        "animals_to_felines",
+        LinkOp::Added,
+        Link(animal_idx, feline_idx),
+    )
+    .unwrap();
+    log.apply(&mut model, "felines_to_cats", LinkOp::Added, Link(feline_idx, cat_idx))
+        .unwrap();
+    log.apply(&mut model, "felines_to_cats", LinkOp::Removed, Link(feline_idx, cat_idx))
+        .unwrap();
+    // the failed changes are not recorded
+    assert!(log
+        .apply(&mut model, "felines_to_cats", LinkOp::Added, Link(animal_idx, cat_idx))
+        .is_err());
+    assert!(log
+        .apply(&mut model, "cats_to_felines", LinkOp::Added, Link(cat_idx, feline_idx))
+        .is_err());
+    assert_eq!(3, log.len());
+
+    let mut replayed = Model::default();
+    log.replay_onto(&mut replayed).unwrap();
+    assert_relations_eq!(model, replayed);
+
+    log.truncate(2);
+    let mut replayed = Model::default();
+    log.replay_onto(&mut replayed).unwrap();
+    let cat_indexes: IdxSet<Cat> = replayed.get_corresponding_from_idx(animal_idx);
+    assert!(cat_indexes.contains(&cat_idx));
+}
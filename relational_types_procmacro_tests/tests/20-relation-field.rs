@@ -0,0 +1,44 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+}
+
+fn main() {
+    let animals = CollectionWithId::from(Animal {
+        id: String::from("animal_id"),
+    });
+    let felines = CollectionWithId::from(Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    });
+    let cats = CollectionWithId::from(Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id"),
+    });
+    let model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: OneToMany::new(&felines, &cats, "felines_to_cats").unwrap(),
+    };
+
+    assert_eq!(
+        &[RelationField::AnimalsToFelines, RelationField::FelinesToCats],
+        RelationField::ALL
+    );
+    assert_eq!("felines_to_cats", RelationField::FelinesToCats.name());
+    assert_eq!(
+        Some(RelationField::AnimalsToFelines),
+        RelationField::from_name("animals_to_felines")
+    );
+    assert_eq!(None, RelationField::from_name("cats_to_felines"));
+
+    let relation = model.relation(RelationField::FelinesToCats);
+    assert_eq!(vec![(0, 0)], relation.raw_links());
+    assert!(relation.target_type_name().ends_with("Cat"));
+}
@@ -0,0 +1,59 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(Default, GetCorresponding)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: ManyToMany<Feline, Cat>,
+}
+
+fn main() {
+    let animals = CollectionWithId::from(Animal {
+        id: String::from("animal_id"),
+    });
+    let felines = CollectionWithId::new(vec![
+        Feline {
+            id: String::from("feline_id_1"),
+            animal_id: String::from("animal_id"),
+        },
+        Feline {
+            id: String::from("feline_id_2"),
+            animal_id: String::from("animal_id"),
+        },
+    ])
+    .unwrap();
+    let cats = CollectionWithId::from(Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id_2"),
+    });
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let feline1_idx = felines.get_idx("feline_id_1").unwrap();
+    let feline2_idx = felines.get_idx("feline_id_2").unwrap();
+    let cat_idx = cats.get_idx("cat_id").unwrap();
+
+    let mut model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: ManyToMany::default(),
+    };
+    model.felines_to_cats.add_link(Link(feline2_idx, cat_idx));
+
+    model.merge_indices(feline1_idx, &Some(feline2_idx).into_iter().collect());
+    assert_eq!(
+        vec![feline1_idx],
+        model
+            .animals_to_felines
+            .children_of(animal_idx)
+            .collect::<Vec<_>>()
+    );
+    assert!(model
+        .felines_to_cats
+        .contains_link(Link(feline1_idx, cat_idx)));
+    assert!(!model
+        .felines_to_cats
+        .contains_link(Link(feline2_idx, cat_idx)));
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(animal_idx);
+    assert!(cat_indexes.contains(&cat_idx));
+}
@@ -0,0 +1,99 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+pub struct Collections {
+    animals: CollectionWithId<Animal>,
+    felines: CollectionWithId<Feline>,
+    cats: CollectionWithId<Cat>,
+}
+
+#[derive(GetCorresponding, BuildRelations)]
+#[build_relations(collections = "Collections", parallel)]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+    #[build_relations(chain = "animals_to_felines, felines_to_cats", backward = "lazy")]
+    animals_to_cats: ForwardManyToMany<Animal, Cat>,
+}
+
+mod backward_only {
+    use relational_types::*;
+    use typed_index_collection::*;
+
+    use super::test_utils::*;
+
+    pub struct Collections {
+        pub animals: CollectionWithId<Animal>,
+        pub felines: CollectionWithId<Feline>,
+        pub cats: CollectionWithId<Cat>,
+    }
+
+    #[derive(GetCorresponding, BuildRelations)]
+    #[build_relations(collections = "Collections")]
+    pub struct Model {
+        pub animals_to_felines: OneToMany<Animal, Feline>,
+        pub felines_to_cats: OneToMany<Feline, Cat>,
+        #[build_relations(chain = "animals_to_felines, felines_to_cats", forward = "computed")]
+        pub animals_to_cats: BackwardManyToMany<Animal, Cat>,
+    }
+}
+
+fn main() {
+    let animal = Animal {
+        id: String::from("animal_id"),
+    };
+    let feline = Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    };
+    let cat = Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id"),
+    };
+    let collections = Collections {
+        animals: CollectionWithId::from(animal),
+        felines: CollectionWithId::from(feline),
+        cats: CollectionWithId::from(cat),
+    };
+    let mut model = Model::try_build(&collections).unwrap();
+    let animal_idx = collections.animals.get_idx("animal_id").unwrap();
+    let feline_idx = collections.felines.get_idx("feline_id").unwrap();
+    let cat_idx = collections.cats.get_idx("cat_id").unwrap();
+
+    let cats: IdxSet<Cat> = model.get_corresponding_from_idx(animal_idx);
+    assert!(cats.contains(&cat_idx));
+    assert!(!model.animals_to_cats.is_backward_built());
+    let animals: IdxSet<Animal> = model.get_corresponding_from_idx(cat_idx);
+    assert!(animals.contains(&animal_idx));
+    assert!(model.animals_to_cats.is_backward_built());
+
+    model.apply_felines_to_cats_delta(Delta {
+        added: Vec::new(),
+        removed: vec![Link(feline_idx, cat_idx)],
+    });
+    assert!(!model.animals_to_cats.is_backward_built());
+    assert_eq!(0, model.animals_to_cats.links().count());
+    assert!(model.verify_shortcuts().is_empty());
+    let parallel = Model::try_build_parallel(&collections).unwrap();
+    assert_eq!(1, parallel.animals_to_cats.links().count());
+
+    let collections = backward_only::Collections {
+        animals: collections.animals,
+        felines: collections.felines,
+        cats: collections.cats,
+    };
+    let mut model = backward_only::Model::try_build(&collections).unwrap();
+    let cats: IdxSet<Cat> = model.get_corresponding_from_idx(animal_idx);
+    assert!(cats.contains(&cat_idx));
+    let animals: IdxSet<Animal> = model.get_corresponding_from_idx(cat_idx);
+    assert!(animals.contains(&animal_idx));
+    model.apply_felines_to_cats_delta(Delta {
+        added: Vec::new(),
+        removed: vec![Link(feline_idx, cat_idx)],
+    });
+    assert_eq!(0, model.animals_to_cats.links().count());
+    assert!(model.verify_shortcuts().is_empty());
+}
@@ -0,0 +1,133 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+#[derive(GetCorresponding)]
+#[get_corresponding(tie_break = "field_names")]
+pub struct Model {
+    animals_to_felines: OneToMany<Animal, Feline>,
+    felines_to_cats: OneToMany<Feline, Cat>,
+    // as costly as the path through the felines, but declared last
+    // and of a greater name
+    #[get_corresponding(weight = "2")]
+    animals_to_kittens: ManyToMany<Animal, Cat>,
+}
+
+mod parallel {
+    use relational_types::*;
+
+    use super::test_utils::*;
+
+    #[derive(GetCorresponding)]
+    #[get_corresponding(tie_break = "field_names")]
+    pub struct Model {
+        pub felines_to_cats: OneToMany<Feline, Cat>,
+        // as light as `felines_to_cats`, but of a greater name
+        pub felines_to_kittens: OneToMany<Feline, Cat>,
+        // lighter, despite its greater name
+        #[get_corresponding(weight = "0.5")]
+        pub felines_to_toms: OneToMany<Feline, Cat>,
+        pub animals_to_felines: OneToMany<Animal, Feline>,
+        // as light as `animals_to_felines`, declared last but of a
+        // greater name
+        pub animals_to_lions: OneToMany<Animal, Feline>,
+    }
+}
+
+fn main() {
+    let animals = CollectionWithId::from(Animal {
+        id: String::from("animal_id"),
+    });
+    let felines = CollectionWithId::from(Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    });
+    let cats = CollectionWithId::new(vec![
+        Cat {
+            id: String::from("cat_id_1"),
+            feline_id: String::from("feline_id"),
+        },
+        Cat {
+            id: String::from("cat_id_2"),
+            feline_id: String::from("feline_id"),
+        },
+    ])
+    .unwrap();
+    let animal_idx = animals.get_idx("animal_id").unwrap();
+    let feline_idx = felines.get_idx("feline_id").unwrap();
+    let cat_idx_1 = cats.get_idx("cat_id_1").unwrap();
+    let cat_idx_2 = cats.get_idx("cat_id_2").unwrap();
+    let mut model = Model {
+        animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines").unwrap(),
+        felines_to_cats: OneToMany::default(),
+        animals_to_kittens: ManyToMany::default(),
+    };
+    model.felines_to_cats.add_link(Link(feline_idx, cat_idx_1));
+    model
+        .animals_to_kittens
+        .add_link(Link(animal_idx, cat_idx_2));
+
+    // `animals_to_felines` comes before `animals_to_kittens`, thus the
+    // path through the felines is followed
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(animal_idx);
+    assert_eq!(1, cat_indexes.len());
+    assert!(cat_indexes.contains(&cat_idx_1));
+    let animal_to_cat = Model::schema()
+        .queries
+        .into_iter()
+        .find(|query| query.from == "Animal" && query.to == "Cat")
+        .unwrap();
+    assert_eq!(
+        vec!["animals_to_felines", "felines_to_cats"],
+        animal_to_cat
+            .hops
+            .iter()
+            .map(|hop| hop.relation)
+            .collect::<Vec<_>>()
+    );
+
+    // `animals_to_kittens` comes before `felines_to_cats`
+    let animal_indexes: IdxSet<Animal> = model.get_corresponding_from_idx(cat_idx_2);
+    assert!(animal_indexes.contains(&animal_idx));
+
+    let mut model = parallel::Model {
+        felines_to_cats: OneToMany::default(),
+        felines_to_kittens: OneToMany::default(),
+        felines_to_toms: OneToMany::default(),
+        animals_to_felines: OneToMany::default(),
+        animals_to_lions: OneToMany::default(),
+    };
+    model
+        .animals_to_felines
+        .add_link(Link(animal_idx, feline_idx));
+    model.felines_to_toms.add_link(Link(feline_idx, cat_idx_1));
+    model.felines_to_cats.add_link(Link(feline_idx, cat_idx_2));
+    // the lightest parallel relation is followed, then the one of
+    // smallest name among the lightest ones
+    let cat_indexes: IdxSet<Cat> = model.get_corresponding_from_idx(animal_idx);
+    assert_eq!(1, cat_indexes.len());
+    assert!(cat_indexes.contains(&cat_idx_1));
+    assert_eq!(
+        Some("animals_to_felines"),
+        parallel::Model::relation_field_name::<Animal, Feline>()
+    );
+    assert_eq!(
+        Some("felines_to_toms"),
+        parallel::Model::relation_field_name::<Feline, Cat>()
+    );
+    let animal_to_cat = parallel::Model::schema()
+        .queries
+        .into_iter()
+        .find(|query| query.from == "Animal" && query.to == "Cat")
+        .unwrap();
+    assert_eq!(
+        vec!["animals_to_felines", "felines_to_toms"],
+        animal_to_cat
+            .hops
+            .iter()
+            .map(|hop| hop.relation)
+            .collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,53 @@
+mod test_utils;
+
+use relational_types::*;
+use test_utils::*;
+use typed_index_collection::*;
+
+pub struct Collections {
+    felines: CollectionWithId<Feline>,
+    cats: CollectionWithId<Cat>,
+    kittens: CollectionWithId<Cat>,
+}
+
+#[derive(GetCorresponding, BuildRelations)]
+#[build_relations(collections = "Collections")]
+pub struct Model {
+    felines_to_cats: OneToMany<Feline, Cat>,
+    felines_to_kittens: OneToMany<Feline, Cat>,
+}
+
+fn main() {
+    let feline = Feline {
+        id: String::from("feline_id"),
+        animal_id: String::from("animal_id"),
+    };
+    let cat = Cat {
+        id: String::from("cat_id"),
+        feline_id: String::from("feline_id"),
+    };
+    let kitten = Cat {
+        id: String::from("kitten_id"),
+        feline_id: String::from("feline_id"),
+    };
+    let mut collections = Collections {
+        felines: CollectionWithId::from(feline),
+        cats: CollectionWithId::from(cat),
+        kittens: CollectionWithId::default(),
+    };
+    let mut model = Model::try_build(&collections).unwrap();
+    assert_eq!(0, model.felines_to_kittens.links().count());
+
+    collections.kittens.push(kitten).unwrap();
+    match model.rebuild_relation(&collections.felines, &collections.kittens) {
+        Err(Error::AmbiguousRelation(_, _, names)) => {
+            assert_eq!(vec!["felines_to_cats", "felines_to_kittens"], names)
+        }
+        result => panic!("expected an ambiguous relation, found {:?}", result),
+    }
+    model
+        .rebuild_felines_to_kittens(&collections.felines, &collections.kittens)
+        .unwrap();
+    assert_eq!(1, model.felines_to_kittens.links().count());
+    assert_eq!(1, model.felines_to_cats.links().count());
+}
@@ -5,4 +5,48 @@ fn compile_error() {
     t.compile_fail("tests/02-invalid-weight.rs");
     t.compile_fail("tests/03-non-supported-argument.rs");
     t.pass("tests/04-get-corresponding-without-data.rs");
+    t.pass("tests/05-relation-report.rs");
+    t.pass("tests/06-build-relations.rs");
+    t.pass("tests/07-delegate.rs");
+    t.pass("tests/08-merge.rs");
+    t.compile_fail("tests/09-const-weight.rs");
+    t.pass("tests/10-planner.rs");
+    t.pass("tests/11-archive.rs");
+    t.pass("tests/12-only.rs");
+    t.pass("tests/13-flatten.rs");
+    t.pass("tests/14-generate-tests.rs");
+    t.compile_fail("tests/15-cost-fn.rs");
+    t.pass("tests/16-ambiguous-union.rs");
+    t.pass("tests/17-freeze.rs");
+    t.pass("tests/18-unreachable.rs");
+    t.pass("tests/19-world-log.rs");
+    t.pass("tests/20-relation-field.rs");
+    t.pass("tests/21-merge-indices.rs");
+    t.pass("tests/22-one-direction-shortcuts.rs");
+    t.pass("tests/23-tie-break.rs");
+    t.compile_fail("tests/24-nan-weight.rs");
+    t.pass("tests/25-rebuild-parallel-relations.rs");
+}
+
+#[test]
+fn generated_tests() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let output = std::process::Command::new(env!("CARGO"))
+        .args(&["test", "--manifest-path"])
+        .arg(format!("{}/generate_tests_crate/Cargo.toml", manifest_dir))
+        .env(
+            "CARGO_TARGET_DIR",
+            format!("{}/../target/generate_tests_crate", manifest_dir),
+        )
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}\n{}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("model_get_corresponding_tests::traversals_on_empty_model ... ok"));
+    assert!(stdout.contains("model_get_corresponding_tests::consistent_paths ... ok"));
 }
@@ -0,0 +1,889 @@
+//! Utilities to analyze the content of relations.
+
+use crate::{
+    Delta, IdxSet, Link, LinkIds, Links, ManyToMany, OneToMany, Relation, RelationalWorld,
+};
+use std::any::type_name;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use typed_index_collection::{CollectionWithId, Id, Idx};
+
+/// Returns the distribution of the forward degrees of a relation,
+/// i.e. for each number of targets, how many source objects have
+/// exactly this number of targets.
+pub fn degree_histogram<R: Relation>(rel: &R) -> BTreeMap<usize, usize> {
+    let mut histogram = BTreeMap::default();
+    for idx in rel.iter_from() {
+        let from = Some(idx).into_iter().collect();
+        let degree = rel.get_corresponding_forward(&from).len();
+        *histogram.entry(degree).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// Summary of the content of a relation.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RelationStats {
+    /// Name of the relation, usually the name of the field.
+    pub name: String,
+    /// Number of links of the relation.
+    pub links: usize,
+    /// Number of source objects without any target, among the source
+    /// objects given to `RelationStats::new`.
+    pub empty_parents: usize,
+    /// Maximum number of targets of a source object.
+    pub max_forward_degree: usize,
+    /// Maximum number of sources of a target object.
+    pub max_backward_degree: usize,
+}
+
+impl RelationStats {
+    /// Computes the statistics of the given relation, knowing the
+    /// number of source objects of the model, e.g. the length of their
+    /// collection, as the relations don't store the sources without
+    /// targets, e.g. in a `OneToMany`.
+    pub fn new<R: Relation>(name: &str, rel: &R, nb_from: usize) -> Self {
+        let histogram = degree_histogram(rel);
+        let parents_with_children: usize = histogram
+            .iter()
+            .filter(|&(&degree, _)| degree > 0)
+            .map(|(_, count)| count)
+            .sum();
+        let max_backward_degree = rel
+            .iter_to()
+            .map(|idx| {
+                let to: IdxSet<R::To> = Some(idx).into_iter().collect();
+                rel.get_corresponding_backward(&to).len()
+            })
+            .max()
+            .unwrap_or(0);
+        RelationStats {
+            name: name.to_owned(),
+            links: histogram.iter().map(|(degree, count)| degree * count).sum(),
+            empty_parents: nb_from.saturating_sub(parents_with_children),
+            max_forward_degree: histogram.keys().next_back().cloned().unwrap_or(0),
+            max_backward_degree,
+        }
+    }
+}
+
+/// Summary of all the relations of a model, as generated by the
+/// `relation_report` method of the `GetCorresponding` derive.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RelationReport {
+    /// Statistics of each relation, in the order of declaration.
+    pub relations: Vec<RelationStats>,
+}
+
+/// Coverage of a relation, i.e. the share of the objects of the model
+/// linked by the relation.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RelationCoverage {
+    /// Name of the relation, usually the name of the field.
+    pub name: String,
+    /// Number of links of the relation.
+    pub links: usize,
+    /// Percentage of the known source objects having a target.
+    pub from_coverage: f64,
+    /// Percentage of the known target objects having a source.
+    pub to_coverage: f64,
+}
+
+impl RelationCoverage {
+    /// Computes the coverage of the given relation, knowing the
+    /// number of source and target objects of the model.
+    pub fn new<R: Relation>(name: &str, rel: &R, nb_from: usize, nb_to: usize) -> Self {
+        let mut links = 0;
+        let mut linked_from = IdxSet::default();
+        rel.for_each_link(|from_idx, _| {
+            links += 1;
+            linked_from.insert(from_idx);
+        });
+        RelationCoverage {
+            name: name.to_owned(),
+            links,
+            from_coverage: percentage(linked_from.len(), nb_from),
+            to_coverage: percentage(rel.iter_to().count(), nb_to),
+        }
+    }
+}
+
+/// Path chosen by the `GetCorresponding` derive between 2 types.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PathStats {
+    /// Name of the source type.
+    pub from: String,
+    /// Name of the target type.
+    pub to: String,
+    /// Names of the types traversed, from the source to the target.
+    pub route: Vec<String>,
+}
+
+/// Statistics of a model, as generated by the `stats` method of the
+/// `GetCorresponding` derive.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WorldStats {
+    /// Coverage of each relation, in the order of declaration.
+    pub relations: Vec<RelationCoverage>,
+    /// Path chosen between every pair of connected types.
+    pub paths: Vec<PathStats>,
+}
+
+impl WorldStats {
+    /// Serializes the statistics in JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("statistics are always serializable")
+    }
+}
+
+/// Divergence between a shortcut relation and the same relation
+/// recomputed from its base relations, as reported by the
+/// `verify_shortcuts` method of the `BuildRelations` derive.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ShortcutDivergence {
+    /// Name of the shortcut, usually the name of the field.
+    pub name: String,
+    /// Number of recomputed links missing from the shortcut.
+    pub missing_links: usize,
+    /// Number of links of the shortcut absent from the recomputed
+    /// relation.
+    pub unexpected_links: usize,
+}
+
+impl ShortcutDivergence {
+    /// Compares the links of the `actual` shortcut to the `expected`
+    /// ones, returning `None` if they are the same.
+    pub fn new<T, U, R>(name: &str, actual: &R, expected: &ManyToMany<T, U>) -> Option<Self>
+    where
+        R: Relation<From = T, To = U>,
+    {
+        let delta = Delta::between(actual, expected);
+        let missing_links = delta.added.len();
+        let unexpected_links = delta.removed.len();
+        if missing_links == 0 && unexpected_links == 0 {
+            None
+        } else {
+            Some(ShortcutDivergence {
+                name: name.to_owned(),
+                missing_links,
+                unexpected_links,
+            })
+        }
+    }
+}
+
+/// The links of a relation differing between 2 models, as returned
+/// by `relation_diffs`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RelationDiff {
+    /// Name of the relation.
+    pub name: String,
+    /// Links of the expected model missing from the actual one, as
+    /// pairs of raw indices.
+    pub missing_links: Vec<(usize, usize)>,
+    /// Links of the actual model absent from the expected one, as
+    /// pairs of raw indices.
+    pub unexpected_links: Vec<(usize, usize)>,
+}
+
+impl RelationDiff {
+    /// Describes the differing links, one per line, using the
+    /// identifiers of the objects given by `ids` if any.
+    pub fn describe(&self, ids: Option<&dyn LinkIds>) -> String {
+        let describe_link = |&(from, to): &(usize, usize)| {
+            ids.and_then(|ids| ids.link_ids(&self.name, (from, to)))
+                .map(|(from_id, to_id)| format!("({}, {})", from_id, to_id))
+                .unwrap_or_else(|| format!("({}, {})", from, to))
+        };
+        let mut description = format!(
+            "relation {}: {} missing links, {} unexpected links",
+            self.name,
+            self.missing_links.len(),
+            self.unexpected_links.len()
+        );
+        for link in &self.missing_links {
+            description.push_str(&format!("\n- {}", describe_link(link)));
+        }
+        for link in &self.unexpected_links {
+            description.push_str(&format!("\n+ {}", describe_link(link)));
+        }
+        description
+    }
+
+    /// Renders the differing links as a Graphviz DOT graph, the missing
+    /// links in red and the unexpected ones in green, labelling the
+    /// objects with the identifiers given by `ids` if any.
+    pub fn to_dot(&self, ids: Option<&dyn LinkIds>) -> String {
+        let escape = |label: &str| label.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut sources = BTreeMap::new();
+        let mut targets = BTreeMap::new();
+        let mut edges = String::new();
+        let links = self
+            .missing_links
+            .iter()
+            .map(|link| (link, "red"))
+            .chain(self.unexpected_links.iter().map(|link| (link, "green")));
+        for (&(from, to), color) in links {
+            let (from_label, to_label) = ids
+                .and_then(|ids| ids.link_ids(&self.name, (from, to)))
+                .unwrap_or_else(|| (from.to_string(), to.to_string()));
+            sources.entry(from).or_insert(from_label);
+            targets.entry(to).or_insert(to_label);
+            edges.push_str(&format!(
+                "    source_{} -> target_{} [color={}];\n",
+                from, to, color
+            ));
+        }
+        let mut dot = format!("digraph \"{}\" {{\n", escape(&self.name));
+        for (idx, label) in &sources {
+            dot.push_str(&format!(
+                "    source_{} [label=\"{}\"];\n",
+                idx,
+                escape(label)
+            ));
+        }
+        for (idx, label) in &targets {
+            dot.push_str(&format!(
+                "    target_{} [label=\"{}\"];\n",
+                idx,
+                escape(label)
+            ));
+        }
+        dot.push_str(&edges);
+        dot.push('}');
+        dot
+    }
+}
+
+/// Compares every relation of the `actual` model to the `expected`
+/// one, returning the relations whose links differ, e.g. to diagnose
+/// a regression between 2 versions of a pipeline.
+///
+/// See the `assert_relations_eq` macro to panic with a readable
+/// report instead.
+pub fn relation_diffs<W: RelationalWorld>(expected: &W, actual: &W) -> Vec<RelationDiff> {
+    expected
+        .relation_names()
+        .into_iter()
+        .filter_map(|name| {
+            let expected_links: BTreeSet<_> = expected.links_of(name)?.into_iter().collect();
+            let actual_links: BTreeSet<_> = actual.links_of(name)?.into_iter().collect();
+            if expected_links == actual_links {
+                return None;
+            }
+            Some(RelationDiff {
+                name: name.to_owned(),
+                missing_links: expected_links.difference(&actual_links).cloned().collect(),
+                unexpected_links: actual_links.difference(&expected_links).cloned().collect(),
+            })
+        })
+        .collect()
+}
+
+/// Asserts that 2 models have the same links in every relation.
+///
+/// On failure, the differing links are printed, as raw indices, or
+/// with the identifiers of their objects if the collections of the
+/// model are given, i.e. a `LinkIds` like the collections of the
+/// `BuildRelations` derive.
+///
+/// ```no_run
+/// # use relational_types::*;
+/// # fn check<W: RelationalWorld, C: LinkIds>(expected: W, actual: W, collections: C) {
+/// assert_relations_eq!(expected, actual);
+/// assert_relations_eq!(expected, actual, collections);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_relations_eq {
+    ($expected:expr, $actual:expr $(,)?) => {
+        $crate::analysis::assert_no_relation_diffs(&$expected, &$actual, None)
+    };
+    ($expected:expr, $actual:expr, $collections:expr $(,)?) => {
+        $crate::analysis::assert_no_relation_diffs(
+            &$expected,
+            &$actual,
+            Some(&$collections as &dyn $crate::LinkIds),
+        )
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_no_relation_diffs<W: RelationalWorld>(
+    expected: &W,
+    actual: &W,
+    ids: Option<&dyn LinkIds>,
+) {
+    let diffs = relation_diffs(expected, actual);
+    if !diffs.is_empty() {
+        let descriptions: Vec<_> = diffs.iter().map(|diff| diff.describe(ids)).collect();
+        panic!(
+            "the relations of the models differ:\n{}",
+            descriptions.join("\n")
+        );
+    }
+}
+
+/// Returns the identifier of the object at the raw index `idx` of
+/// `collection`, if any.
+pub fn object_id<T: Id<T>>(collection: &CollectionWithId<T>, idx: usize) -> Option<String> {
+    collection
+        .iter()
+        .nth(idx)
+        .map(|(_, obj)| obj.id().to_owned())
+}
+
+/// Objects of a collection missing from a relation that must contain
+/// all of them, as reported by the `assert_covers` method of the
+/// `BuildRelations` derive. It usually means that objects were added
+/// to the collection after the relation was built.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CoverageGap {
+    /// Name of the relation, usually the name of the field.
+    pub name: String,
+    /// Identifiers of the objects of the collection without a parent
+    /// in the relation.
+    pub missing_ids: Vec<String>,
+    /// Indices of the relation beyond the end of the collection.
+    pub out_of_bounds: Vec<usize>,
+}
+
+impl CoverageGap {
+    /// Checks that every object of `children` has a parent in
+    /// `relation`, and that `relation` only contains objects of
+    /// `children`, returning `None` if it is the case.
+    pub fn new<T, U>(
+        name: &str,
+        relation: &OneToMany<T, U>,
+        children: &CollectionWithId<U>,
+    ) -> Option<Self>
+    where
+        U: Id<U>,
+    {
+        let missing_ids: Vec<_> = children
+            .iter()
+            .filter(|&(idx, _)| relation.parent_of(idx).is_none())
+            .map(|(_, obj)| obj.id().to_owned())
+            .collect();
+        let out_of_bounds: Vec<_> = relation
+            .iter_to()
+            .map(Idx::get)
+            .filter(|&idx| idx >= children.len())
+            .collect();
+        if missing_ids.is_empty() && out_of_bounds.is_empty() {
+            None
+        } else {
+            Some(CoverageGap {
+                name: name.to_owned(),
+                missing_ids,
+                out_of_bounds,
+            })
+        }
+    }
+}
+
+/// Objects of a model not reachable from a set of roots, as returned
+/// by the `unreachable_from` method of the `GetCorresponding` derive.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UnreachableReport {
+    /// The unreachable objects of each type linked by the relations,
+    /// in the order of first appearance of the types.
+    pub types: Vec<UnreachableObjects>,
+}
+
+impl UnreachableReport {
+    /// Returns `true` if every object is reachable.
+    pub fn is_empty(&self) -> bool {
+        self.types.iter().all(|objects| objects.indices.is_empty())
+    }
+
+    /// Returns `true` if `idx` is not reachable from the roots.
+    pub fn is_unreachable<T>(&self, idx: Idx<T>) -> bool {
+        self.types
+            .iter()
+            .filter(|objects| objects.type_name == type_name::<T>())
+            .any(|objects| objects.indices.binary_search(&idx.get()).is_ok())
+    }
+}
+
+/// Objects of a type not reachable from a set of roots.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UnreachableObjects {
+    /// Name of the type of the objects, as given by
+    /// `std::any::type_name`.
+    pub type_name: &'static str,
+    /// Sorted raw indices of the objects.
+    pub indices: Vec<usize>,
+}
+
+impl UnreachableObjects {
+    /// Returns the `objects` not belonging to `reached`.
+    pub fn new<T>(objects: impl Iterator<Item = Idx<T>>, reached: &IdxSet<T>) -> Self {
+        let mut indices: Vec<_> = objects
+            .filter(|idx| !reached.contains(idx))
+            .map(Idx::get)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        UnreachableObjects {
+            type_name: type_name::<T>(),
+            indices,
+        }
+    }
+}
+
+/// Returns the raw indices of the objects visited by a random walk of
+/// at most `steps` steps, from the object of raw index `start`, the
+/// `hops` being followed in turn. If the last hop ends on the type of
+/// `start`, e.g. with the hops of the `QuerySchema` from `Line` to
+/// `StopPoint` followed by the ones of the `QuerySchema` from
+/// `StopPoint` to `Line`, the hops are followed again from the first
+/// one until `steps` steps are made, else they are followed once.
+///
+/// Panics if a hop doesn't start on the type the previous one ends
+/// on, or if a relation of the hops is unknown to `world`.
+///
+/// At each step, the next object is chosen among the objects linked to
+/// the current one by the relation of the hop, with a probability
+/// proportional to `weight(relation, current, next)`. The walk stops
+/// at an object without any linked object of positive weight.
+#[cfg(feature = "rand")]
+pub fn random_walk<W, R>(
+    world: &W,
+    hops: &[crate::HopSchema],
+    start: usize,
+    steps: usize,
+    weight: impl Fn(&str, usize, usize) -> f64,
+    rng: &mut R,
+) -> Vec<usize>
+where
+    W: RelationalWorld,
+    R: rand::Rng + ?Sized,
+{
+    let neighbours: Vec<BTreeMap<usize, Vec<usize>>> = hops
+        .iter()
+        .map(|hop| {
+            let mut neighbours: BTreeMap<usize, Vec<usize>> = BTreeMap::default();
+            for (from, to) in world.links_of(hop.relation).unwrap_or_default() {
+                let (current, next) = if hop.forward { (from, to) } else { (to, from) };
+                neighbours.entry(current).or_default().push(next);
+            }
+            neighbours
+        })
+        .collect();
+    let steps = if is_cycle(world.relations(), hops) {
+        steps
+    } else {
+        steps.min(hops.len())
+    };
+    let mut visited = vec![start];
+    let mut current = start;
+    for (hop, neighbours) in hops.iter().zip(&neighbours).cycle().take(steps) {
+        let candidates: Vec<(usize, f64)> = neighbours
+            .get(&current)
+            .into_iter()
+            .flatten()
+            .map(|&next| (next, weight(hop.relation, current, next)))
+            .filter(|&(_, weight)| weight > 0.)
+            .collect();
+        let last = match candidates.last() {
+            Some(&(last, _)) => last,
+            None => break,
+        };
+        let total: f64 = candidates.iter().map(|&(_, weight)| weight).sum();
+        let mut threshold = rng.gen::<f64>() * total;
+        current = candidates
+            .iter()
+            .find(|&&(_, weight)| {
+                threshold -= weight;
+                threshold < 0.
+            })
+            // rounding errors may skip the last candidate
+            .map_or(last, |&(next, _)| next);
+        visited.push(current);
+    }
+    visited
+}
+
+/// Returns `true` if the `hops` end on the type they start from, thus
+/// can be followed again.
+///
+/// Panics if a hop doesn't start on the type the previous one ends
+/// on, or if a relation of the hops is not in `relations`.
+#[cfg(feature = "rand")]
+fn is_cycle(relations: &[crate::RelationInfo], hops: &[crate::HopSchema]) -> bool {
+    let types: Vec<(&str, &str)> = hops
+        .iter()
+        .map(|hop| {
+            let info = relations
+                .iter()
+                .find(|info| info.name == hop.relation)
+                .unwrap_or_else(|| panic!("unknown relation {}", hop.relation));
+            if hop.forward {
+                (info.from, info.to)
+            } else {
+                (info.to, info.from)
+            }
+        })
+        .collect();
+    for pair in types.windows(2) {
+        assert_eq!(
+            pair[0].1, pair[1].0,
+            "a hop from {} follows a hop to {}",
+            pair[1].0, pair[0].1
+        );
+    }
+    match (types.first(), types.last()) {
+        (Some(&(start, _)), Some(&(_, end))) => start == end,
+        _ => false,
+    }
+}
+
+fn percentage(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.
+    } else {
+        100. * count as f64 / total as f64
+    }
+}
+
+/// Returns a maximum matching of the relation, i.e. a largest set of
+/// links such that each source and each target appears in at most one
+/// link.
+///
+/// The matching is computed using the Hopcroft–Karp algorithm.
+pub fn maximum_matching<T, U>(rel: &ManyToMany<T, U>) -> Links<T, U> {
+    let sources: Vec<Idx<T>> = rel.iter_from().collect();
+    let targets: Vec<Idx<U>> = rel.iter_to().collect();
+    let target_positions: BTreeMap<Idx<U>, usize> = targets
+        .iter()
+        .enumerate()
+        .map(|(position, &idx)| (idx, position))
+        .collect();
+    let adjacency = sources
+        .iter()
+        .map(|&idx| {
+            let from = Some(idx).into_iter().collect();
+            rel.get_corresponding_forward(&from)
+                .iter()
+                .map(|to_idx| target_positions[to_idx])
+                .collect()
+        })
+        .collect();
+    let mut matching = HopcroftKarp::new(adjacency, targets.len());
+    matching.run();
+    matching
+        .source_pairs
+        .into_iter()
+        .enumerate()
+        .filter_map(|(source, target)| target.map(|target| Link(sources[source], targets[target])))
+        .collect()
+}
+
+const INFINITE_DISTANCE: usize = usize::MAX;
+
+struct HopcroftKarp {
+    adjacency: Vec<Vec<usize>>,
+    source_pairs: Vec<Option<usize>>,
+    target_pairs: Vec<Option<usize>>,
+    distances: Vec<usize>,
+    free_distance: usize,
+}
+
+impl HopcroftKarp {
+    fn new(adjacency: Vec<Vec<usize>>, nb_targets: usize) -> Self {
+        let nb_sources = adjacency.len();
+        HopcroftKarp {
+            adjacency,
+            source_pairs: vec![None; nb_sources],
+            target_pairs: vec![None; nb_targets],
+            distances: vec![INFINITE_DISTANCE; nb_sources],
+            free_distance: INFINITE_DISTANCE,
+        }
+    }
+
+    fn run(&mut self) {
+        while self.layer() {
+            for source in 0..self.adjacency.len() {
+                if self.source_pairs[source].is_none() {
+                    self.augment(source);
+                }
+            }
+        }
+    }
+
+    /// Computes the distances of the sources from the free sources
+    /// along alternating paths, returning `true` if an augmenting
+    /// path exists.
+    fn layer(&mut self) -> bool {
+        let mut queue = VecDeque::new();
+        for (source, pair) in self.source_pairs.iter().enumerate() {
+            if pair.is_none() {
+                self.distances[source] = 0;
+                queue.push_back(source);
+            } else {
+                self.distances[source] = INFINITE_DISTANCE;
+            }
+        }
+        self.free_distance = INFINITE_DISTANCE;
+        while let Some(source) = queue.pop_front() {
+            if self.distances[source] >= self.free_distance {
+                continue;
+            }
+            for &target in &self.adjacency[source] {
+                match self.target_pairs[target] {
+                    None => {
+                        if self.free_distance == INFINITE_DISTANCE {
+                            self.free_distance = self.distances[source] + 1;
+                        }
+                    }
+                    Some(next) => {
+                        if self.distances[next] == INFINITE_DISTANCE {
+                            self.distances[next] = self.distances[source] + 1;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+            }
+        }
+        self.free_distance != INFINITE_DISTANCE
+    }
+
+    /// Searches an augmenting path starting from `source` following
+    /// the layers, and flips it if found. The path is searched depth
+    /// first with an explicit stack, as it may be as long as the
+    /// relation is large.
+    fn augment(&mut self, source: usize) -> bool {
+        // the sources of the path, with the position in their
+        // adjacency of the next target to try
+        let mut path = vec![(source, 0)];
+        while let Some(&mut (current, ref mut position)) = path.last_mut() {
+            let target = match self.adjacency[current].get(*position) {
+                Some(&target) => target,
+                None => {
+                    self.distances[current] = INFINITE_DISTANCE;
+                    path.pop();
+                    continue;
+                }
+            };
+            *position += 1;
+            let next_distance = self.distances[current] + 1;
+            match self.target_pairs[target] {
+                None if next_distance == self.free_distance => {
+                    // each source of the path takes the target it tried
+                    for &(source, position) in &path {
+                        let target = self.adjacency[source][position - 1];
+                        self.source_pairs[source] = Some(target);
+                        self.target_pairs[target] = Some(source);
+                    }
+                    return true;
+                }
+                Some(next) if self.distances[next] == next_distance => path.push((next, 0)),
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // the animals `a0`, `a1`, … and the felines `f0`, `f1`, …, linked by
+    // the `links` between their numbers
+    fn animals_to_felines(
+        nb_objects: usize,
+        links: &[(usize, usize)],
+    ) -> ManyToMany<Animal, Feline> {
+        let animal_ids: Vec<String> = (0..nb_objects).map(|i| format!("a{}", i)).collect();
+        let feline_ids: Vec<String> = (0..nb_objects).map(|i| format!("f{}", i)).collect();
+        let animals = animals(&animal_ids.iter().map(String::as_str).collect::<Vec<_>>());
+        let felines = felines(
+            &feline_ids
+                .iter()
+                .map(|id| (id.as_str(), "a0"))
+                .collect::<Vec<_>>(),
+        );
+        let mut relation = ManyToMany::default();
+        for &(animal, feline) in links {
+            relation.add_link(Link(
+                animals.get_idx(&animal_ids[animal]).unwrap(),
+                felines.get_idx(&feline_ids[feline]).unwrap(),
+            ));
+        }
+        relation
+    }
+
+    fn check_matching(relation: &ManyToMany<Animal, Feline>, matching: &Links<Animal, Feline>) {
+        let sources: BTreeSet<_> = matching.iter().map(|link| link.from()).collect();
+        let targets: BTreeSet<_> = matching.iter().map(|link| link.to()).collect();
+        assert_eq!(matching.len(), sources.len());
+        assert_eq!(matching.len(), targets.len());
+        assert!(matching.iter().all(|&link| relation.contains_link(link)));
+    }
+
+    #[test]
+    fn relation_stats_count_the_parents_without_children() {
+        let animals = animals(&["a1", "a2", "a3"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a1"), ("f3", "a2")]);
+        let relation = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        let stats = RelationStats::new("animals_to_felines", &relation, animals.len());
+        assert_eq!(3, stats.links);
+        assert_eq!(1, stats.empty_parents);
+        assert_eq!(2, stats.max_forward_degree);
+        assert_eq!(1, stats.max_backward_degree);
+    }
+
+    #[test]
+    fn perfect_matching() {
+        // the greedy matching of `a0` with `f0` must be flipped
+        let relation = animals_to_felines(3, &[(0, 0), (0, 1), (1, 0), (2, 1), (2, 2)]);
+        let matching = maximum_matching(&relation);
+        check_matching(&relation, &matching);
+        assert_eq!(3, matching.len());
+    }
+
+    #[test]
+    fn deficient_matching() {
+        // `a0` and `a1` are only linked to `f0`
+        let relation = animals_to_felines(3, &[(0, 0), (1, 0), (2, 1), (2, 2)]);
+        let matching = maximum_matching(&relation);
+        check_matching(&relation, &matching);
+        assert_eq!(2, matching.len());
+    }
+
+    #[test]
+    fn long_augmenting_path() {
+        // `ai` is linked to `fi` and `f(i+1)`, and the last animal to
+        // `f0` only, thus matching `ai` with `fi` first leaves a single
+        // augmenting path through all the animals
+        let nb_objects = 100_000;
+        let mut links: Vec<_> = (0..nb_objects - 1)
+            .flat_map(|i| vec![(i, i), (i, i + 1)])
+            .collect();
+        links.push((nb_objects - 1, 0));
+        let relation = animals_to_felines(nb_objects, &links);
+        let matching = maximum_matching(&relation);
+        check_matching(&relation, &matching);
+        assert_eq!(nb_objects, matching.len());
+    }
+
+    #[test]
+    fn empty_matching() {
+        let relation = animals_to_felines(0, &[]);
+        assert!(maximum_matching(&relation).is_empty());
+    }
+
+    #[cfg(feature = "rand")]
+    mod random_walk {
+        use crate::analysis::random_walk;
+        use crate::test_utils::*;
+        use crate::{HopSchema, OneToMany, RelationInfo, RelationalWorld};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        struct World {
+            animals_to_felines: OneToMany<Animal, Feline>,
+        }
+
+        impl RelationalWorld for World {
+            fn relations(&self) -> &'static [RelationInfo] {
+                &[RelationInfo {
+                    name: "animals_to_felines",
+                    from: "Animal",
+                    to: "Feline",
+                }]
+            }
+
+            fn links_of(&self, name: &str) -> Option<Vec<(usize, usize)>> {
+                match name {
+                    "animals_to_felines" => Some(
+                        self.animals_to_felines
+                            .links()
+                            .map(|link| (link.from().get(), link.to().get()))
+                            .collect(),
+                    ),
+                    _ => None,
+                }
+            }
+        }
+
+        // `a1` has the felines `f1` and `f2`, `a2` the feline `f3`
+        fn world() -> World {
+            let animals = animals(&["a1", "a2"]);
+            let felines = felines(&[("f1", "a1"), ("f2", "a1"), ("f3", "a2")]);
+            World {
+                animals_to_felines: OneToMany::new(&animals, &felines, "animals_to_felines")
+                    .unwrap(),
+            }
+        }
+
+        const FORWARD: HopSchema = HopSchema {
+            relation: "animals_to_felines",
+            forward: true,
+        };
+        const BACKWARD: HopSchema = HopSchema {
+            relation: "animals_to_felines",
+            forward: false,
+        };
+
+        #[test]
+        fn cycle() {
+            let mut rng = StdRng::seed_from_u64(42);
+            let walk = random_walk(&world(), &[FORWARD, BACKWARD], 0, 6, |_, _, _| 1., &mut rng);
+            assert_eq!(7, walk.len());
+            for (step, &idx) in walk.iter().enumerate() {
+                if step % 2 == 0 {
+                    assert_eq!(0, idx);
+                } else {
+                    assert!(idx < 2, "f3 is not a feline of a1");
+                }
+            }
+        }
+
+        #[test]
+        fn hops_followed_once() {
+            let mut rng = StdRng::seed_from_u64(42);
+            let walk = random_walk(&world(), &[FORWARD], 1, 6, |_, _, _| 1., &mut rng);
+            assert_eq!(vec![1, 2], walk);
+        }
+
+        #[test]
+        fn weights() {
+            let mut rng = StdRng::seed_from_u64(42);
+            // the link from `a1` to `f1` has no weight
+            let only_f2 = |_: &str, current, next| {
+                if (current, next) == (0, 0) {
+                    0.
+                } else {
+                    1.
+                }
+            };
+            let walk = random_walk(&world(), &[FORWARD, BACKWARD], 0, 4, only_f2, &mut rng);
+            assert_eq!(vec![0, 1, 0, 1, 0], walk);
+            let nothing = |_: &str, _, _| 0.;
+            let walk = random_walk(&world(), &[FORWARD, BACKWARD], 0, 4, nothing, &mut rng);
+            assert_eq!(vec![0], walk);
+        }
+
+        #[test]
+        #[should_panic(expected = "a hop from Animal follows a hop to Feline")]
+        fn hops_not_chained() {
+            let mut rng = StdRng::seed_from_u64(42);
+            random_walk(&world(), &[FORWARD, FORWARD], 0, 4, |_, _, _| 1., &mut rng);
+        }
+    }
+}
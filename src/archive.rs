@@ -0,0 +1,273 @@
+use crate::{Error, ManyToMany, RelationalWorld};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, Write};
+use typed_index_collection::{CollectionWithId, Idx};
+
+/// Version of the format written by `RelationsArchive::write`.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+const MAGIC: &str = "relational_types archive";
+
+/// The relations of a whole model, that can be saved and reloaded.
+///
+/// The archive starts with a header containing the version of the
+/// format and the version of the model, and each relation is stored
+/// with its name and a checksum of its links. Reading an archive
+/// written with another format, or a corrupted one, fails instead of
+/// returning wrong relations. An archive of an older version of the
+/// model is upgraded by the `Migrations` given to
+/// `RelationsArchive::read`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationsArchive {
+    /// Version of the model, defined by the user.
+    pub version: u32,
+    /// The relations of the model.
+    pub relations: Vec<ArchivedRelation>,
+}
+
+/// A relation stored in a `RelationsArchive`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedRelation {
+    /// Name of the relation.
+    pub name: String,
+    /// The links of the relation, as pairs of raw indices.
+    pub links: Vec<(usize, usize)>,
+}
+
+type Migration = Box<dyn Fn(&mut RelationsArchive) -> Result<(), Error>>;
+
+/// The hooks upgrading an archive to the current version of the
+/// model.
+pub struct Migrations {
+    version: u32,
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl Migrations {
+    /// Creates the migrations of a model whose current version is
+    /// `version`.
+    pub fn new(version: u32) -> Self {
+        Migrations {
+            version,
+            migrations: BTreeMap::default(),
+        }
+    }
+
+    /// Adds the hook upgrading an archive from `version` to
+    /// `version + 1`.
+    pub fn migrate_from<F>(&mut self, version: u32, migration: F) -> &mut Self
+    where
+        F: Fn(&mut RelationsArchive) -> Result<(), Error> + 'static,
+    {
+        self.migrations.insert(version, Box::new(migration));
+        self
+    }
+
+    fn apply(&self, archive: &mut RelationsArchive) -> Result<(), Error> {
+        if archive.version > self.version {
+            return Err(Error::IncompatibleArchive(archive.version, self.version));
+        }
+        while archive.version < self.version {
+            let migration = self
+                .migrations
+                .get(&archive.version)
+                .ok_or(Error::IncompatibleArchive(archive.version, self.version))?;
+            migration(archive)?;
+            archive.version += 1;
+        }
+        Ok(())
+    }
+}
+
+impl RelationsArchive {
+    /// Archives the relations of `world`, `version` being the version
+    /// of the model.
+    pub fn from_world<W: RelationalWorld>(world: &W, version: u32) -> Self {
+        let relations = world
+            .relation_names()
+            .into_iter()
+            .map(|name| {
+                let mut links = world.links_of(name).unwrap_or_default();
+                // whatever the storage, the same relations give the
+                // same archive
+                links.sort_unstable();
+                ArchivedRelation {
+                    name: name.to_owned(),
+                    links,
+                }
+            })
+            .collect();
+        RelationsArchive { version, relations }
+    }
+
+    /// Returns the relation named `name`, if any.
+    pub fn relation(&self, name: &str) -> Option<&ArchivedRelation> {
+        self.relations.iter().find(|relation| relation.name == name)
+    }
+
+    /// Returns the raw indices reached from the raw indices `from` by
+    /// following `hops`, pairs of a relation name and `true` to follow
+    /// the relation from its sources to its targets, like the hops of a
+    /// `QuerySchema`, e.g. to run the traversals of a model from its
+    /// archive only. Returns `None` if a relation is not in the
+    /// archive.
+    pub fn traverse(&self, hops: &[(&str, bool)], from: &[usize]) -> Option<Vec<usize>> {
+        let mut reached: BTreeSet<usize> = from.iter().copied().collect();
+        for &(name, forward) in hops {
+            let relation = self.relation(name)?;
+            reached = relation
+                .links
+                .iter()
+                .filter_map(|&(from, to)| {
+                    let (source, target) = if forward { (from, to) } else { (to, from) };
+                    if reached.contains(&source) {
+                        Some(target)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+        }
+        Some(reached.into_iter().collect())
+    }
+
+    /// Returns the raw indices reached from the raw indices `from` by
+    /// following the relations named in `path`, separated by commas,
+    /// a name prefixed by `-` being followed from its targets to its
+    /// sources, e.g. `"brands_to_bikes,-owners_to_bikes"`, as `traverse`
+    /// does. It is used by the bindings, where the hops are given as
+    /// text.
+    pub fn traverse_path(&self, path: &str, from: &[usize]) -> Option<Vec<usize>> {
+        let hops: Vec<(&str, bool)> = path
+            .split(',')
+            .map(str::trim)
+            .filter(|hop| !hop.is_empty())
+            .map(|hop| match hop.strip_prefix('-') {
+                Some(name) => (name, false),
+                None => (hop, true),
+            })
+            .collect();
+        self.traverse(&hops, from)
+    }
+
+    /// Writes the archive.
+    pub fn write<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writeln!(writer, "{} {}", MAGIC, ARCHIVE_FORMAT_VERSION)?;
+        writeln!(writer, "version {}", self.version)?;
+        for relation in &self.relations {
+            writeln!(
+                writer,
+                "relation {} {} {:016x}",
+                relation.name,
+                relation.links.len(),
+                relation.checksum()
+            )?;
+            for (from, to) in &relation.links {
+                writeln!(writer, "{} {}", from, to)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads an archive written by `write`, and upgrades it to the
+    /// current version of the model with `migrations`.
+    pub fn read<R: BufRead>(reader: R, migrations: &Migrations) -> Result<Self, Error> {
+        let mut lines = reader.lines();
+        let mut next_line = || -> Result<Option<String>, Error> {
+            lines
+                .next()
+                .transpose()
+                .map_err(|e| Error::InvalidArchive(e.to_string()))
+        };
+        let header = next_line()?.unwrap_or_default();
+        let format_version = header
+            .strip_prefix(MAGIC)
+            .and_then(|version| version.trim().parse().ok())
+            .ok_or_else(|| Error::InvalidArchive(format!("invalid header \"{}\"", header)))?;
+        if format_version != ARCHIVE_FORMAT_VERSION {
+            return Err(Error::IncompatibleArchiveFormat(
+                format_version,
+                ARCHIVE_FORMAT_VERSION,
+            ));
+        }
+        let line = next_line()?.unwrap_or_default();
+        let version = line
+            .strip_prefix("version ")
+            .and_then(|version| version.parse().ok())
+            .ok_or_else(|| Error::InvalidArchive(format!("invalid version \"{}\"", line)))?;
+        let mut relations = Vec::new();
+        while let Some(line) = next_line()? {
+            let invalid_relation =
+                || Error::InvalidArchive(format!("invalid relation \"{}\"", line));
+            let fields: Vec<_> = line.split(' ').collect();
+            let (name, nb_links, checksum) = match fields.as_slice() {
+                ["relation", name, nb_links, checksum] => (name, nb_links, checksum),
+                _ => return Err(invalid_relation()),
+            };
+            let nb_links: usize = nb_links.parse().map_err(|_| invalid_relation())?;
+            let checksum = u64::from_str_radix(checksum, 16).map_err(|_| invalid_relation())?;
+            let mut links = Vec::with_capacity(nb_links);
+            for _ in 0..nb_links {
+                let line = next_line()?.ok_or_else(|| {
+                    Error::InvalidArchive(format!("missing links of relation {}", name))
+                })?;
+                let link = line
+                    .split_once(' ')
+                    .and_then(|(from, to)| from.parse().ok().zip(to.parse().ok()))
+                    .ok_or_else(|| Error::InvalidArchive(format!("invalid link \"{}\"", line)))?;
+                links.push(link);
+            }
+            let relation = ArchivedRelation {
+                name: (*name).to_owned(),
+                links,
+            };
+            if relation.checksum() != checksum {
+                return Err(Error::CorruptedArchive(relation.name));
+            }
+            relations.push(relation);
+        }
+        let mut archive = RelationsArchive { version, relations };
+        migrations.apply(&mut archive)?;
+        Ok(archive)
+    }
+}
+
+impl ArchivedRelation {
+    /// Rebuilds the relation between the objects of `from` and `to`.
+    pub fn to_many_to_many<T, U>(
+        &self,
+        from: &CollectionWithId<T>,
+        to: &CollectionWithId<U>,
+    ) -> Result<ManyToMany<T, U>, Error> {
+        let from_indices: Vec<Idx<T>> = from.iter().map(|(idx, _)| idx).collect();
+        let to_indices: Vec<Idx<U>> = to.iter().map(|(idx, _)| idx).collect();
+        let mut forward: BTreeMap<Idx<T>, BTreeSet<Idx<U>>> = BTreeMap::default();
+        for &(from_idx, to_idx) in &self.links {
+            let from_idx = *from_indices
+                .get(from_idx)
+                .ok_or(Error::IndexOutOfBounds(from_idx, from_indices.len()))?;
+            let to_idx = *to_indices
+                .get(to_idx)
+                .ok_or(Error::IndexOutOfBounds(to_idx, to_indices.len()))?;
+            forward.entry(from_idx).or_default().insert(to_idx);
+        }
+        Ok(ManyToMany::from_forward(forward))
+    }
+
+    /// FNV-1a hash of the links, stable between versions of Rust and
+    /// platforms, unlike the hashers of the standard library.
+    fn checksum(&self) -> u64 {
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+        for &(from, to) in &self.links {
+            for byte in (from as u64)
+                .to_le_bytes()
+                .iter()
+                .chain(&(to as u64).to_le_bytes())
+            {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
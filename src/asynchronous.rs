@@ -0,0 +1,118 @@
+//! Asynchronous construction of relations, enabled by the `async`
+//! feature.
+//!
+//! The construction of relations is often bound by the IO needed to
+//! read the links. `OneToMany::new_from_stream` builds a relation
+//! while the links are read, and `spawn_new_from_stream` spawns
+//! this construction on any executor, so that the relations coming
+//! from independent sources are built in parallel.
+//!
+//! ```no_run
+//! # use relational_types::{asynchronous::*, *};
+//! # use futures::{stream::Stream, task::Spawn};
+//! # use std::sync::Arc;
+//! # use typed_index_collection::CollectionWithId;
+//! # struct Brand;
+//! # struct Bike;
+//! # struct Owner;
+//! # impl typed_index_collection::Id<Brand> for Brand { fn id(&self) -> &str { unimplemented!() } fn set_id(&mut self, _: String) {} }
+//! # impl typed_index_collection::Id<Owner> for Owner { fn id(&self) -> &str { unimplemented!() } fn set_id(&mut self, _: String) {} }
+//! # impl typed_index_collection::Id<Bike> for Bike { fn id(&self) -> &str { unimplemented!() } fn set_id(&mut self, _: String) {} }
+//! # impl typed_index_collection::Id<Brand> for Bike { fn id(&self) -> &str { unimplemented!() } fn set_id(&mut self, _: String) {} }
+//! # impl typed_index_collection::Id<Owner> for Bike { fn id(&self) -> &str { unimplemented!() } fn set_id(&mut self, _: String) {} }
+//! # fn read_links(_: &str) -> impl Stream<Item = (String, String)> + Send + 'static { futures::stream::empty() }
+//! # async fn load(executor: &impl Spawn, brands: Arc<CollectionWithId<Brand>>, owners: Arc<CollectionWithId<Owner>>, bikes: Arc<CollectionWithId<Bike>>) -> Result<(), Box<dyn std::error::Error>> {
+//! let brands_to_bikes = spawn_new_from_stream(
+//!     executor,
+//!     brands,
+//!     bikes.clone(),
+//!     read_links("brands_to_bikes"),
+//!     "brands_to_bikes".to_owned(),
+//! )?;
+//! let owners_to_bikes = spawn_new_from_stream(
+//!     executor,
+//!     owners,
+//!     bikes,
+//!     read_links("owners_to_bikes"),
+//!     "owners_to_bikes".to_owned(),
+//! )?;
+//! let brands_to_bikes: OneToMany<Brand, Bike> = brands_to_bikes.await?;
+//! let owners_to_bikes: OneToMany<Owner, Bike> = owners_to_bikes.await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Link, OneToMany};
+use futures::future::RemoteHandle;
+use futures::stream::{Stream, StreamExt};
+use futures::task::{Spawn, SpawnError, SpawnExt};
+use std::sync::Arc;
+use typed_index_collection::{CollectionWithId, Id};
+
+/// The handle of a relation built by `spawn_new_from_stream`.
+pub type RelationHandle<T, U> = RemoteHandle<Result<OneToMany<T, U>, Error>>;
+
+impl<T, U> OneToMany<T, U>
+where
+    T: Id<T>,
+    U: Id<U>,
+{
+    /// Construct the relation from a stream of `(parent id, child
+    /// id)` links, resolving the identifiers in the 2 given
+    /// `CollectionWithId`s.
+    ///
+    /// If a child appears several times, its last parent is kept.
+    pub async fn new_from_stream<S, P, C>(
+        one: &CollectionWithId<T>,
+        many: &CollectionWithId<U>,
+        links: S,
+        rel_name: &str,
+    ) -> Result<Self, Error>
+    where
+        S: Stream<Item = (P, C)>,
+        P: AsRef<str>,
+        C: AsRef<str>,
+    {
+        futures::pin_mut!(links);
+        let mut relation = OneToMany::default();
+        while let Some((one_id, many_id)) = links.next().await {
+            let one_idx = get_idx(one, one_id.as_ref(), rel_name)?;
+            let many_idx = get_idx(many, many_id.as_ref(), rel_name)?;
+            relation.add_link(Link(one_idx, many_idx));
+        }
+        Ok(relation)
+    }
+}
+
+/// Spawns the construction of a relation from a stream of `(parent
+/// id, child id)` links on the given executor, see
+/// `OneToMany::new_from_stream`.
+pub fn spawn_new_from_stream<T, U, S, P, C, E>(
+    executor: &E,
+    one: Arc<CollectionWithId<T>>,
+    many: Arc<CollectionWithId<U>>,
+    links: S,
+    rel_name: String,
+) -> Result<RelationHandle<T, U>, SpawnError>
+where
+    E: Spawn + ?Sized,
+    T: Id<T> + Send + Sync + 'static,
+    U: Id<U> + Send + Sync + 'static,
+    S: Stream<Item = (P, C)> + Send + 'static,
+    P: AsRef<str> + Send,
+    C: AsRef<str> + Send,
+{
+    executor.spawn_with_handle(async move {
+        OneToMany::new_from_stream(&one, &many, links, &rel_name).await
+    })
+}
+
+fn get_idx<T: Id<T>>(
+    collection: &CollectionWithId<T>,
+    id: &str,
+    rel_name: &str,
+) -> Result<typed_index_collection::Idx<T>, Error> {
+    collection
+        .get_idx(id)
+        .ok_or_else(|| Error::IdentifierNotFound(id.to_owned(), rel_name.to_owned()))
+}
@@ -0,0 +1,119 @@
+use crate::{IdxSet, Link, ManyToMany, Relation};
+use std::collections::BTreeMap;
+use typed_index_collection::Idx;
+
+/// A many to many relation whose links carry a count, e.g. the number
+/// of trips going from a stop to another.
+///
+/// A link is in the relation as long as its count is positive, thus
+/// the relation can be traversed like a `ManyToMany`.
+#[derive(Debug)]
+pub struct CountedManyToMany<T, U> {
+    relation: ManyToMany<T, U>,
+    counts: BTreeMap<Link<T, U>, usize>,
+}
+
+impl<T, U> Default for CountedManyToMany<T, U> {
+    fn default() -> Self {
+        CountedManyToMany {
+            relation: Default::default(),
+            counts: Default::default(),
+        }
+    }
+}
+
+impl<T, U> CountedManyToMany<T, U> {
+    /// Increments the count of a link, adding it to the relation if
+    /// needed, and returns the new count.
+    pub fn add_link(&mut self, link: impl Into<Link<T, U>>) -> usize {
+        let link = link.into();
+        let count = self.counts.entry(link).or_insert(0);
+        if *count == 0 {
+            self.relation.add_link(link);
+        }
+        *count += 1;
+        *count
+    }
+
+    /// Decrements the count of a link, removing it from the relation
+    /// when it reaches zero, and returns the new count. Returns `None`
+    /// if the link was not in the relation.
+    pub fn remove_link(&mut self, link: impl Into<Link<T, U>>) -> Option<usize> {
+        let link = link.into();
+        let count = self.counts.get_mut(&link)?;
+        *count -= 1;
+        let count = *count;
+        if count == 0 {
+            self.counts.remove(&link);
+            self.relation.remove_link(link);
+        }
+        Some(count)
+    }
+
+    /// Returns the count of the link between `from` and `to`, zero if
+    /// they are not linked.
+    pub fn count(&self, from: Idx<T>, to: Idx<U>) -> usize {
+        self.counts.get(&Link(from, to)).copied().unwrap_or(0)
+    }
+
+    /// Returns an iterator over the links of the relation and their
+    /// counts, sorted by source then by target.
+    pub fn counts(&self) -> impl Iterator<Item = (Link<T, U>, usize)> + '_ {
+        self.counts.iter().map(|(&link, &count)| (link, count))
+    }
+
+    /// Returns the relation without the counts.
+    pub fn relation(&self) -> &ManyToMany<T, U> {
+        &self.relation
+    }
+}
+
+impl<T, U> Relation for CountedManyToMany<T, U> {
+    type From = T;
+    type To = U;
+    forward_relation!(
+        relation,
+        iter_from,
+        iter_to,
+        get_corresponding_forward,
+        get_corresponding_backward,
+        get_corresponding_forward_within,
+        get_corresponding_backward_within
+    );
+    fn len_hint(&self) -> usize {
+        self.counts.len()
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.counts.contains_key(&link)
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for link in self.counts.keys() {
+            f(link.from(), link.to());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn decrementing_to_zero_removes_the_link() {
+        let animals = animals(&["a1"]);
+        let felines = felines(&[("f1", "a1")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let mut relation = CountedManyToMany::default();
+        assert_eq!(1, relation.add_link(Link(a1, f1)));
+        assert_eq!(2, relation.add_link(Link(a1, f1)));
+        assert_eq!(Some(1), relation.remove_link(Link(a1, f1)));
+        assert!(relation.contains_link(Link(a1, f1)));
+        assert_eq!(Some(0), relation.remove_link(Link(a1, f1)));
+        assert!(!relation.contains_link(Link(a1, f1)));
+        assert!(!relation.relation().contains_link(Link(a1, f1)));
+        assert_eq!(0, relation.count(a1, f1));
+        assert!(relation.counts().next().is_none());
+        assert_eq!(None, relation.remove_link(Link(a1, f1)));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{IdxSet, Link, OneToMany, Relation};
+use std::collections::BTreeMap;
+use typed_index_collection::Idx;
+
+/// A one to many relation indexing its parents by their number of
+/// children, e.g. to find the lines with a single route without
+/// counting the routes of every line.
+#[derive(Debug)]
+pub struct DegreeIndexedOneToMany<T, U> {
+    relation: OneToMany<T, U>,
+    // parents with children only
+    by_degree: BTreeMap<usize, IdxSet<T>>,
+}
+
+impl<T, U> Default for DegreeIndexedOneToMany<T, U> {
+    fn default() -> Self {
+        DegreeIndexedOneToMany {
+            relation: Default::default(),
+            by_degree: Default::default(),
+        }
+    }
+}
+
+impl<T, U> DegreeIndexedOneToMany<T, U> {
+    /// Adds a link, replacing the previous parent of its child if any.
+    pub fn add_link(&mut self, link: impl Into<Link<T, U>>) {
+        let Link(from, to) = link.into();
+        let previous = self.relation.parent_of(to);
+        if previous == Some(from) {
+            return;
+        }
+        let degree = self.degree(from);
+        let previous_degree = previous.map(|previous| (previous, self.degree(previous)));
+        self.relation.add_link(Link(from, to));
+        self.reindex(from, degree);
+        if let Some((previous, previous_degree)) = previous_degree {
+            self.reindex(previous, previous_degree);
+        }
+    }
+
+    /// Removes a link, returning `true` if it was in the relation.
+    pub fn remove_link(&mut self, link: impl Into<Link<T, U>>) -> bool {
+        let link = link.into();
+        let degree = self.degree(link.from());
+        let removed = self.relation.remove_link(link);
+        if removed {
+            self.reindex(link.from(), degree);
+        }
+        removed
+    }
+
+    /// Returns the number of children of `parent`.
+    pub fn degree(&self, parent: Idx<T>) -> usize {
+        self.relation
+            .children(parent)
+            .map_or(0, |children| children.len())
+    }
+
+    /// Returns an iterator over the parents with exactly `n` children,
+    /// none for `n = 0`, the parents without children not being in the
+    /// relation.
+    pub fn parents_with_exactly(&self, n: usize) -> impl Iterator<Item = Idx<T>> + '_ {
+        self.by_degree
+            .get(&n)
+            .into_iter()
+            .flat_map(|parents| parents.iter().copied())
+    }
+
+    /// Returns an iterator over the parents with at least `n` children,
+    /// sorted by number of children.
+    pub fn parents_with_at_least(&self, n: usize) -> impl Iterator<Item = Idx<T>> + '_ {
+        self.by_degree
+            .range(n..)
+            .flat_map(|(_, parents)| parents.iter().copied())
+    }
+
+    /// Returns the relation without the index.
+    pub fn relation(&self) -> &OneToMany<T, U> {
+        &self.relation
+    }
+
+    /// Moves `parent` from the index of its previous `degree` to the
+    /// one of its current degree.
+    fn reindex(&mut self, parent: Idx<T>, degree: usize) {
+        if let Some(parents) = self.by_degree.get_mut(&degree) {
+            parents.remove(&parent);
+            if parents.is_empty() {
+                self.by_degree.remove(&degree);
+            }
+        }
+        let degree = self.degree(parent);
+        if degree > 0 {
+            self.by_degree.entry(degree).or_default().insert(parent);
+        }
+    }
+}
+
+impl<T, U> From<OneToMany<T, U>> for DegreeIndexedOneToMany<T, U> {
+    fn from(relation: OneToMany<T, U>) -> Self {
+        let mut by_degree: BTreeMap<usize, IdxSet<T>> = BTreeMap::default();
+        for parent in relation.iter_from() {
+            let degree = relation
+                .children(parent)
+                .map_or(0, |children| children.len());
+            by_degree.entry(degree).or_default().insert(parent);
+        }
+        DegreeIndexedOneToMany {
+            relation,
+            by_degree,
+        }
+    }
+}
+
+impl<T, U> Relation for DegreeIndexedOneToMany<T, U> {
+    type From = T;
+    type To = U;
+    forward_relation!(relation);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn add_link_reindexes_both_parents() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a1"), ("f3", "a2")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f3 = felines.get_idx("f3").unwrap();
+        let relation = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        let mut relation = DegreeIndexedOneToMany::from(relation);
+        assert_eq!(
+            vec![a2],
+            relation.parents_with_exactly(1).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![a1],
+            relation.parents_with_exactly(2).collect::<Vec<_>>()
+        );
+
+        // f1 moves from a1 to a2
+        relation.add_link(Link(a2, f1));
+        assert_eq!(1, relation.degree(a1));
+        assert_eq!(2, relation.degree(a2));
+        assert_eq!(
+            vec![a1],
+            relation.parents_with_exactly(1).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![a2],
+            relation.parents_with_exactly(2).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![a1, a2],
+            relation.parents_with_at_least(1).collect::<Vec<_>>()
+        );
+
+        // a parent losing its last child leaves the index
+        assert!(relation.remove_link(Link(a2, f3)));
+        assert!(relation.remove_link(Link(a2, f1)));
+        assert!(!relation.remove_link(Link(a2, f1)));
+        assert_eq!(
+            vec![a1],
+            relation.parents_with_at_least(0).collect::<Vec<_>>()
+        );
+        assert!(relation.parents_with_exactly(0).next().is_none());
+    }
+}
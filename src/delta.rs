@@ -0,0 +1,65 @@
+use crate::{Link, Links, Relation};
+
+/// A change of the links of a relation.
+pub struct Delta<T, U> {
+    /// Links added to the relation.
+    pub added: Links<T, U>,
+    /// Links removed from the relation.
+    pub removed: Links<T, U>,
+}
+
+impl<T, U> Delta<T, U> {
+    /// Returns the delta changing the links of `old` into the links of
+    /// `new`.
+    pub fn between<R1, R2>(old: &R1, new: &R2) -> Self
+    where
+        R1: Relation<From = T, To = U>,
+        R2: Relation<From = T, To = U>,
+    {
+        let mut delta = Delta::default();
+        old.for_each_link(|from_idx, to_idx| {
+            if !new.contains_link(Link(from_idx, to_idx)) {
+                delta.removed.push(Link(from_idx, to_idx));
+            }
+        });
+        new.for_each_link(|from_idx, to_idx| {
+            if !old.contains_link(Link(from_idx, to_idx)) {
+                delta.added.push(Link(from_idx, to_idx));
+            }
+        });
+        delta
+    }
+
+    /// Returns `true` if the delta contains no change.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+// manual implementations, for the reason given for `Link`
+impl<T, U> Default for Delta<T, U> {
+    fn default() -> Self {
+        Delta {
+            added: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+}
+
+impl<T, U> Clone for Delta<T, U> {
+    fn clone(&self) -> Self {
+        Delta {
+            added: self.added.clone(),
+            removed: self.removed.clone(),
+        }
+    }
+}
+
+impl<T, U> std::fmt::Debug for Delta<T, U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Delta")
+            .field("added", &self.added)
+            .field("removed", &self.removed)
+            .finish()
+    }
+}
@@ -0,0 +1,392 @@
+use crate::relations::{get_corresponding, get_corresponding_within, shrink_map};
+use crate::{Delta, IdxMap, IdxRemaps, IdxSet, Link, ManyToMany, Relation};
+use std::sync::OnceLock;
+use typed_index_collection::Idx;
+
+/// A many to many relation storing its forward links only, the
+/// backward ones being computed on the first backward query, if any,
+/// e.g. to save half the memory of a relation queried forward only.
+///
+/// Modifying the relation drops its backward links, computed again
+/// on the next backward query.
+///
+/// The `BuildRelations` derive builds a shortcut as a
+/// `ForwardManyToMany` with the `backward = "lazy"` attribute.
+#[derive(Debug)]
+pub struct ForwardManyToMany<T, U> {
+    forward: IdxMap<Idx<T>, IdxSet<U>>,
+    backward: OnceLock<IdxMap<Idx<U>, IdxSet<T>>>,
+}
+
+impl<T, U> Default for ForwardManyToMany<T, U> {
+    fn default() -> Self {
+        ForwardManyToMany {
+            forward: Default::default(),
+            backward: Default::default(),
+        }
+    }
+}
+
+impl<T, U> ForwardManyToMany<T, U> {
+    /// Add a link between 2 indices.
+    pub fn add_link(&mut self, link: impl Into<Link<T, U>>) {
+        let Link(from, to) = link.into();
+        self.forward.entry(from).or_default().insert(to);
+        self.backward.take();
+    }
+
+    /// Removes a link, returning `true` if it was in the relation.
+    pub fn remove_link(&mut self, link: impl Into<Link<T, U>>) -> bool {
+        let Link(from, to) = link.into();
+        let targets = match self.forward.get_mut(&from) {
+            Some(targets) if targets.contains(&to) => targets,
+            _ => return false,
+        };
+        targets.remove(&to);
+        if targets.is_empty() {
+            self.forward.remove(&from);
+        }
+        self.backward.take();
+        true
+    }
+
+    /// Applies the removals then the additions of `delta`, and
+    /// returns the changes actually made.
+    pub fn apply_delta(&mut self, delta: Delta<T, U>) -> Delta<T, U> {
+        let mut applied = Delta::default();
+        for link in delta.removed {
+            if self.remove_link(link) {
+                applied.removed.push(link);
+            }
+        }
+        for link in delta.added {
+            if !self.contains_link(link) {
+                self.add_link(link);
+                applied.added.push(link);
+            }
+        }
+        applied
+    }
+
+    /// Returns an iterator over the links of the relation, sorted by
+    /// source with the default `BTreeMap` storage.
+    pub fn links(&self) -> impl Iterator<Item = Link<T, U>> + '_ {
+        self.forward
+            .iter()
+            .flat_map(|(&from, targets)| targets.iter().map(move |&to| Link(from, to)))
+    }
+
+    /// Adds the links of `other` to the relation, after remapping
+    /// their indices with `remaps`.
+    pub fn merge(&mut self, other: Self, remaps: &IdxRemaps)
+    where
+        T: 'static,
+        U: 'static,
+    {
+        for Link(from, to) in other.links() {
+            let (from, to) = (remaps.apply(from), remaps.apply(to));
+            self.forward.entry(from).or_default().insert(to);
+        }
+        self.backward.take();
+    }
+
+    /// Rebuilds the internal storage to reclaim the memory lost after
+    /// a heavy mutation of the relation. The backward links are
+    /// dropped, computed again on the next backward query.
+    pub fn shrink(&mut self) {
+        shrink_map(&mut self.forward);
+        self.backward.take();
+    }
+
+    /// Returns `true` if the backward links are computed, i.e. the
+    /// relation was queried backward since its last modification.
+    pub fn is_backward_built(&self) -> bool {
+        self.backward.get().is_some()
+    }
+
+    fn backward(&self) -> &IdxMap<Idx<U>, IdxSet<T>> {
+        self.backward.get_or_init(|| {
+            let mut backward: IdxMap<Idx<U>, IdxSet<T>> = IdxMap::default();
+            for Link(from, to) in self.links() {
+                backward.entry(to).or_default().insert(from);
+            }
+            backward
+        })
+    }
+}
+
+impl<T, U> From<ManyToMany<T, U>> for ForwardManyToMany<T, U> {
+    fn from(relation: ManyToMany<T, U>) -> Self {
+        let mut forward: IdxMap<Idx<T>, IdxSet<U>> = IdxMap::default();
+        relation.for_each_link(|from, to| {
+            forward.entry(from).or_default().insert(to);
+        });
+        ForwardManyToMany {
+            forward,
+            backward: OnceLock::new(),
+        }
+    }
+}
+
+impl<T, U> Relation for ForwardManyToMany<T, U> {
+    type From = T;
+    type To = U;
+    fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<T>> + '_> {
+        Box::new(self.forward.keys().cloned())
+    }
+    // the targets are collected from the forward links, without
+    // computing the backward ones
+    fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<U>> + '_> {
+        let targets: IdxSet<U> = self.forward.values().flatten().cloned().collect();
+        Box::new(targets.into_iter())
+    }
+    fn len_hint(&self) -> usize {
+        self.forward.values().map(|targets| targets.len()).sum()
+    }
+    fn get_corresponding_forward(&self, from: &IdxSet<T>) -> IdxSet<U> {
+        get_corresponding(&self.forward, from)
+    }
+    fn get_corresponding_backward(&self, from: &IdxSet<U>) -> IdxSet<T> {
+        get_corresponding(self.backward(), from)
+    }
+    fn get_corresponding_forward_within(&self, from: &IdxSet<T>, allowed: &IdxSet<U>) -> IdxSet<U> {
+        get_corresponding_within(&self.forward, from, allowed)
+    }
+    fn get_corresponding_backward_within(
+        &self,
+        from: &IdxSet<U>,
+        allowed: &IdxSet<T>,
+    ) -> IdxSet<T> {
+        get_corresponding_within(self.backward(), from, allowed)
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.forward
+            .get(&link.from())
+            .is_some_and(|targets| targets.contains(&link.to()))
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for (&from, targets) in &self.forward {
+            for &to in targets {
+                f(from, to);
+            }
+        }
+    }
+}
+
+/// A many to many relation storing its backward links only, e.g. to
+/// save half the memory of a relation queried backward only.
+///
+/// The forward queries are supported, but go through all the
+/// backward links of the relation.
+///
+/// The `BuildRelations` derive builds a shortcut as a
+/// `BackwardManyToMany` with the `forward = "computed"` attribute.
+#[derive(Debug)]
+pub struct BackwardManyToMany<T, U> {
+    backward: IdxMap<Idx<U>, IdxSet<T>>,
+}
+
+impl<T, U> Default for BackwardManyToMany<T, U> {
+    fn default() -> Self {
+        BackwardManyToMany {
+            backward: Default::default(),
+        }
+    }
+}
+
+impl<T, U> BackwardManyToMany<T, U> {
+    /// Add a link between 2 indices.
+    pub fn add_link(&mut self, link: impl Into<Link<T, U>>) {
+        let Link(from, to) = link.into();
+        self.backward.entry(to).or_default().insert(from);
+    }
+
+    /// Removes a link, returning `true` if it was in the relation.
+    pub fn remove_link(&mut self, link: impl Into<Link<T, U>>) -> bool {
+        let Link(from, to) = link.into();
+        let sources = match self.backward.get_mut(&to) {
+            Some(sources) if sources.contains(&from) => sources,
+            _ => return false,
+        };
+        sources.remove(&from);
+        if sources.is_empty() {
+            self.backward.remove(&to);
+        }
+        true
+    }
+
+    /// Applies the removals then the additions of `delta`, and
+    /// returns the changes actually made.
+    pub fn apply_delta(&mut self, delta: Delta<T, U>) -> Delta<T, U> {
+        let mut applied = Delta::default();
+        for link in delta.removed {
+            if self.remove_link(link) {
+                applied.removed.push(link);
+            }
+        }
+        for link in delta.added {
+            if !self.contains_link(link) {
+                self.add_link(link);
+                applied.added.push(link);
+            }
+        }
+        applied
+    }
+
+    /// Adds the links of `other` to the relation, after remapping
+    /// their indices with `remaps`.
+    pub fn merge(&mut self, other: Self, remaps: &IdxRemaps)
+    where
+        T: 'static,
+        U: 'static,
+    {
+        for Link(from, to) in other.links() {
+            let (from, to) = (remaps.apply(from), remaps.apply(to));
+            self.backward.entry(to).or_default().insert(from);
+        }
+    }
+
+    /// Rebuilds the internal storage to reclaim the memory lost after
+    /// a heavy mutation of the relation.
+    pub fn shrink(&mut self) {
+        shrink_map(&mut self.backward);
+    }
+
+    /// Returns an iterator over the links of the relation, sorted by
+    /// target with the default `BTreeMap` storage.
+    pub fn links(&self) -> impl Iterator<Item = Link<T, U>> + '_ {
+        self.backward
+            .iter()
+            .flat_map(|(&to, sources)| sources.iter().map(move |&from| Link(from, to)))
+    }
+}
+
+impl<T, U> From<ManyToMany<T, U>> for BackwardManyToMany<T, U> {
+    fn from(relation: ManyToMany<T, U>) -> Self {
+        let mut backward: IdxMap<Idx<U>, IdxSet<T>> = IdxMap::default();
+        relation.for_each_link(|from, to| {
+            backward.entry(to).or_default().insert(from);
+        });
+        BackwardManyToMany { backward }
+    }
+}
+
+impl<T, U> Relation for BackwardManyToMany<T, U> {
+    type From = T;
+    type To = U;
+    fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<T>> + '_> {
+        let sources: IdxSet<T> = self.backward.values().flatten().cloned().collect();
+        Box::new(sources.into_iter())
+    }
+    fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<U>> + '_> {
+        Box::new(self.backward.keys().cloned())
+    }
+    fn iter_links_by_target(&self) -> Box<dyn Iterator<Item = Link<T, U>> + '_> {
+        Box::new(self.links())
+    }
+    fn len_hint(&self) -> usize {
+        self.backward.values().map(|sources| sources.len()).sum()
+    }
+    fn get_corresponding_forward(&self, from: &IdxSet<T>) -> IdxSet<U> {
+        self.backward
+            .iter()
+            .filter(|(_, sources)| !sources.is_disjoint(from))
+            .map(|(&to, _)| to)
+            .collect()
+    }
+    fn get_corresponding_backward(&self, from: &IdxSet<U>) -> IdxSet<T> {
+        get_corresponding(&self.backward, from)
+    }
+    fn get_corresponding_forward_within(&self, from: &IdxSet<T>, allowed: &IdxSet<U>) -> IdxSet<U> {
+        allowed
+            .iter()
+            .filter(|to| {
+                self.backward
+                    .get(to)
+                    .is_some_and(|sources| !sources.is_disjoint(from))
+            })
+            .cloned()
+            .collect()
+    }
+    fn get_corresponding_backward_within(
+        &self,
+        from: &IdxSet<U>,
+        allowed: &IdxSet<T>,
+    ) -> IdxSet<T> {
+        get_corresponding_within(&self.backward, from, allowed)
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.backward
+            .get(&link.to())
+            .is_some_and(|sources| sources.contains(&link.from()))
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for (&to, sources) in &self.backward {
+            for &from in sources {
+                f(from, to);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn forward_merge_drops_the_backward_links() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        let mut relation = ForwardManyToMany::default();
+        relation.add_link(Link(a1, f1));
+        assert_eq!(
+            IdxSet::from([a1]),
+            relation.get_corresponding_backward(&IdxSet::from([f1]))
+        );
+        assert!(relation.is_backward_built());
+        let mut other = ForwardManyToMany::default();
+        other.add_link(Link(a1, f1));
+        let mut remaps = IdxRemaps::default();
+        remaps.insert(move |_: Idx<Animal>| a2);
+        remaps.insert(move |_: Idx<Feline>| f2);
+        relation.merge(other, &remaps);
+        assert!(!relation.is_backward_built());
+        assert_eq!(
+            IdxSet::from([a2]),
+            relation.get_corresponding_backward(&IdxSet::from([f2]))
+        );
+        assert_eq!(2, relation.links().count());
+        relation.shrink();
+        assert!(!relation.is_backward_built());
+        assert_eq!(vec![Link(a1, f1), Link(a2, f2)], relation.links_sorted());
+    }
+
+    #[test]
+    fn backward_merge() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        let mut relation = BackwardManyToMany::default();
+        relation.add_link(Link(a1, f1));
+        let mut other = BackwardManyToMany::default();
+        other.add_link(Link(a1, f1));
+        let mut remaps = IdxRemaps::default();
+        remaps.insert(move |_: Idx<Animal>| a2);
+        remaps.insert(move |_: Idx<Feline>| f2);
+        relation.merge(other, &remaps);
+        assert_eq!(
+            IdxSet::from([f2]),
+            relation.get_corresponding_forward(&IdxSet::from([a2]))
+        );
+        relation.shrink();
+        assert_eq!(vec![Link(a1, f1), Link(a2, f2)], relation.links_sorted());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{Error, IdxSet, Link, OneToMany, Relation};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use typed_index_collection::{CollectionWithId, Id, Idx};
+
+/// The index of an object that is either an `A` or a `B`.
+pub enum EitherIdx<A, B> {
+    /// Index of an `A`.
+    Left(Idx<A>),
+    /// Index of a `B`.
+    Right(Idx<B>),
+}
+
+impl<A, B> EitherIdx<A, B> {
+    /// Returns the index of the `A`, if any.
+    pub fn left(self) -> Option<Idx<A>> {
+        match self {
+            EitherIdx::Left(idx) => Some(idx),
+            EitherIdx::Right(_) => None,
+        }
+    }
+
+    /// Returns the index of the `B`, if any.
+    pub fn right(self) -> Option<Idx<B>> {
+        match self {
+            EitherIdx::Left(_) => None,
+            EitherIdx::Right(idx) => Some(idx),
+        }
+    }
+}
+
+// manual implementations, for the reason given for `Link`
+impl<A, B> Clone for EitherIdx<A, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A, B> Copy for EitherIdx<A, B> {}
+
+impl<A, B> PartialEq for EitherIdx<A, B> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<A, B> Eq for EitherIdx<A, B> {}
+
+impl<A, B> PartialOrd for EitherIdx<A, B> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A, B> Ord for EitherIdx<A, B> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (EitherIdx::Left(idx), EitherIdx::Left(other_idx)) => idx.cmp(other_idx),
+            (EitherIdx::Right(idx), EitherIdx::Right(other_idx)) => idx.cmp(other_idx),
+            (EitherIdx::Left(_), EitherIdx::Right(_)) => Ordering::Less,
+            (EitherIdx::Right(_), EitherIdx::Left(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl<A, B> Hash for EitherIdx<A, B> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            EitherIdx::Left(idx) => (0u8, idx).hash(state),
+            EitherIdx::Right(idx) => (1u8, idx).hash(state),
+        }
+    }
+}
+
+impl<A, B> fmt::Debug for EitherIdx<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EitherIdx::Left(idx) => f.debug_tuple("Left").field(idx).finish(),
+            EitherIdx::Right(idx) => f.debug_tuple("Right").field(idx).finish(),
+        }
+    }
+}
+
+/// A one to many relation whose targets are either `A`s or `B`s,
+/// e.g. a relation from a `Line` to the `StopPoint`s or `StopArea`s
+/// it serves. A target has one corresponding `T`.
+///
+/// The targets of each type form a `OneToMany`, available through
+/// `lefts` and `rights`, e.g. to be used as a `Relation`.
+#[derive(Debug)]
+pub struct OneToEitherMany<T, A, B> {
+    lefts: OneToMany<T, A>,
+    rights: OneToMany<T, B>,
+}
+
+impl<T, A, B> Default for OneToEitherMany<T, A, B> {
+    fn default() -> Self {
+        OneToEitherMany {
+            lefts: Default::default(),
+            rights: Default::default(),
+        }
+    }
+}
+
+impl<T, A, B> OneToEitherMany<T, A, B>
+where
+    T: Id<T>,
+    A: Id<A> + Id<T>,
+    B: Id<B> + Id<T>,
+{
+    /// Construct the relation automatically from the 3 given
+    /// `CollectionWithId`s.
+    pub fn new(
+        one: &CollectionWithId<T>,
+        lefts: &CollectionWithId<A>,
+        rights: &CollectionWithId<B>,
+        rel_name: &str,
+    ) -> Result<Self, Error> {
+        Ok(OneToEitherMany {
+            lefts: OneToMany::new(one, lefts, rel_name)?,
+            rights: OneToMany::new(one, rights, rel_name)?,
+        })
+    }
+}
+
+impl<T, A, B> OneToEitherMany<T, A, B> {
+    /// Constructor from the relations to each type of target.
+    pub fn from_relations(lefts: OneToMany<T, A>, rights: OneToMany<T, B>) -> Self {
+        OneToEitherMany { lefts, rights }
+    }
+
+    /// Returns the relation from `T` to the `A` targets.
+    pub fn lefts(&self) -> &OneToMany<T, A> {
+        &self.lefts
+    }
+
+    /// Returns the relation from `T` to the `B` targets.
+    pub fn rights(&self) -> &OneToMany<T, B> {
+        &self.rights
+    }
+
+    /// Add a link between 2 indices, replacing the previous source of
+    /// `to` if any.
+    pub fn add_link(&mut self, from: Idx<T>, to: EitherIdx<A, B>) {
+        match to {
+            EitherIdx::Left(to) => self.lefts.add_link(Link(from, to)),
+            EitherIdx::Right(to) => self.rights.add_link(Link(from, to)),
+        }
+    }
+
+    /// Removes a link, returning `true` if it was in the relation.
+    pub fn remove_link(&mut self, from: Idx<T>, to: EitherIdx<A, B>) -> bool {
+        match to {
+            EitherIdx::Left(to) => self.lefts.remove_link(Link(from, to)),
+            EitherIdx::Right(to) => self.rights.remove_link(Link(from, to)),
+        }
+    }
+
+    /// Returns an iterator over the children of `parent`, the `A`s
+    /// first.
+    pub fn children_of(&self, parent: Idx<T>) -> impl Iterator<Item = EitherIdx<A, B>> + '_ {
+        self.lefts
+            .children_of(parent)
+            .map(EitherIdx::Left)
+            .chain(self.rights.children_of(parent).map(EitherIdx::Right))
+    }
+
+    /// Returns the parent of `child`, if any.
+    pub fn parent_of(&self, child: EitherIdx<A, B>) -> Option<Idx<T>> {
+        match child {
+            EitherIdx::Left(child) => self.lefts.parent_of(child),
+            EitherIdx::Right(child) => self.rights.parent_of(child),
+        }
+    }
+
+    /// For a given set of `T`, returns the corresponding `A`s and
+    /// `B`s.
+    pub fn get_corresponding_forward(&self, from: &IdxSet<T>) -> (IdxSet<A>, IdxSet<B>) {
+        (
+            self.lefts.get_corresponding_forward(from),
+            self.rights.get_corresponding_forward(from),
+        )
+    }
+
+    /// For given sets of `A`s and `B`s, returns the corresponding
+    /// `T`s.
+    pub fn get_corresponding_backward(&self, lefts: &IdxSet<A>, rights: &IdxSet<B>) -> IdxSet<T> {
+        let mut from = self.lefts.get_corresponding_backward(lefts);
+        from.extend(self.rights.get_corresponding_backward(rights));
+        from
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn links_to_both_types_of_targets() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1")]);
+        let cats = cats(&[("c1", "f1"), ("c2", "f1")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let c1 = cats.get_idx("c1").unwrap();
+        let c2 = cats.get_idx("c2").unwrap();
+        let lefts = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        let mut relation = OneToEitherMany::from_relations(lefts, OneToMany::default());
+        relation.add_link(a1, EitherIdx::Right(c2));
+        relation.add_link(a1, EitherIdx::Right(c1));
+        relation.add_link(a2, EitherIdx::Right(c1));
+
+        // the `A`s first
+        assert_eq!(
+            vec![EitherIdx::Left(f1), EitherIdx::Right(c2)],
+            relation.children_of(a1).collect::<Vec<_>>()
+        );
+        // the previous parent is replaced
+        assert_eq!(Some(a2), relation.parent_of(EitherIdx::Right(c1)));
+        assert_eq!(Some(a1), relation.parent_of(EitherIdx::Left(f1)));
+        let only_a1: IdxSet<Animal> = Some(a1).into_iter().collect();
+        let (lefts, rights) = relation.get_corresponding_forward(&only_a1);
+        assert_eq!(Some(f1).into_iter().collect::<IdxSet<_>>(), lefts);
+        assert_eq!(Some(c2).into_iter().collect::<IdxSet<_>>(), rights);
+        let all_animals: IdxSet<Animal> = vec![a1, a2].into_iter().collect();
+        let rights: IdxSet<Cat> = vec![c1, c2].into_iter().collect();
+        assert_eq!(
+            all_animals,
+            relation.get_corresponding_backward(&IdxSet::default(), &rights)
+        );
+
+        assert!(relation.remove_link(a1, EitherIdx::Left(f1)));
+        assert!(!relation.remove_link(a1, EitherIdx::Right(c1)));
+        assert_eq!(None, relation.parent_of(EitherIdx::Left(f1)));
+        assert!(relation.lefts().links().next().is_none());
+        assert_eq!(2, relation.rights().links().count());
+    }
+
+    #[test]
+    fn lefts_come_before_rights() {
+        let animals = animals(&["a1"]);
+        let felines = felines(&[("f1", "a1")]);
+        let f1 = felines.get_idx("f1").unwrap();
+        let a1 = animals.get_idx("a1").unwrap();
+        let left: EitherIdx<Feline, Animal> = EitherIdx::Left(f1);
+        let right: EitherIdx<Feline, Animal> = EitherIdx::Right(a1);
+        assert!(left < right);
+        assert_eq!(Some(f1), left.left());
+        assert_eq!(None, left.right());
+        assert_eq!(Some(a1), right.right());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{IdxSet, Link, ManyToMany, OneToMany};
+use std::ops::{Deref, DerefMut};
+use typed_index_collection::Idx;
+
+/// A guard giving mutable access to the children of one `T` of a
+/// `OneToMany`, returned by `OneToMany::entry`.
+///
+/// The relation is updated when the guard is dropped. A `U` added to
+/// the set is detached from its previous parent, if any.
+pub struct OneToManyEntry<'a, T, U> {
+    relation: &'a mut OneToMany<T, U>,
+    from: Idx<T>,
+    original: IdxSet<U>,
+    children: IdxSet<U>,
+}
+
+impl<'a, T, U> OneToManyEntry<'a, T, U> {
+    pub(crate) fn new(relation: &'a mut OneToMany<T, U>, from: Idx<T>) -> Self {
+        let original = relation.children(from).cloned().unwrap_or_default();
+        OneToManyEntry {
+            relation,
+            from,
+            children: original.clone(),
+            original,
+        }
+    }
+
+    /// Returns the `T` whose children are edited.
+    pub fn from(&self) -> Idx<T> {
+        self.from
+    }
+}
+
+impl<T, U> Deref for OneToManyEntry<'_, T, U> {
+    type Target = IdxSet<U>;
+
+    fn deref(&self) -> &IdxSet<U> {
+        &self.children
+    }
+}
+
+impl<T, U> DerefMut for OneToManyEntry<'_, T, U> {
+    fn deref_mut(&mut self) -> &mut IdxSet<U> {
+        &mut self.children
+    }
+}
+
+impl<T, U> Drop for OneToManyEntry<'_, T, U> {
+    fn drop(&mut self) {
+        for &to in self.original.difference(&self.children) {
+            self.relation.remove_link(Link(self.from, to));
+        }
+        for &to in self.children.difference(&self.original) {
+            self.relation.add_link(Link(self.from, to));
+        }
+    }
+}
+
+/// A guard giving mutable access to the `U` linked from one `T` of a
+/// `ManyToMany`, returned by `ManyToMany::entry`.
+///
+/// The relation, including its backward map, is updated when the
+/// guard is dropped.
+pub struct ManyToManyEntry<'a, T, U> {
+    relation: &'a mut ManyToMany<T, U>,
+    from: Idx<T>,
+    original: IdxSet<U>,
+    to: IdxSet<U>,
+}
+
+impl<'a, T, U> ManyToManyEntry<'a, T, U> {
+    pub(crate) fn new(relation: &'a mut ManyToMany<T, U>, from: Idx<T>) -> Self {
+        let original = relation.forward_of(from).cloned().unwrap_or_default();
+        ManyToManyEntry {
+            relation,
+            from,
+            to: original.clone(),
+            original,
+        }
+    }
+
+    /// Returns the `T` whose links are edited.
+    pub fn from(&self) -> Idx<T> {
+        self.from
+    }
+}
+
+impl<T, U> Deref for ManyToManyEntry<'_, T, U> {
+    type Target = IdxSet<U>;
+
+    fn deref(&self) -> &IdxSet<U> {
+        &self.to
+    }
+}
+
+impl<T, U> DerefMut for ManyToManyEntry<'_, T, U> {
+    fn deref_mut(&mut self) -> &mut IdxSet<U> {
+        &mut self.to
+    }
+}
+
+impl<T, U> Drop for ManyToManyEntry<'_, T, U> {
+    fn drop(&mut self) {
+        for &to in self.original.difference(&self.to) {
+            self.relation.remove_link(Link(self.from, to));
+        }
+        for &to in self.to.difference(&self.original) {
+            self.relation.add_link(Link(self.from, to));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+    use crate::{IdxSet, Link, ManyToMany, OneToMany};
+
+    #[test]
+    fn child_added_to_an_entry_leaves_its_parent() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        let mut relation = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        {
+            let mut entry = relation.entry(a1);
+            entry.insert(f2);
+            entry.remove(&f1);
+            // the relation is updated on drop only
+        }
+        assert_eq!(Some(a1), relation.parent_of(f2));
+        assert_eq!(None, relation.parent_of(f1));
+        assert!(relation.children(a2).is_none_or(IdxSet::is_empty));
+    }
+
+    #[test]
+    fn many_to_many_entry_updates_the_backward_links() {
+        let animals = animals(&["a1"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a1")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        let mut relation = ManyToMany::default();
+        relation.add_link(Link(a1, f1));
+        {
+            let mut entry = relation.entry(a1);
+            entry.remove(&f1);
+            entry.insert(f2);
+        }
+        assert!(relation.backward_of(f1).is_none_or(IdxSet::is_empty));
+        assert_eq!(
+            Some(&Some(a1).into_iter().collect()),
+            relation.backward_of(f2)
+        );
+    }
+}
@@ -1,9 +1,232 @@
-use thiserror::Error;
+use crate::IdxSet;
+use std::fmt;
+use typed_index_collection::Idx;
 
-#[derive(Error, Debug)]
+#[derive(Debug)]
 /// Typed error for `collections`.
 pub enum Error {
     /// This error occurs when an identifier is not in a `CollectionWithId`.
-    #[error("identifier {0} not found while building relation {1}")]
     IdentifierNotFound(String, String),
+    /// This error occurs when a strict construction detects
+    /// inconsistent data.
+    Anomalies(String, Vec<Anomaly>),
+    /// This error occurs when an index does not belong to a
+    /// `CollectionWithId`.
+    IndexOutOfBounds(usize, usize),
+    /// This error occurs when a link does not match the identifiers
+    /// of the objects.
+    InconsistentLink(String, String, String),
+    /// This error occurs when a relation required to be non empty
+    /// contains no link.
+    EmptyRelation(String),
+    /// This error occurs when some parents of a relation requiring
+    /// at least one child per parent have no child.
+    ChildlessParents(String, Vec<String>),
+    /// This error occurs when a model has no relation between the
+    /// given types.
+    UnknownRelation(String, String),
+    /// This error occurs when a model has several relations between
+    /// the given types, e.g. when rebuilding one of them by types.
+    AmbiguousRelation(String, String, Vec<String>),
+    /// This error occurs when a change recorded by a `WorldLog` names
+    /// a relation of the model which can't be changed, or links
+    /// objects of other types.
+    UnloggableRelation(String),
+    /// This error occurs when a rule of a `rules::Program` can't be
+    /// parsed.
+    InvalidRule(String, String),
+    /// This error occurs when a `RelationsArchive` can't be parsed.
+    InvalidArchive(String),
+    /// This error occurs when a line read by
+    /// `ReplayLog::import_links_jsonl` is not a `LinkRecord`.
+    InvalidRecord(String),
+    /// This error occurs when a `RelationsArchive` was written with
+    /// another version of the format.
+    IncompatibleArchiveFormat(u32, u32),
+    /// This error occurs when a `RelationsArchive` of a version of the
+    /// model can't be upgraded to the current one.
+    IncompatibleArchive(u32, u32),
+    /// This error occurs when the links of a relation of a
+    /// `RelationsArchive` don't match their checksum.
+    CorruptedArchive(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+        match self {
+            IdentifierNotFound(id, relation) => write!(
+                f,
+                "identifier {} not found while building relation {}",
+                id, relation
+            ),
+            Anomalies(relation, anomalies) => write!(
+                f,
+                "{} anomalies found while building relation {}",
+                anomalies.len(),
+                relation
+            ),
+            IndexOutOfBounds(index, len) => write!(
+                f,
+                "index {} out of bounds of a collection of {} objects",
+                index, len
+            ),
+            InconsistentLink(object, expected, referenced) => write!(
+                f,
+                "object {} references {}, not {}",
+                object, referenced, expected
+            ),
+            EmptyRelation(relation) => write!(f, "relation {} is empty", relation),
+            ChildlessParents(relation, parents) => write!(
+                f,
+                "{} parents without children in relation {}: {}",
+                parents.len(),
+                relation,
+                parents.join(", ")
+            ),
+            UnknownRelation(from, to) => write!(f, "no relation between {} and {}", from, to),
+            AmbiguousRelation(from, to, relations) => write!(
+                f,
+                "several relations between {} and {}: {}",
+                from,
+                to,
+                relations.join(", ")
+            ),
+            UnloggableRelation(relation) => {
+                write!(f, "relation {} can't be changed by a log", relation)
+            }
+            InvalidRule(rule, reason) => write!(f, "invalid rule {}: {}", rule, reason),
+            InvalidArchive(reason) => write!(f, "invalid archive: {}", reason),
+            InvalidRecord(reason) => write!(f, "invalid link record: {}", reason),
+            IncompatibleArchiveFormat(version, expected) => write!(
+                f,
+                "archive format version {} is not supported, expected {}",
+                version, expected
+            ),
+            IncompatibleArchive(version, current) => write!(
+                f,
+                "archive of version {} can't be migrated to version {}",
+                version, current
+            ),
+            CorruptedArchive(relation) => write!(
+                f,
+                "checksum mismatch for relation {} of the archive",
+                relation
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An inconsistency detected while building a relation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    /// The parent identifier referenced by a child does not exist.
+    UnknownParent {
+        /// Identifier of the child.
+        child_id: String,
+        /// Identifier of the missing parent.
+        parent_id: String,
+    },
+    /// The child identifier of an additional link does not exist.
+    UnknownChild(String),
+    /// A child resolves to several distinct parents.
+    ConflictingParents {
+        /// Identifier of the child.
+        child_id: String,
+        /// Identifiers of all the parents of the child.
+        parent_ids: Vec<String>,
+    },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::UnknownParent {
+                child_id,
+                parent_id,
+            } => write!(
+                f,
+                "child {} references unknown parent {}",
+                child_id, parent_id
+            ),
+            Anomaly::UnknownChild(child_id) => write!(f, "unknown child {}", child_id),
+            Anomaly::ConflictingParents {
+                child_id,
+                parent_ids,
+            } => write!(
+                f,
+                "child {} has several parents: {}",
+                child_id,
+                parent_ids.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Anomaly {}
+
+/// The error returned by the `try_get_corresponding_from_idx` method
+/// of the `GetCorresponding` derive, when the index is linked by no
+/// relation of the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownIndex {
+    /// The raw index.
+    pub index: usize,
+    /// Name of the type of the object.
+    pub type_name: &'static str,
+}
+
+impl fmt::Display for UnknownIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "index {} of {} is not linked by any relation",
+            self.index, self.type_name
+        )
+    }
+}
+
+impl std::error::Error for UnknownIndex {}
+
+/// What the `get_corresponding_with` method of the `GetCorresponding`
+/// derive does with an index linked by no relation of the model, e.g.
+/// a stale index, to tell it from an object without any corresponding
+/// object.
+pub enum MissingIndex<'a, T, U> {
+    /// The index gives no corresponding object, as with
+    /// `get_corresponding`.
+    Ignore,
+    /// The traversal fails with an `UnknownIndex` error.
+    Error,
+    /// The corresponding objects of the index are given by the closure.
+    Fallback(&'a dyn Fn(Idx<T>) -> IdxSet<U>),
+}
+
+/// The errors found by the `build_partial` constructor of the
+/// `BuildRelations` derive, which leaves empty the relations it can't
+/// build instead of stopping at the first error.
+#[derive(Debug, Default)]
+pub struct WorldBuildReport {
+    /// The relations which can't be built, with their error, in the
+    /// order of declaration.
+    pub failures: Vec<(&'static str, Error)>,
+}
+
+impl WorldBuildReport {
+    /// Returns `true` if every relation was built.
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl std::fmt::Display for WorldBuildReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} relations can't be built", self.failures.len())?;
+        for (relation, error) in &self.failures {
+            write!(f, "\n- {}: {}", relation, error)?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,110 @@
+//! C bindings, running the traversals of a model from its
+//! `RelationsArchive` in other languages.
+//!
+//! ```c
+//! RelationsArchive *archive = relational_types_archive_read(bytes, len, 1);
+//! size_t brand = 0, len_owners;
+//! size_t *owners = relational_types_archive_traverse(
+//!     archive, "brands_to_bikes,-owners_to_bikes", &brand, 1, &len_owners);
+//! relational_types_indices_free(owners, len_owners);
+//! relational_types_archive_free(archive);
+//! ```
+
+use crate::{Migrations, RelationsArchive};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+/// Reads the archive written by `RelationsArchive::write` in the `len`
+/// bytes at `bytes`, for the version `version` of the model, and
+/// returns it, or a null pointer if it can't be read. It must be freed
+/// by `relational_types_archive_free`.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn relational_types_archive_read(
+    bytes: *const u8,
+    len: usize,
+    version: u32,
+) -> *mut RelationsArchive {
+    if bytes.is_null() {
+        return std::ptr::null_mut();
+    }
+    let bytes = std::slice::from_raw_parts(bytes, len);
+    match RelationsArchive::read(bytes, &Migrations::new(version)) {
+        Ok(archive) => Box::into_raw(Box::new(archive)),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Frees an archive returned by `relational_types_archive_read`.
+///
+/// # Safety
+///
+/// `archive` must be null or returned by
+/// `relational_types_archive_read`, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn relational_types_archive_free(archive: *mut RelationsArchive) {
+    if !archive.is_null() {
+        drop(Box::from_raw(archive));
+    }
+}
+
+/// Returns the raw indices reached from the `from_len` raw indices at
+/// `from` by following the relations of `path`, as
+/// `RelationsArchive::traverse_path`, and writes their number to
+/// `reached_len`. Returns a null pointer if `path` is not UTF-8 or
+/// names an unknown relation. The indices must be freed by
+/// `relational_types_indices_free`.
+///
+/// # Safety
+///
+/// `archive` must be returned by `relational_types_archive_read`,
+/// `path` must be a nul terminated string, `from` must point to
+/// `from_len` indices, and `reached_len` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn relational_types_archive_traverse(
+    archive: *const RelationsArchive,
+    path: *const c_char,
+    from: *const usize,
+    from_len: usize,
+    reached_len: *mut usize,
+) -> *mut usize {
+    if archive.is_null() || path.is_null() || reached_len.is_null() {
+        return std::ptr::null_mut();
+    }
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(_) => return std::ptr::null_mut(),
+    };
+    let from = if from_len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(from, from_len)
+    };
+    match (*archive).traverse_path(path, from) {
+        Some(reached) => {
+            *reached_len = reached.len();
+            Box::into_raw(reached.into_boxed_slice()) as *mut usize
+        }
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Frees the `len` indices returned by
+/// `relational_types_archive_traverse`.
+///
+/// # Safety
+///
+/// `indices` must be null or returned by
+/// `relational_types_archive_traverse`, with their number `len`, and
+/// not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn relational_types_indices_free(indices: *mut usize, len: usize) {
+    if !indices.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            indices, len,
+        )));
+    }
+}
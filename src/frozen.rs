@@ -0,0 +1,142 @@
+use crate::{IdxSet, Link, Relation};
+use typed_index_collection::Idx;
+
+/// The links of a relation from each of its source objects, in
+/// compressed sparse row format.
+#[derive(Debug)]
+struct Adjacency<T, U> {
+    // the neighbours of the object of raw index `i` are
+    // `neighbours[offsets[i]..offsets[i + 1]]`, sorted by index
+    offsets: Vec<usize>,
+    neighbours: Vec<Idx<U>>,
+    // the objects having at least one neighbour, sorted by index
+    sources: Vec<Idx<T>>,
+}
+
+impl<T, U> Adjacency<T, U> {
+    /// Constructor from links sorted by source then by target.
+    fn new(links: impl Iterator<Item = (Idx<T>, Idx<U>)>) -> Self {
+        let mut adjacency = Adjacency {
+            offsets: vec![0],
+            neighbours: Vec::new(),
+            sources: Vec::new(),
+        };
+        for (from, to) in links {
+            if adjacency.sources.last() != Some(&from) {
+                adjacency.sources.push(from);
+                adjacency
+                    .offsets
+                    .resize(from.get() + 1, adjacency.neighbours.len());
+            }
+            adjacency.neighbours.push(to);
+        }
+        adjacency.offsets.push(adjacency.neighbours.len());
+        adjacency
+    }
+
+    fn neighbours(&self, idx: Idx<T>) -> &[Idx<U>] {
+        match (self.offsets.get(idx.get()), self.offsets.get(idx.get() + 1)) {
+            (Some(&begin), Some(&end)) => &self.neighbours[begin..end],
+            _ => &[],
+        }
+    }
+
+    fn get_corresponding(&self, from: &IdxSet<T>) -> IdxSet<U> {
+        from.iter()
+            .flat_map(|&idx| self.neighbours(idx))
+            .cloned()
+            .collect()
+    }
+
+    fn get_corresponding_within(&self, from: &IdxSet<T>, allowed: &IdxSet<U>) -> IdxSet<U> {
+        from.iter()
+            .flat_map(|&idx| self.neighbours(idx))
+            .filter(|idx| allowed.contains(idx))
+            .cloned()
+            .collect()
+    }
+}
+
+/// An immutable relation, built once from another relation, e.g. to
+/// serve the traversals of a model that won't change anymore.
+///
+/// The links are stored in contiguous arrays, indexed by the raw
+/// indices of the objects, thus the traversals are faster than with
+/// a `ManyToMany`, and the relation can't be modified by mistake.
+///
+/// It is used by the frozen models returned by the `freeze` method
+/// generated by the `GetCorresponding` derive.
+#[derive(Debug)]
+pub struct FrozenRelation<T, U> {
+    forward: Adjacency<T, U>,
+    backward: Adjacency<U, T>,
+}
+
+impl<T, U> FrozenRelation<T, U> {
+    /// Freezes the links of `relation`.
+    pub fn new<R>(relation: &R) -> Self
+    where
+        R: Relation<From = T, To = U>,
+    {
+        let mut links = Vec::with_capacity(relation.len_hint());
+        relation.for_each_link(|from, to| links.push((from, to)));
+        links.sort_unstable();
+        links.dedup();
+        let forward = Adjacency::new(links.iter().cloned());
+        links.sort_unstable_by_key(|&(from, to)| (to, from));
+        let backward = Adjacency::new(links.into_iter().map(|(from, to)| (to, from)));
+        FrozenRelation { forward, backward }
+    }
+
+    /// Returns the objects linked to `from`, sorted by index.
+    pub fn targets_of(&self, from: Idx<T>) -> &[Idx<U>] {
+        self.forward.neighbours(from)
+    }
+
+    /// Returns the objects linked to `to`, sorted by index.
+    pub fn sources_of(&self, to: Idx<U>) -> &[Idx<T>] {
+        self.backward.neighbours(to)
+    }
+}
+
+impl<T, U> Relation for FrozenRelation<T, U> {
+    type From = T;
+    type To = U;
+    fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<T>> + '_> {
+        Box::new(self.forward.sources.iter().cloned())
+    }
+    fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<U>> + '_> {
+        Box::new(self.backward.sources.iter().cloned())
+    }
+    fn len_hint(&self) -> usize {
+        self.forward.neighbours.len()
+    }
+    fn get_corresponding_forward(&self, from: &IdxSet<T>) -> IdxSet<U> {
+        self.forward.get_corresponding(from)
+    }
+    fn get_corresponding_backward(&self, from: &IdxSet<U>) -> IdxSet<T> {
+        self.backward.get_corresponding(from)
+    }
+    fn get_corresponding_forward_within(&self, from: &IdxSet<T>, allowed: &IdxSet<U>) -> IdxSet<U> {
+        self.forward.get_corresponding_within(from, allowed)
+    }
+    fn get_corresponding_backward_within(
+        &self,
+        from: &IdxSet<U>,
+        allowed: &IdxSet<T>,
+    ) -> IdxSet<T> {
+        self.backward.get_corresponding_within(from, allowed)
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.targets_of(link.from())
+            .binary_search(&link.to())
+            .is_ok()
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for &from in &self.forward.sources {
+            for &to in self.targets_of(from) {
+                f(from, to);
+            }
+        }
+    }
+}
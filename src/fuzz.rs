@@ -0,0 +1,192 @@
+use crate::{
+    BackwardManyToMany, ForwardManyToMany, Link, ManyToMany, ManyToOne, OneToMany, Relation,
+};
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use std::iter::once;
+
+/// A relation that can be mutated randomly, checking its internal
+/// consistency after each mutation.
+///
+/// It is used by the `apply_random_ops` method generated by the
+/// `GetCorresponding` derive with the `fuzz` feature.
+pub trait Fuzz {
+    /// Adds or removes a link between objects already in the
+    /// relation.
+    fn apply_random_op<R: Rng + ?Sized>(&mut self, rng: &mut R);
+
+    /// Panics if the internal maps of the relation are inconsistent.
+    fn assert_invariants(&self);
+}
+
+impl<T, U> Fuzz for OneToMany<T, U> {
+    fn apply_random_op<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        if rng.gen_bool(0.5) {
+            let from = self.iter_from().choose(rng);
+            let to = self.iter_to().choose(rng);
+            if let (Some(from), Some(to)) = (from, to) {
+                self.add_link(Link(from, to));
+            }
+        } else if let Some(link) = self.links().choose(rng) {
+            assert!(self.remove_link(link), "{:?} is not removed", link);
+        }
+    }
+
+    fn assert_invariants(&self) {
+        for (from, children) in self.iter_from().map(|from| (from, self.children(from))) {
+            let children = children.expect("a source has children");
+            assert!(!children.is_empty(), "{:?} has no child", from);
+            for &child in children {
+                assert_eq!(
+                    Some(from),
+                    self.parent_of(child),
+                    "wrong parent of {:?}",
+                    child
+                );
+            }
+        }
+        assert_eq!(self.links().count(), self.iter_to().count());
+    }
+}
+
+impl<T, U> Fuzz for ManyToOne<T, U> {
+    fn apply_random_op<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let mut inverse = self.invert();
+        inverse.apply_random_op(rng);
+        *self = inverse.invert();
+    }
+
+    fn assert_invariants(&self) {
+        self.invert().assert_invariants()
+    }
+}
+
+impl<T, U> Fuzz for ManyToMany<T, U> {
+    fn apply_random_op<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        if rng.gen_bool(0.5) {
+            let from = self.iter_from().choose(rng);
+            let to = self.iter_to().choose(rng);
+            if let (Some(from), Some(to)) = (from, to) {
+                self.add_link(Link(from, to));
+            }
+        } else if let Some(link) = self.links().choose(rng) {
+            assert!(self.remove_link(link), "{:?} is not removed", link);
+        }
+    }
+
+    fn assert_invariants(&self) {
+        let mut nb_links = 0;
+        for link in self.links() {
+            let sources = self.backward_of(link.to()).expect("a target has sources");
+            assert!(sources.contains(&link.from()), "{:?} is not backward", link);
+            nb_links += 1;
+        }
+        let nb_backward_links: usize = self
+            .iter_to()
+            .map(|to| self.backward_of(to).expect("a target has sources").len())
+            .sum();
+        // the sources without targets, e.g. kept by
+        // `from_relations_sink`, are valid, thus only the links are
+        // checked
+        assert_eq!(nb_links, nb_backward_links);
+    }
+}
+
+impl<T, U> Fuzz for ForwardManyToMany<T, U> {
+    fn apply_random_op<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        if rng.gen_bool(0.5) {
+            let from = self.iter_from().choose(rng);
+            let to = self.iter_to().choose(rng);
+            if let (Some(from), Some(to)) = (from, to) {
+                self.add_link(Link(from, to));
+            }
+        } else if let Some(link) = self.links().choose(rng) {
+            assert!(self.remove_link(link), "{:?} is not removed", link);
+        }
+    }
+
+    fn assert_invariants(&self) {
+        for from in self.iter_from() {
+            let targets = self.get_corresponding_forward(&once(from).collect());
+            assert!(!targets.is_empty(), "{:?} has no target", from);
+        }
+        // querying backward computes the backward links from the
+        // forward ones, thus both must agree
+        for link in self.links() {
+            let sources = self.get_corresponding_backward(&once(link.to()).collect());
+            assert!(sources.contains(&link.from()), "{:?} is not backward", link);
+        }
+    }
+}
+
+impl<T, U> Fuzz for BackwardManyToMany<T, U> {
+    fn apply_random_op<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        if rng.gen_bool(0.5) {
+            let from = self.iter_from().choose(rng);
+            let to = self.iter_to().choose(rng);
+            if let (Some(from), Some(to)) = (from, to) {
+                self.add_link(Link(from, to));
+            }
+        } else if let Some(link) = self.links().choose(rng) {
+            assert!(self.remove_link(link), "{:?} is not removed", link);
+        }
+    }
+
+    fn assert_invariants(&self) {
+        for to in self.iter_to() {
+            let sources = self.get_corresponding_backward(&once(to).collect());
+            assert!(!sources.is_empty(), "{:?} has no source", to);
+        }
+        for link in self.links() {
+            let targets = self.get_corresponding_forward(&once(link.from()).collect());
+            assert!(targets.contains(&link.to()), "{:?} is not forward", link);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn fuzz_relation_from_relations_sink() {
+        // `a2` has the feline `f2` without any cat, thus no cat
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let cats = cats(&[("c1", "f1"), ("c2", "f1")]);
+        let animals_to_felines = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        let felines_to_cats = OneToMany::new(&felines, &cats, "felines_to_cats").unwrap();
+        let cats_to_felines = felines_to_cats.invert();
+        let mut relation = ManyToMany::from_relations_sink(&animals_to_felines, &cats_to_felines);
+        let a2 = animals.get_idx("a2").unwrap();
+        assert!(relation.forward_of(a2).is_some_and(|to| to.is_empty()));
+        relation.assert_invariants();
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            relation.apply_random_op(&mut rng);
+            relation.assert_invariants();
+        }
+    }
+
+    #[test]
+    fn fuzz_directed_relations() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2"), ("f3", "a2")]);
+        let animals_to_felines = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        let copy = animals_to_felines.invert().invert();
+        let mut forward = ForwardManyToMany::from(ManyToMany::from(animals_to_felines));
+        let mut backward = BackwardManyToMany::from(ManyToMany::from(copy));
+        forward.assert_invariants();
+        backward.assert_invariants();
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            forward.apply_random_op(&mut rng);
+            forward.assert_invariants();
+            backward.apply_random_op(&mut rng);
+            backward.assert_invariants();
+        }
+    }
+}
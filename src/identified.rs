@@ -0,0 +1,234 @@
+use crate::{Error, IdxSet, Link, ManyToMany, Relation};
+use std::collections::BTreeMap;
+use typed_index_collection::{CollectionWithId, Idx};
+
+/// Identifier of a link of an `IdentifiedManyToMany`, given at its
+/// insertion and never reused, unlike the indices of its objects
+/// which shift when their collections change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkId(pub u32);
+
+/// A many to many relation giving a `LinkId` to each link, so that
+/// external systems, e.g. annotations stored in a database, can
+/// reference the links.
+#[derive(Debug)]
+pub struct IdentifiedManyToMany<T, U> {
+    relation: ManyToMany<T, U>,
+    next_id: u32,
+    ids: BTreeMap<Link<T, U>, LinkId>,
+    links: BTreeMap<LinkId, Link<T, U>>,
+}
+
+impl<T, U> Default for IdentifiedManyToMany<T, U> {
+    fn default() -> Self {
+        IdentifiedManyToMany {
+            relation: Default::default(),
+            next_id: Default::default(),
+            ids: Default::default(),
+            links: Default::default(),
+        }
+    }
+}
+
+/// The links of an `IdentifiedManyToMany` with their identifiers,
+/// the objects being given by their raw indices, e.g. to store them
+/// with the `serde` feature and restore the relation with the same
+/// identifiers.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdentifiedLinks {
+    /// Identifier of the next added link.
+    pub next_id: u32,
+    /// Identifiers and raw indices of the links, sorted by identifier.
+    pub links: Vec<(LinkId, usize, usize)>,
+}
+
+impl<T, U> IdentifiedManyToMany<T, U> {
+    /// Adds a link, returning its identifier, the one it already had
+    /// if it was in the relation.
+    ///
+    /// Panics if `u32::MAX` links were already added.
+    pub fn add_link(&mut self, link: impl Into<Link<T, U>>) -> LinkId {
+        let link = link.into();
+        if let Some(&id) = self.ids.get(&link) {
+            return id;
+        }
+        let id = LinkId(self.next_id);
+        self.next_id = self.next_id.checked_add(1).expect("too many links added");
+        self.ids.insert(link, id);
+        self.links.insert(id, link);
+        self.relation.add_link(link);
+        id
+    }
+
+    /// Removes a link, returning `true` if it was in the relation. Its
+    /// identifier is not given to another link.
+    pub fn remove_link(&mut self, link: impl Into<Link<T, U>>) -> bool {
+        let link = link.into();
+        let id = match self.ids.remove(&link) {
+            Some(id) => id,
+            None => return false,
+        };
+        self.links.remove(&id);
+        self.relation.remove_link(link);
+        true
+    }
+
+    /// Returns the link identified by `id`, if it is in the relation.
+    pub fn link_by_id(&self, id: LinkId) -> Option<Link<T, U>> {
+        self.links.get(&id).copied()
+    }
+
+    /// Returns the identifier of `link`, if it is in the relation.
+    pub fn id_of_link(&self, link: Link<T, U>) -> Option<LinkId> {
+        self.ids.get(&link).copied()
+    }
+
+    /// Returns the links with their identifiers.
+    pub fn identified_links(&self) -> IdentifiedLinks {
+        IdentifiedLinks {
+            next_id: self.next_id,
+            links: self
+                .links
+                .iter()
+                .map(|(&id, link)| (id, link.from().get(), link.to().get()))
+                .collect(),
+        }
+    }
+
+    /// Restores the relation from the links returned by
+    /// `identified_links`, their raw indices being the ones of the
+    /// `from` and `to` collections.
+    pub fn from_identified_links(
+        identified: &IdentifiedLinks,
+        from: &CollectionWithId<T>,
+        to: &CollectionWithId<U>,
+    ) -> Result<Self, Error> {
+        let from_indices: Vec<Idx<T>> = from.iter().map(|(idx, _)| idx).collect();
+        let to_indices: Vec<Idx<U>> = to.iter().map(|(idx, _)| idx).collect();
+        let mut relation = IdentifiedManyToMany {
+            next_id: identified.next_id,
+            ..Default::default()
+        };
+        for &(id, from_raw, to_raw) in &identified.links {
+            let from_idx = *from_indices
+                .get(from_raw)
+                .ok_or(Error::IndexOutOfBounds(from_raw, from_indices.len()))?;
+            let to_idx = *to_indices
+                .get(to_raw)
+                .ok_or(Error::IndexOutOfBounds(to_raw, to_indices.len()))?;
+            let link = Link(from_idx, to_idx);
+            if id.0 >= identified.next_id
+                || relation.links.contains_key(&id)
+                || relation.ids.contains_key(&link)
+            {
+                return Err(Error::InvalidRecord(format!(
+                    "link {} ({}, {}) is duplicated or its identifier is not below {}",
+                    id.0, from_raw, to_raw, identified.next_id
+                )));
+            }
+            relation.ids.insert(link, id);
+            relation.links.insert(id, link);
+            relation.relation.add_link(link);
+        }
+        Ok(relation)
+    }
+
+    /// Returns the relation without the identifiers.
+    pub fn relation(&self) -> &ManyToMany<T, U> {
+        &self.relation
+    }
+}
+
+impl<T, U> Relation for IdentifiedManyToMany<T, U> {
+    type From = T;
+    type To = U;
+    forward_relation!(
+        relation,
+        iter_from,
+        iter_to,
+        get_corresponding_forward,
+        get_corresponding_backward,
+        get_corresponding_forward_within,
+        get_corresponding_backward_within
+    );
+    fn len_hint(&self) -> usize {
+        self.ids.len()
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.ids.contains_key(&link)
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for link in self.ids.keys() {
+            f(link.from(), link.to());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn identifiers_round_trip() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        let mut relation = IdentifiedManyToMany::default();
+        let removed = relation.add_link(Link(a1, f1));
+        let kept = relation.add_link(Link(a1, f2));
+        let last = relation.add_link(Link(a2, f2));
+        relation.remove_link(Link(a1, f1));
+        let identified = relation.identified_links();
+        assert_eq!(3, identified.next_id);
+
+        let mut restored =
+            IdentifiedManyToMany::from_identified_links(&identified, &animals, &felines).unwrap();
+        assert_eq!(identified, restored.identified_links());
+        assert_eq!(Some(Link(a1, f2)), restored.link_by_id(kept));
+        assert_eq!(Some(last), restored.id_of_link(Link(a2, f2)));
+        assert_eq!(None, restored.link_by_id(removed));
+        // the identifiers are not reused after the restoration
+        assert_eq!(LinkId(3), restored.add_link(Link(a1, f1)));
+    }
+
+    fn restore(
+        links: Vec<(LinkId, usize, usize)>,
+    ) -> Result<IdentifiedManyToMany<Animal, Feline>, Error> {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let identified = IdentifiedLinks { next_id: 2, links };
+        IdentifiedManyToMany::from_identified_links(&identified, &animals, &felines)
+    }
+
+    #[test]
+    fn from_identified_links_rejects_duplicated_identifiers() {
+        let restored = restore(vec![(LinkId(0), 0, 0), (LinkId(0), 1, 1)]);
+        assert!(matches!(restored, Err(Error::InvalidRecord(_))));
+    }
+
+    #[test]
+    fn from_identified_links_rejects_duplicated_links() {
+        let restored = restore(vec![(LinkId(0), 0, 0), (LinkId(1), 0, 0)]);
+        assert!(matches!(restored, Err(Error::InvalidRecord(_))));
+    }
+
+    #[test]
+    fn from_identified_links_rejects_identifiers_from_next_id() {
+        let restored = restore(vec![(LinkId(2), 0, 0)]);
+        assert!(matches!(restored, Err(Error::InvalidRecord(_))));
+    }
+
+    #[test]
+    fn from_identified_links_rejects_out_of_bounds_indices() {
+        let restored = restore(vec![(LinkId(0), 2, 0)]);
+        assert!(matches!(restored, Err(Error::IndexOutOfBounds(2, 2))));
+        let restored = restore(vec![(LinkId(0), 0, 5)]);
+        assert!(matches!(restored, Err(Error::IndexOutOfBounds(5, 2))));
+    }
+}
@@ -0,0 +1,414 @@
+//! Rules the relations of a model must respect, checked at once by
+//! `RelationalWorld::check_invariants`, rather than by hand-written
+//! checks scattered across the crates using the model.
+//!
+//! The rules are checked on the raw links returned by
+//! `RelationalWorld::links_of`, thus on any model of the
+//! `GetCorresponding` derive.
+//!
+//! ```
+//! # use relational_types::{invariants::Invariant, RelationalWorld};
+//! # struct Line;
+//! # struct Route;
+//! # struct StopArea;
+//! # fn check<W: RelationalWorld>(world: &W) {
+//! let report = world.check_invariants(&[
+//!     Invariant::each_target_has_source::<Route, Line>(),
+//!     Invariant::at_most_one_source::<Route, Line>(),
+//!     Invariant::acyclic::<StopArea>(),
+//! ]);
+//! assert!(report.is_empty(), "{:?}", report);
+//! # }
+//! ```
+
+use crate::RelationalWorld;
+use std::any::type_name;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A rule on the relations of a model, checked by
+/// `RelationalWorld::check_invariants`. The types are given by their
+/// full names, as returned by `std::any::type_name`, matching the
+/// names of `RelationInfo` ending them, e.g. `Line` for
+/// `my_crate::Line`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Invariant {
+    /// Each object of type `target` appearing in a relation of the
+    /// model is linked to an object of type `source`.
+    EachTargetHasSource {
+        /// Name of the type of the objects to check.
+        target: &'static str,
+        /// Name of the type of the objects they must be linked to.
+        source: &'static str,
+    },
+    /// Each object of type `target` is linked to at most one object of
+    /// type `source`.
+    AtMostOneSource {
+        /// Name of the type of the objects to check.
+        target: &'static str,
+        /// Name of the type of the objects they are linked to.
+        source: &'static str,
+    },
+    /// The relations from objects of type `of` to objects of the same
+    /// type contain no cycle.
+    Acyclic {
+        /// Name of the type of the objects.
+        of: &'static str,
+    },
+}
+
+impl Invariant {
+    /// Each `T` appearing in a relation of the model is linked to an
+    /// `S`, e.g. each route belongs to a line. The objects linked to
+    /// nothing at all can't be found from the relations, see the
+    /// `assert_covers` method of the `BuildRelations` derive for them.
+    pub fn each_target_has_source<T, S>() -> Self {
+        Invariant::EachTargetHasSource {
+            target: type_name::<T>(),
+            source: type_name::<S>(),
+        }
+    }
+
+    /// Each `T` is linked to at most one `S`, e.g. a route belongs to
+    /// a single line.
+    pub fn at_most_one_source<T, S>() -> Self {
+        Invariant::AtMostOneSource {
+            target: type_name::<T>(),
+            source: type_name::<S>(),
+        }
+    }
+
+    /// The relations between `T`s contain no cycle, e.g. no stop area
+    /// is its own parent.
+    pub fn acyclic<T>() -> Self {
+        Invariant::Acyclic {
+            of: type_name::<T>(),
+        }
+    }
+
+    /// Returns the objects breaking the invariant in `world`, as raw
+    /// indices, sorted.
+    fn violating_objects<W: RelationalWorld + ?Sized>(&self, world: &W) -> Vec<usize> {
+        match *self {
+            Invariant::EachTargetHasSource { target, source } => {
+                let mut appearing = BTreeSet::new();
+                for info in world.relations() {
+                    let links = world.links_of(info.name).unwrap_or_default();
+                    if same_type(info.from, target) {
+                        appearing.extend(links.iter().map(|&(from, _)| from));
+                    }
+                    if same_type(info.to, target) {
+                        appearing.extend(links.iter().map(|&(_, to)| to));
+                    }
+                }
+                let linked = sources_by_target(world, target, source);
+                appearing
+                    .into_iter()
+                    .filter(|object| !linked.contains_key(object))
+                    .collect()
+            }
+            Invariant::AtMostOneSource { target, source } => {
+                sources_by_target(world, target, source)
+                    .into_iter()
+                    .filter(|(_, sources)| sources.len() > 1)
+                    .map(|(object, _)| object)
+                    .collect()
+            }
+            Invariant::Acyclic { of } => objects_in_cycles(world, of),
+        }
+    }
+}
+
+/// Returns `true` if the type named `name` in a `RelationInfo`, as
+/// written in the model, e.g. `Line`, is the type of full name
+/// `full_name`, e.g. `my_crate::Line`.
+fn same_type(name: &str, full_name: &str) -> bool {
+    full_name
+        .strip_suffix(name)
+        .is_some_and(|path| path.is_empty() || path.ends_with("::"))
+}
+
+/// Returns, for each object of type `target` linked to objects of type
+/// `source`, the raw indices of these objects, whatever the direction
+/// of the relations.
+fn sources_by_target<W: RelationalWorld + ?Sized>(
+    world: &W,
+    target: &str,
+    source: &str,
+) -> BTreeMap<usize, BTreeSet<usize>> {
+    let mut sources: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    for info in world.relations() {
+        let links = world.links_of(info.name).unwrap_or_default();
+        if same_type(info.from, source) && same_type(info.to, target) {
+            for (from, to) in links {
+                sources.entry(to).or_default().insert(from);
+            }
+        } else if same_type(info.from, target) && same_type(info.to, source) {
+            for (from, to) in links {
+                sources.entry(from).or_default().insert(to);
+            }
+        }
+    }
+    sources
+}
+
+/// Returns the objects of type `of` belonging to a cycle, i.e. to a
+/// strongly connected component of several objects or of an object
+/// linked to itself, found by the algorithm of Tarjan. The objects
+/// between 2 cycles, without being in a cycle, are not returned.
+fn objects_in_cycles<W: RelationalWorld + ?Sized>(world: &W, of: &str) -> Vec<usize> {
+    let mut successors: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    for info in world.relations() {
+        if !same_type(info.from, of) || !same_type(info.to, of) {
+            continue;
+        }
+        for (from, to) in world.links_of(info.name).unwrap_or_default() {
+            successors.entry(from).or_default().insert(to);
+        }
+    }
+    let no_successors = BTreeSet::new();
+    let successors_of = |object| successors.get(&object).unwrap_or(&no_successors);
+    // the order of discovery of the visited objects, and the smallest
+    // order of the objects of the stack reachable from them
+    let mut order: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut low_link: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut stack = Vec::new();
+    let mut on_stack = BTreeSet::new();
+    let mut in_cycles = BTreeSet::new();
+    for &root in successors.keys() {
+        if order.contains_key(&root) {
+            continue;
+        }
+        // the objects being visited, with their successors left to
+        // visit, instead of recursive calls overflowing on long paths
+        let mut visiting = Vec::new();
+        let mut discovered = Some(root);
+        loop {
+            if let Some(object) = discovered.take() {
+                let discovery = order.len();
+                order.insert(object, discovery);
+                low_link.insert(object, discovery);
+                stack.push(object);
+                on_stack.insert(object);
+                visiting.push((object, successors_of(object).iter()));
+            }
+            let (object, next) = match visiting.last_mut() {
+                Some((object, next)) => (*object, next),
+                None => break,
+            };
+            if let Some(&successor) = next.next() {
+                match order.get(&successor) {
+                    None => discovered = Some(successor),
+                    Some(&discovery) if on_stack.contains(&successor) => {
+                        let low = low_link.get_mut(&object).expect("a visited object");
+                        *low = std::cmp::min(*low, discovery);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+            visiting.pop();
+            let low = low_link[&object];
+            if let Some(&(parent, _)) = visiting.last() {
+                let parent_low = low_link.get_mut(&parent).expect("a visited object");
+                *parent_low = std::cmp::min(*parent_low, low);
+            }
+            if low == order[&object] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack.remove(&member);
+                    component.push(member);
+                    if member == object {
+                        break;
+                    }
+                }
+                if component.len() > 1 || successors_of(object).contains(&object) {
+                    in_cycles.extend(component);
+                }
+            }
+        }
+    }
+    in_cycles.into_iter().collect()
+}
+
+/// An invariant broken by a model, as reported by
+/// `RelationalWorld::check_invariants`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InvariantViolation {
+    /// The broken invariant.
+    pub invariant: Invariant,
+    /// Raw indices of the objects breaking the invariant, sorted.
+    pub objects: Vec<usize>,
+}
+
+/// The invariants broken by a model, as returned by
+/// `RelationalWorld::check_invariants`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InvariantsReport {
+    /// The broken invariants, in the order they were given.
+    pub violations: Vec<InvariantViolation>,
+}
+
+impl InvariantsReport {
+    /// Returns `true` if every invariant holds.
+    pub fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Checks the `invariants` on `world`, as
+/// `RelationalWorld::check_invariants`.
+pub fn check<W: RelationalWorld + ?Sized>(world: &W, invariants: &[Invariant]) -> InvariantsReport {
+    let violations = invariants
+        .iter()
+        .filter_map(|invariant| {
+            let objects = invariant.violating_objects(world);
+            if objects.is_empty() {
+                None
+            } else {
+                Some(InvariantViolation {
+                    invariant: invariant.clone(),
+                    objects,
+                })
+            }
+        })
+        .collect();
+    InvariantsReport { violations }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::RelationInfo;
+
+    // a model given by the raw links of its relations
+    #[derive(Default)]
+    struct World {
+        animals_to_felines: Vec<(usize, usize)>,
+        felines_to_felines: Vec<(usize, usize)>,
+        animals_to_animals: Vec<(usize, usize)>,
+    }
+
+    impl RelationalWorld for World {
+        fn relations(&self) -> &'static [RelationInfo] {
+            &[
+                RelationInfo {
+                    name: "animals_to_felines",
+                    from: "Animal",
+                    to: "Feline",
+                },
+                RelationInfo {
+                    name: "felines_to_felines",
+                    from: "Feline",
+                    to: "Feline",
+                },
+                RelationInfo {
+                    name: "animals_to_animals",
+                    from: "Animal",
+                    to: "Animal",
+                },
+            ]
+        }
+
+        fn links_of(&self, name: &str) -> Option<Vec<(usize, usize)>> {
+            match name {
+                "animals_to_felines" => Some(self.animals_to_felines.clone()),
+                "felines_to_felines" => Some(self.felines_to_felines.clone()),
+                "animals_to_animals" => Some(self.animals_to_animals.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn each_target_has_source() {
+        let world = World {
+            animals_to_felines: vec![(0, 0), (0, 1)],
+            // the feline 2 appears without any animal
+            felines_to_felines: vec![(1, 2)],
+            ..World::default()
+        };
+        let invariant = Invariant::each_target_has_source::<Feline, Animal>();
+        assert_eq!(vec![2], invariant.violating_objects(&world));
+        let invariant = Invariant::each_target_has_source::<Animal, Feline>();
+        assert!(invariant.violating_objects(&world).is_empty());
+    }
+
+    #[test]
+    fn at_most_one_source() {
+        let world = World {
+            animals_to_felines: vec![(0, 0), (1, 0), (1, 1)],
+            ..World::default()
+        };
+        let invariant = Invariant::at_most_one_source::<Feline, Animal>();
+        assert_eq!(vec![0], invariant.violating_objects(&world));
+        // whatever the direction of the relation
+        let invariant = Invariant::at_most_one_source::<Animal, Feline>();
+        assert_eq!(vec![1], invariant.violating_objects(&world));
+    }
+
+    #[test]
+    fn acyclic_without_cycle() {
+        let world = World {
+            animals_to_animals: vec![(0, 1), (1, 2), (0, 2)],
+            ..World::default()
+        };
+        let invariant = Invariant::acyclic::<Animal>();
+        assert!(invariant.violating_objects(&world).is_empty());
+    }
+
+    #[test]
+    fn acyclic_reports_the_cycles_only() {
+        let world = World {
+            // the cycles 0-1 and 3-4 joined by the bridge 1-2-3, the
+            // animal 5 linked to itself and the animal 6 leading to a
+            // cycle
+            animals_to_animals: vec![
+                (0, 1),
+                (1, 0),
+                (1, 2),
+                (2, 3),
+                (3, 4),
+                (4, 3),
+                (5, 5),
+                (6, 0),
+            ],
+            ..World::default()
+        };
+        let invariant = Invariant::acyclic::<Animal>();
+        assert_eq!(vec![0, 1, 3, 4, 5], invariant.violating_objects(&world));
+    }
+
+    #[test]
+    fn check_reports_the_broken_invariants() {
+        let world = World {
+            animals_to_felines: vec![(0, 0), (1, 0)],
+            animals_to_animals: vec![(0, 1), (1, 0)],
+            ..World::default()
+        };
+        let report = check(
+            &world,
+            &[
+                Invariant::each_target_has_source::<Feline, Animal>(),
+                Invariant::at_most_one_source::<Feline, Animal>(),
+                Invariant::acyclic::<Animal>(),
+            ],
+        );
+        assert_eq!(
+            vec![
+                InvariantViolation {
+                    invariant: Invariant::at_most_one_source::<Feline, Animal>(),
+                    objects: vec![0],
+                },
+                InvariantViolation {
+                    invariant: Invariant::acyclic::<Animal>(),
+                    objects: vec![0, 1],
+                },
+            ],
+            report.violations
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+/// A model built on first use, e.g. to store the model of a service
+/// in a `static`.
+///
+/// The model is built once by the given function, even if several
+/// threads use it at the same time, and its methods are then
+/// available through `Deref`. The `GetCorresponding` derive also
+/// generates a `lazy` constructor, e.g. `World::lazy(load_world)`.
+///
+/// ```
+/// # use relational_types::{LazyWorld, OneToMany, Relation};
+/// # struct Line;
+/// # struct Route;
+/// pub struct World {
+///     lines_to_routes: OneToMany<Line, Route>,
+/// }
+/// fn load_world() -> World {
+///     World {
+///         lines_to_routes: OneToMany::default(),
+///     }
+/// }
+/// static WORLD: LazyWorld<World> = LazyWorld::new(load_world);
+/// assert!(!WORLD.is_initialized());
+/// assert_eq!(0, WORLD.lines_to_routes.iter_from().count());
+/// ```
+pub struct LazyWorld<W> {
+    world: OnceLock<W>,
+    init: fn() -> W,
+}
+
+impl<W> LazyWorld<W> {
+    /// Creates a model that will be built by `init` on first use.
+    pub const fn new(init: fn() -> W) -> Self {
+        LazyWorld {
+            world: OnceLock::new(),
+            init,
+        }
+    }
+
+    /// Returns `true` if the model is already built.
+    pub fn is_initialized(&self) -> bool {
+        self.world.get().is_some()
+    }
+
+    /// Builds the model if needed, and returns it.
+    pub fn force(&self) -> &W {
+        self.world.get_or_init(self.init)
+    }
+}
+
+impl<W> Deref for LazyWorld<W> {
+    type Target = W;
+    fn deref(&self) -> &W {
+        self.force()
+    }
+}
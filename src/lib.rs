@@ -4,7 +4,8 @@
 //!
 //! By default, feature `relational_types_procmacro` is enabled, exposing macros to
 //! help build relations. See documentation of the crate `relational_types_procmacro`
-//! for more information.
+//! for more information. The other features are described in the
+//! [Features](#features) section.
 //!
 //! This module defines types for modeling the relations between
 //! objects, and use them thanks to the `GetCorresponding` custom
@@ -63,7 +64,11 @@
 //! `bikes_to_brands: OneToMany<Bike, Brand>` is a relation between
 //! `Bike` and `Brand`. Using all the relations, it generates a graph,
 //! compute the shortest path between all the types, and generate an
-//! `impl GetCorresponding` for each feasible path.
+//! `impl GetCorresponding` for each feasible path. Between paths of
+//! the same cost, the choice only depends on the order of the
+//! fields, and the sets of indices are sorted, thus the results of
+//! the traversals are deterministic. `Relation::links_sorted` also
+//! returns the links of any relation in a canonical order.
 //!
 //! These `impl GetCorresponding` are used by
 //! `World::get_corresponding_from_idx` and `World::get_corresponding`
@@ -72,7 +77,11 @@
 //! Thus, when we call `world.get_corresponding_from_idx(mbk)` for
 //! `Owner`, we will use the generated code that, basically, gets all
 //! the `Bike`s corresponding to the `Brand` MBK, and then gets all
-//! the `Owner`s corresponding to these `Bike`s.
+//! the `Owner`s corresponding to these `Bike`s. An index linked by
+//! no relation, e.g. an index of another collection, gives an empty
+//! set, whereas `World::try_get_corresponding_from_idx` returns an
+//! `UnknownIndex` error, and `World::get_corresponding_with` does what
+//! its `MissingIndex` argument tells.
 //!
 //! Imagine that, in our application, we use a lot the `Owner->Kind`
 //! and `Brand->Kind` search.  To do these searches, we pass by
@@ -145,11 +154,365 @@
 //!     }
 //! }
 //! ```
+//!
+//! # Building the relations
+//!
+//! Writing such a constructor by hand is tedious when the model
+//! contains dozens of relations. The `BuildRelations` derive
+//! generates it from a struct containing the `CollectionWithId`s,
+//! named after the two parts of the relation names. Each `OneToMany`
+//! is built with `OneToMany::new`, and the shortcuts are built with
+//! `ManyToMany::from_relations_chain`,
+//! `ManyToMany::from_relations_sink` or
+//! `ManyToMany::from_relations_source` depending on their
+//! `build_relations` attribute.
+//!
+//! ```no_run
+//! # use relational_types_procmacro::*;
+//! # use relational_types::*;
+//! # use typed_index_collection::{impl_id, CollectionWithId};
+//! # struct Bike { id: String, brand_id: String, owner_id: String, kind_id: String }
+//! # impl_id!(Bike);
+//! # impl_id!(Bike, Brand, brand_id);
+//! # impl_id!(Bike, Owner, owner_id);
+//! # impl_id!(Bike, Kind, kind_id);
+//! # struct Brand { id: String }
+//! # impl_id!(Brand);
+//! # struct Owner { id: String, job_id: String }
+//! # impl_id!(Owner);
+//! # impl_id!(Owner, Job, job_id);
+//! # struct Job { id: String }
+//! # impl_id!(Job);
+//! # struct Kind { id: String }
+//! # impl_id!(Kind);
+//! pub struct Collections {
+//!     brands: CollectionWithId<Brand>,
+//!     bikes: CollectionWithId<Bike>,
+//!     owners: CollectionWithId<Owner>,
+//!     jobs: CollectionWithId<Job>,
+//!     kinds: CollectionWithId<Kind>,
+//! }
+//!
+//! #[derive(GetCorresponding, BuildRelations)]
+//! #[build_relations(collections = "Collections")]
+//! pub struct World {
+//!     brands_to_bikes: OneToMany<Brand, Bike>,
+//!     owners_to_bikes: OneToMany<Owner, Bike>,
+//!     jobs_to_owners: OneToMany<Job, Owner>,
+//!     kinds_to_bikes: OneToMany<Kind, Bike>,
+//!
+//!     // shortcuts
+//!     #[get_corresponding(weight = "1.9")]
+//!     #[build_relations(sink = "brands_to_bikes, kinds_to_bikes")]
+//!     brands_to_kinds: ManyToMany<Brand, Kind>,
+//!     #[get_corresponding(weight = "1.9")]
+//!     #[build_relations(sink = "kinds_to_bikes, owners_to_bikes")]
+//!     kinds_to_owners: ManyToMany<Kind, Owner>,
+//! }
+//! # fn load_collections() -> Collections { unimplemented!() }
+//! let world = World::try_build(&load_collections()).unwrap();
+//! ```
+//!
+//! With `backward = "lazy"` in this attribute, a shortcut is built as
+//! a `ForwardManyToMany`, whose backward links are only computed if
+//! it is queried backward, and with `forward = "computed"` as a
+//! `BackwardManyToMany`, storing its backward links only. Such
+//! shortcuts are recomputed rather than updated by the deltas of
+//! their base relations.
+//!
+//! To load what can be loaded from inconsistent collections, the
+//! generated `build_partial` constructor leaves empty the base
+//! relations failing to be built, and returns a `WorldBuildReport`
+//! with all their errors. With
+//! `#[build_relations(collections = "Collections", parallel)]`, the
+//! generated `try_build_parallel` constructor builds the base
+//! relations in parallel, then the shortcuts whose relations are
+//! built, to shorten the loading of large models. It is opt-in, as it
+//! requires the collections to be `Sync` and the relations to be
+//! `Send`.
+//!
+//! # Updating the relations
+//!
+//! The derive also generates a `verify_shortcuts` method, recomputing
+//! the shortcuts to detect the ones left stale by a mutation of their
+//! base relations. To avoid them, a base relation `a_to_b` can be
+//! changed with the generated `apply_a_to_b_delta` method, which
+//! applies a `Delta` to it and incrementally updates the shortcuts
+//! depending on it, and `rebuild_relation::<A, B>` rebuilds the base
+//! relation from `A` to `B` after a change of their collections the
+//! same way. It fails when several base relations link `A` to `B`,
+//! each of them being rebuilt by its generated `rebuild_a_to_b`
+//! method instead. Finally, the generated `assert_covers` method
+//! checks that every object of the collections still belongs to the
+//! base relations, e.g. to catch the objects pushed into a collection
+//! after the relations were built.
+//!
+//! The model also implements `ReplayLog`, thus its `OneToMany` and
+//! `ManyToMany` relations can be edited through a `WorldLog`, which
+//! records every change, e.g. `log.apply(&mut world,
+//! "brands_to_bikes", LinkOp::Added, Link(brand, bike))`, and can
+//! replay them onto another model with `log.replay_onto(&mut other)`.
+//! To deduplicate objects, `world.merge_indices(canonical,
+//! &duplicates)` folds the duplicates into the canonical object in the
+//! same relations.
+//!
+//! The collections also implement `LinkIds`, so that
+//! `assert_relations_eq!(expected, actual, collections)` reports the
+//! links differing between 2 models with the identifiers of their
+//! objects, and that, with the `serde` feature,
+//! `world.export_links_jsonl(writer, &collections)` writes every link
+//! with these identifiers. The collections also implement
+//! `LinkIndices`, the converse, so that
+//! `world.import_links_jsonl(reader, &collections)` adds the links
+//! read back.
+//!
+//! # Customizing the traversals
+//!
+//! A relation can be wrapped in a newtype, e.g. to attach
+//! domain methods to it. As the derive can't see through the
+//! newtype, the wrapped relation type must be given with the
+//! `delegate` attribute, and the newtype must dereference to it.
+//!
+//! ```
+//! # use relational_types_procmacro::*;
+//! # use relational_types::*;
+//! # use typed_index_collection::Idx;
+//! # struct Bike;
+//! # struct Brand;
+//! pub struct BrandsToBikes(OneToMany<Brand, Bike>);
+//! impl std::ops::Deref for BrandsToBikes {
+//!     type Target = OneToMany<Brand, Bike>;
+//!     fn deref(&self) -> &Self::Target {
+//!         &self.0
+//!     }
+//! }
+//!
+//! #[derive(GetCorresponding)]
+//! pub struct World {
+//!     #[get_corresponding(delegate = "OneToMany<Brand, Bike>")]
+//!     brands_to_bikes: BrandsToBikes,
+//! }
+//! ```
+//!
+//! To review the paths chosen by the derive, the struct can be
+//! annotated with `#[get_corresponding(dump_to = "OUT_DIR")]`: the
+//! path table and the generated code are then written at compile time
+//! in the given directory, or in the directory contained by the
+//! environment variable of this name if it exists.
+//!
+//! With `#[get_corresponding(generate_tests)]`, the derive also
+//! generates a test module, checking the traversals between every
+//! pair of types on an empty model and the consistency of the paths
+//! described by `World::schema`. The regressions of the derive are
+//! then caught by the tests of the crates using it, run by `cargo
+//! test`. The empty model being built with `Default`, the model must
+//! implement `Default` to use this attribute.
+//!
+//! The weights are static, while the cost of a path depends on the
+//! size of the relations it goes through. With
+//! `#[get_corresponding(planner)]`, the derive also generates a
+//! `get_corresponding_planned` method, choosing at runtime among the
+//! paths starting by each neighbour of the source type the one whose
+//! relations have the smallest `Relation::len_hint`.
+//!
+//! A traversal through several relations is implemented by default
+//! with the traversals of its parts. With
+//! `#[get_corresponding(flatten)]`, each traversal is instead
+//! generated as a single function following the relations of its
+//! path, which is easier to inline and to read in a profile.
+//!
+//! By default, the traversals between every pair of connected types
+//! are generated. For large models, `#[get_corresponding(only =
+//! "Line -> StopPoint, StopPoint -> Line")]` restricts them to the
+//! given pairs, and to the pairs their paths go through.
+//!
+//! When several paths have the same cost, only one of them is
+//! followed. With `#[get_corresponding(ambiguous = "union")]`, all of
+//! them are followed instead, and the union of their results is
+//! returned.
+//!
+//! The path followed among paths of the same cost depends by default
+//! on the order of the fields, thus may change when they are
+//! reordered. With `#[get_corresponding(tie_break = "field_names")]`,
+//! the path whose list of relation names comes first in lexicographic
+//! order is followed instead, whatever the order of the fields.
+//! Likewise, among several relations linking the same 2 types, the
+//! last declared one is followed by default, and with this attribute
+//! the one of smallest name among the lightest ones.
+//!
+//! # Using the model
+//!
+//! To store a model in a `static`, the generated `World::lazy`
+//! constructor returns a `LazyWorld`, building the model on first
+//! use.
+//!
+//! Once built, a model can be frozen by the generated `World::freeze`
+//! method, returning a `FrozenWorld` whose relations are stored in
+//! `FrozenRelation`s, contiguous arrays which are faster to traverse.
+//! A `FrozenWorld` only exposes `get_corresponding` and
+//! `get_corresponding_from_idx`, thus the code serving the queries
+//! can't modify the model by mistake.
+//!
+//! `World::unreachable_from(&roots)` returns an `UnreachableReport`
+//! listing, for each type, the objects of the relations that can't be
+//! reached from the `roots` through any path, e.g. the data left
+//! unused after filtering a part of the model.
+//!
+//! The derive also generates a `RelationField` enum, with a variant
+//! for each relation, e.g. `RelationField::BrandsToBikes`, so that a
+//! configuration can name the relations to export or validate with
+//! typed identifiers. `World::relation(RelationField::BrandsToBikes)`
+//! returns the relation as a `DynRelation`.
+//!
+//! # Features
+//!
+//! By default, the crate gives the relations and the derives only,
+//! without any dependency but `typed_index_collection`. Disabling the
+//! default features but `storage-btree` leaves the relations only,
+//! e.g. for a small WebAssembly build.
+//!
+//! - `relational_types_procmacro`, enabled by default: the derives,
+//!   which also enable the `analysis`, `lazy` and `log` features used
+//!   by the generated code.
+//! - `analysis`, `archive` and `rules`: the modules of the same name,
+//!   the `analysis` one also giving the `invariants` module.
+//! - `lazy`: `LazyWorld`, a model built on first use.
+//! - `log`: `WorldLog`, recording the changes of a model to replay
+//!   them.
+//! - `metrics`: `InstrumentedWorld`, measuring the traversals of a
+//!   model.
+//! - `counted`, `degree`, `either`, `identified`, `revisioned` and
+//!   `ternary`: the specialized relations `CountedManyToMany`,
+//!   `DegreeIndexedOneToMany`, `OneToEitherMany`,
+//!   `IdentifiedManyToMany`, `RevisionedManyToMany` and `Ternary`.
+//! - `storage-btree`, enabled by default, `storage-hash` and
+//!   `storage-hash-ahash`: the relations store the objects linked to
+//!   each object in an `IdxMap`, a `BTreeMap` with `storage-btree`.
+//!   The other features replace it by a `HashMap`, using the `ahash`
+//!   hasher for the latter, even if `storage-btree` is also enabled,
+//!   which speeds up the lookups of large relations, but the relations
+//!   are then iterated in an arbitrary order. The public API is
+//!   unchanged, the sets of indices still being `IdxSet`s.
+//! - `async`: the `asynchronous` module, building the relations while
+//!   their links are read.
+//! - `wasm`, `ffi` and `pyo3`: the `wasm`, `ffi` and `python` modules,
+//!   running the traversals of a model from its archive in
+//!   JavaScript, C and Python respectively.
+//! - `serde`: the export and import of the links as JSON lines, see
+//!   [Updating the relations](#updating-the-relations).
+//! - `tracing`: every traversal going through several relations is
+//!   wrapped in a `debug` span named `get_corresponding`, recording
+//!   the source and target types, the size of the source set, the
+//!   number of hops and the size of the result.
+//! - `rand`: the random sampling of the relations, e.g.
+//!   `OneToMany::sample_subrelation`, and `analysis::random_walk`.
+//! - `fuzz`: the derive also generates an `apply_random_ops` method,
+//!   adding and removing links randomly and checking the consistency
+//!   of the relations after each mutation.
+//! - `sprs`: `ManyToMany::to_csr_matrix` and `ManyToMany::from_matrix`
+//!   convert the relations from and to sparse adjacency matrices, for
+//!   numerical workflows.
+
+// first, so that its macros are visible in the other modules
+#[macro_use]
+mod macros;
 
+#[cfg(feature = "analysis")]
+pub mod analysis;
+#[cfg(feature = "archive")]
+mod archive;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+#[cfg(feature = "counted")]
+mod counted;
+#[cfg(feature = "degree")]
+mod degree;
+mod delta;
+mod directed;
+#[cfg(feature = "either")]
+mod either;
+mod entry;
 mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod frozen;
+#[cfg(feature = "fuzz")]
+mod fuzz;
+#[cfg(feature = "identified")]
+mod identified;
+#[cfg(feature = "analysis")]
+pub mod invariants;
+#[cfg(feature = "lazy")]
+mod lazy;
+mod link;
+#[cfg(feature = "log")]
+mod log;
+#[cfg(feature = "sprs")]
+mod matrix;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "pyo3")]
+pub mod python;
 mod relations;
+mod remap;
+#[cfg(feature = "revisioned")]
+mod revisioned;
+#[cfg(feature = "rules")]
+pub mod rules;
+mod storage;
+#[cfg(feature = "ternary")]
+mod ternary;
+#[cfg(test)]
+mod test_utils;
+mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod world;
 
+#[cfg(feature = "archive")]
+pub use crate::archive::*;
+#[cfg(feature = "counted")]
+pub use crate::counted::*;
+#[cfg(feature = "degree")]
+pub use crate::degree::*;
+pub use crate::delta::*;
+pub use crate::directed::*;
+#[cfg(feature = "either")]
+pub use crate::either::*;
+pub use crate::entry::*;
 pub use crate::error::*;
+pub use crate::frozen::*;
+#[cfg(feature = "fuzz")]
+pub use crate::fuzz::*;
+#[cfg(feature = "identified")]
+pub use crate::identified::*;
+#[cfg(feature = "lazy")]
+pub use crate::lazy::*;
+pub use crate::link::*;
+#[cfg(feature = "log")]
+pub use crate::log::*;
+#[cfg(feature = "metrics")]
+pub use crate::metrics::*;
 pub use crate::relations::*;
+pub use crate::remap::*;
+#[cfg(feature = "revisioned")]
+pub use crate::revisioned::*;
+pub use crate::storage::*;
+#[cfg(feature = "ternary")]
+pub use crate::ternary::*;
+pub use crate::view::*;
+pub use crate::world::*;
 #[cfg(feature = "relational_types_procmacro")]
 pub use relational_types_procmacro::*;
+// used by the code generated with the `relational_types_procmacro` feature
+#[cfg(feature = "relational_types_procmacro")]
+#[doc(hidden)]
+pub use typed_index_collection;
+// used by the code generated with the `fuzz` feature
+#[cfg(feature = "fuzz")]
+#[doc(hidden)]
+pub use rand;
+// used by the code generated with the `tracing` feature
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing;
@@ -0,0 +1,69 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use typed_index_collection::Idx;
+
+/// A link between a source object of type `T` and a target object of
+/// type `U`.
+pub struct Link<T, U>(pub Idx<T>, pub Idx<U>);
+
+impl<T, U> Link<T, U> {
+    /// Returns the index of the source object.
+    pub fn from(&self) -> Idx<T> {
+        self.0
+    }
+
+    /// Returns the index of the target object.
+    pub fn to(&self) -> Idx<U> {
+        self.1
+    }
+}
+
+impl<T, U> From<(Idx<T>, Idx<U>)> for Link<T, U> {
+    fn from((from, to): (Idx<T>, Idx<U>)) -> Self {
+        Link(from, to)
+    }
+}
+
+// manual implementations, as derives would require `T` and `U` to
+// implement the traits
+impl<T, U> Clone for Link<T, U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, U> Copy for Link<T, U> {}
+
+impl<T, U> PartialEq for Link<T, U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && self.1 == other.1
+    }
+}
+
+impl<T, U> Eq for Link<T, U> {}
+
+impl<T, U> PartialOrd for Link<T, U> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, U> Ord for Link<T, U> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0, self.1).cmp(&(other.0, other.1))
+    }
+}
+
+impl<T, U> Hash for Link<T, U> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        self.1.hash(state);
+    }
+}
+
+impl<T, U> fmt::Debug for Link<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Link").field(&self.0).field(&self.1).finish()
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{Error, Link, ReplayLog};
+use std::any::Any;
+use std::fmt;
+
+/// The kind of change of a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkOp {
+    /// The link is added to the relation.
+    Added,
+    /// The link is removed from the relation.
+    Removed,
+}
+
+/// A change of a link of a relation of a model, recorded by a
+/// `WorldLog`.
+pub struct LoggedChange {
+    relation: &'static str,
+    op: LinkOp,
+    link: Box<dyn Any>,
+    raw_link: (usize, usize),
+}
+
+impl LoggedChange {
+    /// Constructs the change of `link` in the relation named
+    /// `relation`.
+    pub fn new<T: 'static, U: 'static>(
+        relation: &'static str,
+        op: LinkOp,
+        link: Link<T, U>,
+    ) -> Self {
+        LoggedChange {
+            relation,
+            op,
+            raw_link: (link.from().get(), link.to().get()),
+            link: Box::new(link),
+        }
+    }
+
+    /// Returns the name of the changed relation.
+    pub fn relation(&self) -> &'static str {
+        self.relation
+    }
+
+    /// Returns the kind of change.
+    pub fn op(&self) -> LinkOp {
+        self.op
+    }
+
+    /// Returns the changed link, as a pair of raw indices.
+    pub fn raw_link(&self) -> (usize, usize) {
+        self.raw_link
+    }
+
+    /// Returns the changed link, or `None` if it doesn't link a `T` to
+    /// a `U`.
+    pub fn link<T: 'static, U: 'static>(&self) -> Option<Link<T, U>> {
+        self.link.downcast_ref().copied()
+    }
+}
+
+impl fmt::Debug for LoggedChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoggedChange")
+            .field("relation", &self.relation)
+            .field("op", &self.op)
+            .field("raw_link", &self.raw_link)
+            .finish()
+    }
+}
+
+/// An append-only log of the changes of the relations of a model,
+/// e.g. to keep an audit trail of the edits of a model, or to apply
+/// them again on another copy of the model.
+///
+/// The changes are applied to the model through `WorldLog::apply`,
+/// thus every successful change is recorded.
+#[derive(Debug, Default)]
+pub struct WorldLog {
+    changes: Vec<LoggedChange>,
+}
+
+impl WorldLog {
+    /// Applies the change of `link` in the relation named `relation`
+    /// to `world`, and appends it to the log if it succeeded.
+    pub fn apply<W, T, U>(
+        &mut self,
+        world: &mut W,
+        relation: &'static str,
+        op: LinkOp,
+        link: impl Into<Link<T, U>>,
+    ) -> Result<(), Error>
+    where
+        W: ReplayLog,
+        T: 'static,
+        U: 'static,
+    {
+        let change = LoggedChange::new(relation, op, link.into());
+        world.apply_change(&change)?;
+        self.changes.push(change);
+        Ok(())
+    }
+
+    /// Returns the changes, in the order they were applied.
+    pub fn changes(&self) -> &[LoggedChange] {
+        &self.changes
+    }
+
+    /// Returns the number of changes.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Returns `true` if the log contains no change.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Keeps only the first `len` changes, e.g. to replay the log
+    /// without the last edits.
+    pub fn truncate(&mut self, len: usize) {
+        self.changes.truncate(len);
+    }
+
+    /// Applies every change of the log to `world`, in order, stopping
+    /// at the first failure.
+    pub fn replay_onto<W: ReplayLog>(&self, world: &mut W) -> Result<(), Error> {
+        self.changes
+            .iter()
+            .try_for_each(|change| world.apply_change(change))
+    }
+}
@@ -0,0 +1,78 @@
+/// Implements the given methods of `Relation` by forwarding them to
+/// the relation stored in the `$field` field, e.g.
+/// `forward_relation!(relation, iter_from, iter_to);` in an
+/// `impl Relation` block. Without methods, every method handled by
+/// the macro is forwarded.
+#[allow(unused_macros)]
+macro_rules! forward_relation {
+    ($field:ident) => {
+        forward_relation!(
+            $field,
+            iter_from,
+            iter_to,
+            len_hint,
+            get_corresponding_forward,
+            get_corresponding_backward,
+            get_corresponding_forward_within,
+            get_corresponding_backward_within,
+            contains_link,
+            for_each_link
+        );
+    };
+    ($field:ident, $($method:ident),+) => {
+        $(forward_relation!(@method $field $method);)+
+    };
+    (@method $field:ident iter_from) => {
+        fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<Self::From>> + '_> {
+            self.$field.iter_from()
+        }
+    };
+    (@method $field:ident iter_to) => {
+        fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<Self::To>> + '_> {
+            self.$field.iter_to()
+        }
+    };
+    (@method $field:ident len_hint) => {
+        fn len_hint(&self) -> usize {
+            self.$field.len_hint()
+        }
+    };
+    (@method $field:ident get_corresponding_forward) => {
+        fn get_corresponding_forward(&self, from: &IdxSet<Self::From>) -> IdxSet<Self::To> {
+            self.$field.get_corresponding_forward(from)
+        }
+    };
+    (@method $field:ident get_corresponding_backward) => {
+        fn get_corresponding_backward(&self, from: &IdxSet<Self::To>) -> IdxSet<Self::From> {
+            self.$field.get_corresponding_backward(from)
+        }
+    };
+    (@method $field:ident get_corresponding_forward_within) => {
+        fn get_corresponding_forward_within(
+            &self,
+            from: &IdxSet<Self::From>,
+            allowed: &IdxSet<Self::To>,
+        ) -> IdxSet<Self::To> {
+            self.$field.get_corresponding_forward_within(from, allowed)
+        }
+    };
+    (@method $field:ident get_corresponding_backward_within) => {
+        fn get_corresponding_backward_within(
+            &self,
+            from: &IdxSet<Self::To>,
+            allowed: &IdxSet<Self::From>,
+        ) -> IdxSet<Self::From> {
+            self.$field.get_corresponding_backward_within(from, allowed)
+        }
+    };
+    (@method $field:ident contains_link) => {
+        fn contains_link(&self, link: Link<Self::From, Self::To>) -> bool {
+            self.$field.contains_link(link)
+        }
+    };
+    (@method $field:ident for_each_link) => {
+        fn for_each_link(&self, f: impl FnMut(Idx<Self::From>, Idx<Self::To>)) {
+            self.$field.for_each_link(f)
+        }
+    };
+}
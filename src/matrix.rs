@@ -0,0 +1,74 @@
+//! Conversions between relations and sparse matrices.
+
+use crate::{Error, Link, ManyToMany, Relation};
+use sprs::CsMat;
+use typed_index_collection::{CollectionWithId, Idx};
+
+impl<T, U> ManyToMany<T, U> {
+    /// Returns the adjacency matrix of the relation, in compressed
+    /// sparse rows, with a row per object of `from`, a column per
+    /// object of `to`, and a `1.` per link, e.g. to compute the
+    /// reachability through several hops by powers of the matrix.
+    ///
+    /// Returns an `Error::IndexOutOfBounds` if a link does not belong
+    /// to the collections.
+    pub fn to_csr_matrix(
+        &self,
+        from: &CollectionWithId<T>,
+        to: &CollectionWithId<U>,
+    ) -> Result<CsMat<f64>, Error> {
+        let mut links = Vec::with_capacity(self.len_hint());
+        for link in self.links() {
+            let (from_idx, to_idx) = (link.from().get(), link.to().get());
+            if from_idx >= from.len() {
+                return Err(Error::IndexOutOfBounds(from_idx, from.len()));
+            }
+            if to_idx >= to.len() {
+                return Err(Error::IndexOutOfBounds(to_idx, to.len()));
+            }
+            links.push((from_idx, to_idx));
+        }
+        // the links are only sorted by source with the default storage
+        links.sort_unstable();
+        let mut indptr = Vec::with_capacity(from.len() + 1);
+        let mut indices = Vec::with_capacity(links.len());
+        indptr.push(0);
+        for (from_idx, to_idx) in links {
+            while indptr.len() <= from_idx {
+                indptr.push(indices.len());
+            }
+            indices.push(to_idx);
+        }
+        indptr.resize(from.len() + 1, indices.len());
+        let data = vec![1.; indices.len()];
+        Ok(CsMat::new((from.len(), to.len()), indptr, indices, data))
+    }
+
+    /// Builds the relation from an adjacency matrix, with a row per
+    /// object of `from` and a column per object of `to`, every stored
+    /// entry, whatever its value, giving a link.
+    ///
+    /// Returns an `Error::IndexOutOfBounds` if an entry does not
+    /// belong to the collections.
+    pub fn from_matrix<N>(
+        matrix: &CsMat<N>,
+        from: &CollectionWithId<T>,
+        to: &CollectionWithId<U>,
+    ) -> Result<Self, Error> {
+        let from_indices: Vec<Idx<T>> = from.iter().map(|(idx, _)| idx).collect();
+        let to_indices: Vec<Idx<U>> = to.iter().map(|(idx, _)| idx).collect();
+        let mut links = Vec::with_capacity(matrix.nnz());
+        for (_, (row, col)) in matrix.iter() {
+            let from_idx = *from_indices
+                .get(row)
+                .ok_or(Error::IndexOutOfBounds(row, from_indices.len()))?;
+            let to_idx = *to_indices
+                .get(col)
+                .ok_or(Error::IndexOutOfBounds(col, to_indices.len()))?;
+            links.push(Link(from_idx, to_idx));
+        }
+        let mut relation = ManyToMany::default();
+        relation.extend_links(links);
+        Ok(relation)
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{IdxSet, Traverse};
+use std::any::type_name;
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use typed_index_collection::Idx;
+
+/// The metrics of the traversals from a type to another, as counters
+/// that can be exported to a monitoring system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraversalMetrics {
+    /// Name of the type of the source objects.
+    pub from: &'static str,
+    /// Name of the type of the target objects.
+    pub to: &'static str,
+    /// Number of traversals.
+    pub calls: u64,
+    /// Total number of source objects given to the traversals.
+    pub seed_len: u64,
+    /// Total number of target objects returned by the traversals.
+    pub result_len: u64,
+    /// Total duration of the traversals.
+    pub duration: Duration,
+}
+
+type MetricsByTypes = BTreeMap<(&'static str, &'static str), TraversalMetrics>;
+
+/// A wrapper around a model, measuring the traversals made through
+/// its `get_corresponding` methods.
+///
+/// The other methods of the model are available through `Deref`, and
+/// are not measured.
+pub struct InstrumentedWorld<W> {
+    world: W,
+    metrics: Mutex<MetricsByTypes>,
+}
+
+impl<W> InstrumentedWorld<W> {
+    /// Wraps `world`, with empty metrics.
+    pub fn new(world: W) -> Self {
+        InstrumentedWorld {
+            world,
+            metrics: Mutex::default(),
+        }
+    }
+
+    /// Returns the wrapped model.
+    pub fn into_inner(self) -> W {
+        self.world
+    }
+
+    /// Returns the set of `U` indices corresponding to the `from` set,
+    /// and records the traversal in the metrics.
+    pub fn get_corresponding<T, U>(&self, from: &IdxSet<T>) -> IdxSet<U>
+    where
+        W: Traverse<T, U>,
+    {
+        let start = Instant::now();
+        let to = self.world.traverse(from);
+        let duration = start.elapsed();
+        let mut metrics = self.metrics.lock().unwrap_or_else(PoisonError::into_inner);
+        let metrics = metrics
+            .entry((type_name::<T>(), type_name::<U>()))
+            .or_insert_with(|| TraversalMetrics {
+                from: type_name::<T>(),
+                to: type_name::<U>(),
+                calls: 0,
+                seed_len: 0,
+                result_len: 0,
+                duration: Duration::default(),
+            });
+        metrics.calls += 1;
+        metrics.seed_len += from.len() as u64;
+        metrics.result_len += to.len() as u64;
+        metrics.duration += duration;
+        to
+    }
+
+    /// Returns the set of `U` indices corresponding to the `from`
+    /// index, and records the traversal in the metrics.
+    pub fn get_corresponding_from_idx<T, U>(&self, from: Idx<T>) -> IdxSet<U>
+    where
+        W: Traverse<T, U>,
+    {
+        self.get_corresponding(&Some(from).into_iter().collect())
+    }
+
+    /// Returns the metrics of the traversals made since the creation
+    /// of the wrapper or the last reset, for each pair of types,
+    /// sorted by type names.
+    pub fn metrics_snapshot(&self) -> Vec<TraversalMetrics> {
+        let metrics = self.metrics.lock().unwrap_or_else(PoisonError::into_inner);
+        metrics.values().cloned().collect()
+    }
+
+    /// Resets the metrics.
+    pub fn reset_metrics(&self) {
+        self.metrics
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+impl<W> Deref for InstrumentedWorld<W> {
+    type Target = W;
+
+    fn deref(&self) -> &W {
+        &self.world
+    }
+}
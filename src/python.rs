@@ -0,0 +1,298 @@
+//! Python bindings, running the traversals of a model from its
+//! `RelationsArchive` in Python. A crate building a `cdylib` with the
+//! `pyo3` feature gives the `relational_types` Python module.
+//!
+//! ```python
+//! from relational_types import ArchivedWorld, World
+//!
+//! archive = open("world.archive", "rb").read()
+//! world = ArchivedWorld(archive, 1)
+//! owners = world.traverse("brands_to_bikes,-owners_to_bikes", [0])
+//! brands_to_bikes = world.relation("brands_to_bikes")
+//! bikes = brands_to_bikes.forward([0])
+//!
+//! world = World(archive, 1, {("Brand", "Owner"): "brands_to_bikes,-owners_to_bikes"})
+//! owners = world.corresponding("Brand", "Owner", [0])
+//! ```
+//!
+//! A `World` can also be built in Rust by `PyWorld::from_world`, with
+//! the paths of the `schema` generated by the `GetCorresponding`
+//! derive, and returned to Python by a function of the crate.
+
+use crate::{ArchivedRelation, Migrations, RelationalWorld, RelationsArchive, WorldSchema};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+fn read_archive(bytes: &[u8], version: u32) -> PyResult<RelationsArchive> {
+    RelationsArchive::read(bytes, &Migrations::new(version))
+        .map_err(|error| PyValueError::new_err(error.to_string()))
+}
+
+/// The links of a relation between raw indices, indexed in both
+/// directions.
+#[derive(Default)]
+struct RawRelation {
+    forward: BTreeMap<usize, BTreeSet<usize>>,
+    backward: BTreeMap<usize, BTreeSet<usize>>,
+}
+
+impl RawRelation {
+    fn new(links: &[(usize, usize)]) -> Self {
+        let mut relation = RawRelation::default();
+        for &(from, to) in links {
+            relation.forward.entry(from).or_default().insert(to);
+            relation.backward.entry(to).or_default().insert(from);
+        }
+        relation
+    }
+
+    fn corresponding(map: &BTreeMap<usize, BTreeSet<usize>>, from: &[usize]) -> Vec<usize> {
+        let reached: BTreeSet<usize> = from
+            .iter()
+            .filter_map(|idx| map.get(idx))
+            .flatten()
+            .copied()
+            .collect();
+        reached.into_iter().collect()
+    }
+
+    fn links(&self) -> Vec<(usize, usize)> {
+        self.forward
+            .iter()
+            .flat_map(|(&from, targets)| targets.iter().map(move |&to| (from, to)))
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        self.forward.values().map(BTreeSet::len).sum()
+    }
+}
+
+/// A one to many relation between raw indices, e.g. read from an
+/// `ArchivedWorld`.
+#[pyclass(name = "OneToMany")]
+pub struct PyOneToMany {
+    relation: RawRelation,
+}
+
+#[pymethods]
+impl PyOneToMany {
+    /// Builds the relation from its links, pairs of raw indices of a
+    /// parent and of a child. Fails if a child has several parents.
+    #[new]
+    fn new(links: Vec<(usize, usize)>) -> PyResult<Self> {
+        let relation = RawRelation::new(&links);
+        if let Some((child, parents)) = relation
+            .backward
+            .iter()
+            .find(|(_, parents)| parents.len() > 1)
+        {
+            return Err(PyValueError::new_err(format!(
+                "child {} has several parents: {:?}",
+                child, parents
+            )));
+        }
+        Ok(PyOneToMany { relation })
+    }
+
+    /// Returns the children of the parents `ids`, sorted.
+    fn forward(&self, ids: Vec<usize>) -> Vec<usize> {
+        RawRelation::corresponding(&self.relation.forward, &ids)
+    }
+
+    /// Returns the parents of the children `ids`, sorted.
+    fn backward(&self, ids: Vec<usize>) -> Vec<usize> {
+        RawRelation::corresponding(&self.relation.backward, &ids)
+    }
+
+    /// Returns the parent of the child `id`, if any.
+    fn parent(&self, id: usize) -> Option<usize> {
+        self.relation
+            .backward
+            .get(&id)
+            .and_then(|parents| parents.iter().next().copied())
+    }
+
+    /// Returns the links, sorted.
+    fn links(&self) -> Vec<(usize, usize)> {
+        self.relation.links()
+    }
+
+    fn __len__(&self) -> usize {
+        self.relation.len()
+    }
+}
+
+/// A many to many relation between raw indices, e.g. read from an
+/// `ArchivedWorld`.
+#[pyclass(name = "ManyToMany")]
+pub struct PyManyToMany {
+    relation: RawRelation,
+}
+
+#[pymethods]
+impl PyManyToMany {
+    /// Builds the relation from its links, pairs of raw indices of a
+    /// source and of a target.
+    #[new]
+    fn new(links: Vec<(usize, usize)>) -> Self {
+        PyManyToMany {
+            relation: RawRelation::new(&links),
+        }
+    }
+
+    /// Returns the targets of the sources `ids`, sorted.
+    fn forward(&self, ids: Vec<usize>) -> Vec<usize> {
+        RawRelation::corresponding(&self.relation.forward, &ids)
+    }
+
+    /// Returns the sources of the targets `ids`, sorted.
+    fn backward(&self, ids: Vec<usize>) -> Vec<usize> {
+        RawRelation::corresponding(&self.relation.backward, &ids)
+    }
+
+    /// Returns the links, sorted.
+    fn links(&self) -> Vec<(usize, usize)> {
+        self.relation.links()
+    }
+
+    fn __len__(&self) -> usize {
+        self.relation.len()
+    }
+}
+
+impl From<&ArchivedRelation> for PyManyToMany {
+    fn from(relation: &ArchivedRelation) -> Self {
+        PyManyToMany {
+            relation: RawRelation::new(&relation.links),
+        }
+    }
+}
+
+/// The relations of a model read from a `RelationsArchive`, the
+/// objects being given by their raw indices.
+#[pyclass(name = "ArchivedWorld")]
+pub struct PyArchivedWorld {
+    archive: RelationsArchive,
+}
+
+#[pymethods]
+impl PyArchivedWorld {
+    /// Reads the archive written by `RelationsArchive::write`, for the
+    /// version `version` of the model.
+    #[new]
+    fn new(bytes: &[u8], version: u32) -> PyResult<Self> {
+        let archive = read_archive(bytes, version)?;
+        Ok(PyArchivedWorld { archive })
+    }
+
+    /// Returns the names of the relations, in the order of the archive.
+    fn relation_names(&self) -> Vec<String> {
+        self.archive
+            .relations
+            .iter()
+            .map(|relation| relation.name.clone())
+            .collect()
+    }
+
+    /// Returns the raw indices reached from `from` by following the
+    /// relations of `path`, as `RelationsArchive::traverse_path`.
+    fn traverse(&self, path: &str, from: Vec<usize>) -> PyResult<Vec<usize>> {
+        self.archive
+            .traverse_path(path, &from)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown relation in path {}", path)))
+    }
+
+    /// Returns the relation named `name`.
+    fn relation(&self, name: &str) -> PyResult<PyManyToMany> {
+        self.archive
+            .relation(name)
+            .map(PyManyToMany::from)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown relation {}", name)))
+    }
+}
+
+/// The relations of a model read from a `RelationsArchive`, with the
+/// paths followed between its types, so that the objects of a type
+/// corresponding to the objects of another type are queried by the
+/// names of the types, as `get_corresponding` in Rust.
+#[pyclass(name = "World")]
+pub struct PyWorld {
+    archive: RelationsArchive,
+    // the path followed from a type to another, in the syntax of
+    // `RelationsArchive::traverse_path`
+    paths: HashMap<(String, String), String>,
+}
+
+impl PyWorld {
+    /// Archives the relations of `world`, following the paths of its
+    /// `schema`, as generated by the `GetCorresponding` derive.
+    pub fn from_world<W: RelationalWorld>(world: &W, schema: &WorldSchema) -> Self {
+        let paths = schema
+            .queries
+            .iter()
+            .map(|query| ((query.from.to_owned(), query.to.to_owned()), query.path()))
+            .collect();
+        PyWorld {
+            // the version of the model is only checked when reading
+            archive: RelationsArchive::from_world(world, 0),
+            paths,
+        }
+    }
+}
+
+#[pymethods]
+impl PyWorld {
+    /// Reads the archive written by `RelationsArchive::write`, for the
+    /// version `version` of the model, `paths` giving the path followed
+    /// from a type to another, e.g. `{("Brand", "Owner"):
+    /// "brands_to_bikes,-owners_to_bikes"}`.
+    #[new]
+    fn new(bytes: &[u8], version: u32, paths: HashMap<(String, String), String>) -> PyResult<Self> {
+        let archive = read_archive(bytes, version)?;
+        Ok(PyWorld { archive, paths })
+    }
+
+    /// Returns the raw indices of the objects of type `to_type`
+    /// corresponding to the objects of type `from_type` of raw indices
+    /// `ids`, sorted.
+    fn corresponding(
+        &self,
+        from_type: &str,
+        to_type: &str,
+        ids: Vec<usize>,
+    ) -> PyResult<Vec<usize>> {
+        if from_type == to_type {
+            let ids: BTreeSet<usize> = ids.into_iter().collect();
+            return Ok(ids.into_iter().collect());
+        }
+        let path = self
+            .paths
+            .get(&(from_type.to_owned(), to_type.to_owned()))
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("no path from {} to {}", from_type, to_type))
+            })?;
+        self.archive
+            .traverse_path(path, &ids)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown relation in path {}", path)))
+    }
+
+    /// Returns the relation named `name`.
+    fn relation(&self, name: &str) -> PyResult<PyManyToMany> {
+        self.archive
+            .relation(name)
+            .map(PyManyToMany::from)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown relation {}", name)))
+    }
+}
+
+/// The `relational_types` Python module.
+#[pymodule]
+fn relational_types(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyArchivedWorld>()?;
+    module.add_class::<PyWorld>()?;
+    module.add_class::<PyOneToMany>()?;
+    module.add_class::<PyManyToMany>()?;
+    Ok(())
+}
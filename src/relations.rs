@@ -1,6 +1,12 @@
-use crate::Error;
-use derivative::Derivative;
-use std::collections::{BTreeMap, BTreeSet};
+use crate::storage::{map_with_capacity, reserve};
+use crate::{
+    Anomaly, Delta, Error, IdxMap, IdxRemaps, Link, ManyToManyEntry, OneToManyEntry, RelationView,
+};
+use std::any::{Any, TypeId};
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::iter::FromIterator;
+use std::sync::Arc;
 use typed_index_collection::{CollectionWithId, Id, Idx};
 
 /// The corresponding result type used by the crate.
@@ -9,6 +15,12 @@ type Result<T, E = Error> = std::result::Result<T, E>;
 /// A set of `Idx<T>`
 pub type IdxSet<T> = BTreeSet<Idx<T>>;
 
+/// A set of `Idx<T>` that can be shared between several relations.
+pub type SharedIdxSet<T> = Arc<IdxSet<T>>;
+
+/// A list of links.
+pub type Links<T, U> = Vec<Link<T, U>>;
+
 /// An object linking 2 types together.
 pub trait Relation {
     /// The type of the source object
@@ -18,10 +30,104 @@ pub trait Relation {
     type To;
 
     /// Returns the complete set of the source objects.
-    fn get_from(&self) -> IdxSet<Self::From>;
+    ///
+    /// Implementors must override `get_from` or `iter_from`, each one
+    /// being implemented by default with the other.
+    #[deprecated(since = "2.2.0", note = "use `iter_from` to avoid allocating a set")]
+    fn get_from(&self) -> IdxSet<Self::From> {
+        self.iter_from().collect()
+    }
 
     /// Returns the complete set of the target objects.
-    fn get_to(&self) -> IdxSet<Self::To>;
+    ///
+    /// Implementors must override `get_to` or `iter_to`, each one
+    /// being implemented by default with the other.
+    #[deprecated(since = "2.2.0", note = "use `iter_to` to avoid allocating a set")]
+    fn get_to(&self) -> IdxSet<Self::To> {
+        self.iter_to().collect()
+    }
+
+    /// Returns an iterator over the source objects, sorted by index
+    /// with the default `BTreeMap` storage.
+    fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<Self::From>> + '_> {
+        #[allow(deprecated)]
+        Box::new(self.get_from().into_iter())
+    }
+
+    /// Returns an iterator over the target objects, sorted by index
+    /// with the default `BTreeMap` storage.
+    fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<Self::To>> + '_> {
+        #[allow(deprecated)]
+        Box::new(self.get_to().into_iter())
+    }
+
+    /// For a given set of the source objects, returns the
+    /// corresponding targets objects belonging to `allowed`.
+    ///
+    /// The implementations of the crate filter the targets while
+    /// traversing the relation, without building the complete set of
+    /// targets.
+    fn get_corresponding_forward_within(
+        &self,
+        from: &IdxSet<Self::From>,
+        allowed: &IdxSet<Self::To>,
+    ) -> IdxSet<Self::To> {
+        self.get_corresponding_forward(from)
+            .intersection(allowed)
+            .cloned()
+            .collect()
+    }
+
+    /// For a given set of the target objects, returns the
+    /// corresponding source objects belonging to `allowed`.
+    fn get_corresponding_backward_within(
+        &self,
+        from: &IdxSet<Self::To>,
+        allowed: &IdxSet<Self::From>,
+    ) -> IdxSet<Self::From> {
+        self.get_corresponding_backward(from)
+            .intersection(allowed)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns an estimation of the number of links of the relation,
+    /// e.g. to compare the cost of traversing several relations.
+    ///
+    /// The default implementation returns a lower bound, the largest
+    /// of the numbers of source and target objects.
+    fn len_hint(&self) -> usize {
+        self.iter_from().count().max(self.iter_to().count())
+    }
+
+    /// Returns the source objects with their number of targets, the
+    /// most linked first, then sorted by index, e.g. to process the
+    /// heavy hitters first.
+    fn sources_sorted_by_degree(&self) -> Vec<(Idx<Self::From>, usize)> {
+        let mut sources: Vec<_> = self
+            .iter_from()
+            .map(|from_idx| {
+                let from = Some(from_idx).into_iter().collect();
+                (from_idx, self.get_corresponding_forward(&from).len())
+            })
+            .collect();
+        sources.sort_unstable_by(|(idx, degree), (other_idx, other_degree)| {
+            other_degree.cmp(degree).then(idx.cmp(other_idx))
+        });
+        sources
+    }
+
+    /// Returns an iterator over the links of the relation, grouped by
+    /// target, the targets being sorted by index with the default
+    /// `BTreeMap` storage.
+    fn iter_links_by_target(&self) -> Box<dyn Iterator<Item = Link<Self::From, Self::To>> + '_> {
+        Box::new(self.iter_to().flat_map(move |to_idx| {
+            let to = Some(to_idx).into_iter().collect();
+            self.get_corresponding_backward(&to)
+                .into_iter()
+                .map(move |from_idx| Link(from_idx, to_idx))
+        }))
+    }
 
     /// For a given set of the source objects, returns the
     /// corresponding targets objects.
@@ -30,15 +136,112 @@ pub trait Relation {
     /// For a given set of the target objects, returns the
     /// corresponding source objects.
     fn get_corresponding_backward(&self, from: &IdxSet<Self::To>) -> IdxSet<Self::From>;
+
+    /// Calls `f` on every link of the relation, without allocating
+    /// intermediate sets.
+    fn for_each_link(&self, mut f: impl FnMut(Idx<Self::From>, Idx<Self::To>))
+    where
+        Self: Sized,
+    {
+        for from_idx in self.iter_from() {
+            let from = Some(from_idx).into_iter().collect();
+            for to_idx in self.get_corresponding_forward(&from) {
+                f(from_idx, to_idx);
+            }
+        }
+    }
+
+    /// Returns the links of the relation, sorted by source then by
+    /// target, whatever the implementation of the relation.
+    fn links_sorted(&self) -> Links<Self::From, Self::To>
+    where
+        Self: Sized,
+    {
+        let mut links = Vec::new();
+        self.for_each_link(|from_idx, to_idx| links.push(Link(from_idx, to_idx)));
+        links.sort_unstable();
+        links
+    }
+
+    /// Returns `true` if the relation contains the given link.
+    fn contains_link(&self, link: Link<Self::From, Self::To>) -> bool {
+        let from = Some(link.from()).into_iter().collect();
+        self.get_corresponding_forward(&from).contains(&link.to())
+    }
+
+    /// Returns `true` if every link of the relation is also a link of
+    /// `other`.
+    fn is_subrelation_of<R>(&self, other: &R) -> bool
+    where
+        Self: Sized,
+        R: Relation<From = Self::From, To = Self::To>,
+    {
+        self.iter_from().all(|from_idx| {
+            let from = Some(from_idx).into_iter().collect();
+            self.get_corresponding_forward(&from)
+                .is_subset(&other.get_corresponding_forward(&from))
+        })
+    }
+
+    /// Returns `true` if every link of `other` is also a link of the
+    /// relation.
+    fn is_superrelation_of<R>(&self, other: &R) -> bool
+    where
+        Self: Sized,
+        R: Relation<From = Self::From, To = Self::To>,
+    {
+        other.is_subrelation_of(self)
+    }
+
+    /// Splits the relation into `n` views over contiguous ranges of
+    /// source objects, of approximately the same size, so that
+    /// disjoint subsets of the sources can be processed independently.
+    ///
+    /// Panics if `n` is 0.
+    fn shard(&self, n: usize) -> Vec<RelationView<'_, Self::From, Self::To>>
+    where
+        Self: Sized,
+    {
+        assert!(n > 0, "a relation must be split into at least 1 shard");
+        let sources: Vec<_> = self.iter_from().collect();
+        (0..n)
+            .map(|shard| {
+                let range = shard * sources.len() / n..(shard + 1) * sources.len() / n;
+                RelationView::from_owned(self, sources[range].iter().cloned().collect())
+            })
+            .collect()
+    }
+
+    /// Returns `n` links of the relation chosen randomly, or all the
+    /// links if the relation contains less than `n` links.
+    #[cfg(feature = "rand")]
+    fn sample_links<R>(&self, n: usize, rng: &mut R) -> Links<Self::From, Self::To>
+    where
+        Self: Sized,
+        R: rand::Rng + ?Sized,
+    {
+        use rand::seq::IteratorRandom;
+        let mut links = Vec::new();
+        self.for_each_link(|from_idx, to_idx| links.push(Link(from_idx, to_idx)));
+        links.into_iter().choose_multiple(rng, n)
+    }
 }
 
 /// A one to many relation, i.e. to one `T` corresponds many `U`,
 /// and a `U` has one corresponding `T`.
-#[derive(Derivative, Debug)]
-#[derivative(Default(bound = ""))]
+#[derive(Debug)]
 pub struct OneToMany<T, U> {
-    one_to_many: BTreeMap<Idx<T>, IdxSet<U>>,
-    many_to_one: BTreeMap<Idx<U>, Idx<T>>,
+    one_to_many: IdxMap<Idx<T>, IdxSet<U>>,
+    many_to_one: IdxMap<Idx<U>, Idx<T>>,
+}
+
+impl<T, U> Default for OneToMany<T, U> {
+    fn default() -> Self {
+        OneToMany {
+            one_to_many: Default::default(),
+            many_to_one: Default::default(),
+        }
+    }
 }
 
 impl<T, U> OneToMany<T, U>
@@ -53,8 +256,8 @@ where
         many: &CollectionWithId<U>,
         rel_name: &str,
     ) -> Result<Self> {
-        let mut one_to_many = BTreeMap::default();
-        let mut many_to_one = BTreeMap::default();
+        let mut one_to_many = IdxMap::default();
+        let mut many_to_one = IdxMap::default();
         for (many_idx, obj) in many {
             let one_id = <U as Id<T>>::id(obj);
             let one_idx = one
@@ -71,16 +274,399 @@ where
             many_to_one,
         })
     }
+
+    /// Construct the relation like `new`, requiring every object of
+    /// `one` to have at least one child, e.g. every `Line` must have
+    /// at least one `Route`.
+    ///
+    /// Returns an `Error::EmptyRelation` if the relation contains no
+    /// link, and an `Error::ChildlessParents` listing the identifiers
+    /// of the objects of `one` without children otherwise.
+    pub fn new_non_empty(
+        one: &CollectionWithId<T>,
+        many: &CollectionWithId<U>,
+        rel_name: &str,
+    ) -> Result<Self> {
+        let relation = Self::new(one, many, rel_name)?;
+        if relation.many_to_one.is_empty() {
+            return Err(Error::EmptyRelation(rel_name.to_owned()));
+        }
+        let childless: Vec<_> = one
+            .iter()
+            .filter(|(one_idx, _)| !relation.one_to_many.contains_key(one_idx))
+            .map(|(_, obj)| obj.id().to_owned())
+            .collect();
+        if !childless.is_empty() {
+            return Err(Error::ChildlessParents(rel_name.to_owned(), childless));
+        }
+        Ok(relation)
+    }
+
+    /// Construct the relation from the 2 given `CollectionWithId`s
+    /// and additional `(parent id, child id)` links, checking that
+    /// the whole input is consistent.
+    ///
+    /// Instead of stopping at the first missing identifier, every
+    /// anomaly is collected and reported in an `Error::Anomalies`,
+    /// including the children resolving to several distinct
+    /// parents.
+    pub fn new_strict<'a, I>(
+        one: &CollectionWithId<T>,
+        many: &CollectionWithId<U>,
+        extra_links: I,
+        rel_name: &str,
+    ) -> Result<Self>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut anomalies = Vec::new();
+        let mut parents: BTreeMap<Idx<U>, IdxSet<T>> = BTreeMap::default();
+        let links = many
+            .iter()
+            .map(|(many_idx, obj)| (<U as Id<T>>::id(obj), Some(many_idx), <U as Id<U>>::id(obj)));
+        let extra_links = extra_links
+            .into_iter()
+            .map(|(one_id, many_id)| (one_id, many.get_idx(many_id), many_id));
+        for (one_id, many_idx, many_id) in links.chain(extra_links) {
+            let many_idx = match many_idx {
+                Some(many_idx) => many_idx,
+                None => {
+                    anomalies.push(Anomaly::UnknownChild(many_id.to_owned()));
+                    continue;
+                }
+            };
+            match one.get_idx(one_id) {
+                Some(one_idx) => {
+                    parents.entry(many_idx).or_default().insert(one_idx);
+                }
+                None => anomalies.push(Anomaly::UnknownParent {
+                    child_id: many_id.to_owned(),
+                    parent_id: one_id.to_owned(),
+                }),
+            }
+        }
+        for (&many_idx, one_indices) in &parents {
+            if one_indices.len() > 1 {
+                anomalies.push(Anomaly::ConflictingParents {
+                    child_id: <U as Id<U>>::id(&many[many_idx]).to_owned(),
+                    parent_ids: one_indices
+                        .iter()
+                        .map(|&one_idx| one[one_idx].id().to_owned())
+                        .collect(),
+                });
+            }
+        }
+        if !anomalies.is_empty() {
+            return Err(Error::Anomalies(rel_name.to_owned(), anomalies));
+        }
+        let mut one_to_many = IdxMap::default();
+        let mut many_to_one = IdxMap::default();
+        for (many_idx, one_indices) in parents {
+            for one_idx in one_indices {
+                many_to_one.insert(many_idx, one_idx);
+                one_to_many
+                    .entry(one_idx)
+                    .or_insert_with(IdxSet::default)
+                    .insert(many_idx);
+            }
+        }
+        Ok(OneToMany {
+            one_to_many,
+            many_to_one,
+        })
+    }
+
+    /// Add a link between 2 indices, checking that both belong to
+    /// the given `CollectionWithId`s and that the `many` object
+    /// references the `one` object.
+    pub fn try_add_link(
+        &mut self,
+        from: Idx<T>,
+        to: Idx<U>,
+        one: &CollectionWithId<T>,
+        many: &CollectionWithId<U>,
+    ) -> Result<()> {
+        if from.get() >= one.len() {
+            return Err(Error::IndexOutOfBounds(from.get(), one.len()));
+        }
+        if to.get() >= many.len() {
+            return Err(Error::IndexOutOfBounds(to.get(), many.len()));
+        }
+        let one_id = one[from].id();
+        let referenced_id = <U as Id<T>>::id(&many[to]);
+        if one_id != referenced_id {
+            return Err(Error::InconsistentLink(
+                <U as Id<U>>::id(&many[to]).to_owned(),
+                one_id.to_owned(),
+                referenced_id.to_owned(),
+            ));
+        }
+        self.add_link(Link(from, to));
+        Ok(())
+    }
+}
+
+impl<T: Id<T>, U: Id<U>> OneToMany<T, U> {
+    /// Returns `true` if the relation and `other` link the same
+    /// objects, given by their identifiers, the relation linking
+    /// objects of `one` and `many`, and `other` objects of `other_one`
+    /// and `other_many`, e.g. to compare models built from collections
+    /// in different orders. A link not belonging to its collections
+    /// makes the relations different.
+    pub fn equivalent(
+        &self,
+        other: &Self,
+        one: &CollectionWithId<T>,
+        other_one: &CollectionWithId<T>,
+        many: &CollectionWithId<U>,
+        other_many: &CollectionWithId<U>,
+    ) -> bool {
+        // the parent identifier of each child identifier
+        fn parent_ids<'a, T: Id<T>, U: Id<U>>(
+            relation: &OneToMany<T, U>,
+            one: &'a CollectionWithId<T>,
+            many: &'a CollectionWithId<U>,
+        ) -> Option<BTreeMap<&'a str, &'a str>> {
+            relation
+                .many_to_one
+                .iter()
+                .map(|(&child, &parent)| {
+                    if child.get() < many.len() && parent.get() < one.len() {
+                        Some((many[child].id(), one[parent].id()))
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        }
+        self.many_to_one.len() == other.many_to_one.len()
+            && match (
+                parent_ids(self, one, many),
+                parent_ids(other, other_one, other_many),
+            ) {
+                (Some(ids), Some(other_ids)) => ids == other_ids,
+                _ => false,
+            }
+    }
+}
+
+impl<T, U> OneToMany<T, U> {
+    /// Creates an empty relation, able to hold `n_parents` parents
+    /// without reallocating with the hash storages.
+    pub fn with_capacity(n_parents: usize) -> Self {
+        OneToMany {
+            one_to_many: map_with_capacity(n_parents),
+            many_to_one: IdxMap::default(),
+        }
+    }
+
+    /// Reserves the capacity for `additional` more parents with the
+    /// hash storages, e.g. before adding many links.
+    pub fn reserve(&mut self, additional: usize) {
+        reserve(&mut self.one_to_many, additional);
+    }
+
+    /// Add a link between 2 indices, replacing the previous source of
+    /// `to` if any.
+    ///
+    /// Warning: no check is done on the indices, adding a link that
+    /// does not match the objects creates an undefined behavior.
+    /// See `try_add_link` for a checked version.
+    pub fn add_link(&mut self, link: impl Into<Link<T, U>>) {
+        let Link(from, to) = link.into();
+        if let Some(previous) = self.many_to_one.insert(to, from) {
+            self.remove_child(previous, to);
+        }
+        self.one_to_many.entry(from).or_default().insert(to);
+    }
+
+    /// Adds the given links, replacing the previous sources of their
+    /// targets if any.
+    ///
+    /// The consecutive links of a source are inserted at once, thus
+    /// links sorted by source are added faster than one by one.
+    pub fn extend_links<L: Into<Link<T, U>>>(&mut self, links: impl IntoIterator<Item = L>) {
+        let mut links = links.into_iter().map(Into::into).peekable();
+        while let Some(Link(from, to)) = links.next() {
+            let mut children = vec![to];
+            while let Some(link) = links.next_if(|link| link.from() == from) {
+                children.push(link.to());
+            }
+            for &child in &children {
+                match self.many_to_one.insert(child, from) {
+                    Some(previous) if previous != from => self.remove_child(previous, child),
+                    _ => {}
+                }
+            }
+            self.one_to_many.entry(from).or_default().extend(children);
+        }
+    }
+
+    /// Removes a link, returning `true` if it was in the relation.
+    pub fn remove_link(&mut self, link: impl Into<Link<T, U>>) -> bool {
+        let Link(from, to) = link.into();
+        if self.many_to_one.get(&to) != Some(&from) {
+            return false;
+        }
+        self.many_to_one.remove(&to);
+        self.remove_child(from, to);
+        true
+    }
+
+    /// Returns an iterator over the links of the relation, sorted by
+    /// source with the default `BTreeMap` storage.
+    pub fn links(&self) -> impl Iterator<Item = Link<T, U>> + '_ {
+        self.one_to_many
+            .iter()
+            .flat_map(|(&from, children)| children.iter().map(move |&to| Link(from, to)))
+    }
+
+    /// Adds the links of `other` to the relation, after remapping
+    /// their indices with `remaps`. For a `U` in both relations, the
+    /// source from `other` wins.
+    pub fn merge(&mut self, other: Self, remaps: &IdxRemaps)
+    where
+        T: 'static,
+        U: 'static,
+    {
+        for Link(from, to) in other.links() {
+            self.add_link(Link(remaps.apply(from), remaps.apply(to)));
+        }
+    }
+
+    /// Applies the removals then the additions of `delta`, and
+    /// returns the changes actually made, including the removal of
+    /// the links replaced by the added ones.
+    pub fn apply_delta(&mut self, delta: Delta<T, U>) -> Delta<T, U> {
+        let mut applied = Delta::default();
+        for link in delta.removed {
+            if self.remove_link(link) {
+                applied.removed.push(link);
+            }
+        }
+        for link in delta.added {
+            if self.contains_link(link) {
+                continue;
+            }
+            if let Some(previous) = self.parent_of(link.to()) {
+                applied.removed.push(Link(previous, link.to()));
+            }
+            self.add_link(link);
+            applied.added.push(link);
+        }
+        applied
+    }
+
+    /// Returns an iterator over the children of `parent`, i.e. the
+    /// `U` linked to this `T`.
+    pub fn children_of(&self, parent: Idx<T>) -> impl Iterator<Item = Idx<U>> + '_ {
+        self.one_to_many
+            .get(&parent)
+            .into_iter()
+            .flat_map(|children| children.iter().cloned())
+    }
+
+    /// Returns the set of the children of `from`, if it has any,
+    /// without cloning it.
+    pub fn children(&self, from: Idx<T>) -> Option<&IdxSet<U>> {
+        self.one_to_many.get(&from)
+    }
+
+    /// Returns a guard to inspect and edit the children of `from` in
+    /// place, the relation being updated when the guard is dropped.
+    pub fn entry(&mut self, from: Idx<T>) -> OneToManyEntry<'_, T, U> {
+        OneToManyEntry::new(self, from)
+    }
+
+    /// Returns the parent of `child`, i.e. the `T` linked to this
+    /// `U`, if any.
+    pub fn parent_of(&self, child: Idx<U>) -> Option<Idx<T>> {
+        self.many_to_one.get(&child).cloned()
+    }
+
+    /// Returns the parent of each of the `children` having one, e.g.
+    /// to annotate each child with its parent, where
+    /// `get_corresponding_backward` would merge the parents.
+    pub fn parents_of(&self, children: &IdxSet<U>) -> BTreeMap<Idx<U>, Idx<T>> {
+        children
+            .iter()
+            .filter_map(|&child| self.parent_of(child).map(|parent| (child, parent)))
+            .collect()
+    }
+
+    /// Moves all the children of `from_old` to `to_new`, e.g. when
+    /// merging 2 parents, `from_old` having no children afterwards.
+    pub fn reassign_children(&mut self, from_old: Idx<T>, to_new: Idx<T>) {
+        if from_old == to_new {
+            return;
+        }
+        if let Some(children) = self.one_to_many.remove(&from_old) {
+            for &child in &children {
+                self.many_to_one.insert(child, to_new);
+            }
+            self.one_to_many.entry(to_new).or_default().extend(children);
+        }
+    }
+
+    fn remove_child(&mut self, from: Idx<T>, to: Idx<U>) {
+        if let Some(children) = self.one_to_many.get_mut(&from) {
+            children.remove(&to);
+            if children.is_empty() {
+                self.one_to_many.remove(&from);
+            }
+        }
+    }
+
+    /// Returns a random sub-relation containing the given `fraction`
+    /// (between 0 and 1) of the links of the relation.
+    #[cfg(feature = "rand")]
+    pub fn sample_subrelation<R>(&self, fraction: f64, rng: &mut R) -> Self
+    where
+        R: rand::Rng + ?Sized,
+    {
+        use rand::seq::IteratorRandom;
+        let amount = (self.many_to_one.len() as f64 * fraction).round() as usize;
+        let mut one_to_many = IdxMap::default();
+        let mut many_to_one = IdxMap::default();
+        for (&many_idx, &one_idx) in self.many_to_one.iter().choose_multiple(rng, amount) {
+            many_to_one.insert(many_idx, one_idx);
+            one_to_many
+                .entry(one_idx)
+                .or_insert_with(IdxSet::default)
+                .insert(many_idx);
+        }
+        OneToMany {
+            one_to_many,
+            many_to_one,
+        }
+    }
+
+    /// Rebuilds the internal storage to reclaim the memory lost after
+    /// a heavy mutation of the relation.
+    pub fn shrink(&mut self) {
+        shrink_map(&mut self.one_to_many);
+        self.many_to_one = std::mem::take(&mut self.many_to_one).into_iter().collect();
+    }
 }
 
 impl<T, U> Relation for OneToMany<T, U> {
     type From = T;
     type To = U;
-    fn get_from(&self) -> IdxSet<T> {
-        self.one_to_many.keys().cloned().collect()
+    fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<T>> + '_> {
+        Box::new(self.one_to_many.keys().cloned())
     }
-    fn get_to(&self) -> IdxSet<U> {
-        self.many_to_one.keys().cloned().collect()
+    fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<U>> + '_> {
+        Box::new(self.many_to_one.keys().cloned())
+    }
+    fn iter_links_by_target(&self) -> Box<dyn Iterator<Item = Link<T, U>> + '_> {
+        Box::new(
+            self.many_to_one
+                .iter()
+                .map(|(&to_idx, &from_idx)| Link(from_idx, to_idx)),
+        )
+    }
+    fn len_hint(&self) -> usize {
+        self.many_to_one.len()
     }
     fn get_corresponding_forward(&self, from: &IdxSet<T>) -> IdxSet<U> {
         get_corresponding(&self.one_to_many, from)
@@ -91,17 +677,251 @@ impl<T, U> Relation for OneToMany<T, U> {
             .cloned()
             .collect()
     }
+    fn get_corresponding_forward_within(&self, from: &IdxSet<T>, allowed: &IdxSet<U>) -> IdxSet<U> {
+        get_corresponding_within(&self.one_to_many, from, allowed)
+    }
+    fn get_corresponding_backward_within(
+        &self,
+        from: &IdxSet<U>,
+        allowed: &IdxSet<T>,
+    ) -> IdxSet<T> {
+        from.iter()
+            .filter_map(|from_idx| self.many_to_one.get(from_idx))
+            .filter(|idx| allowed.contains(idx))
+            .cloned()
+            .collect()
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.many_to_one.get(&link.to()) == Some(&link.from())
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for (&from_idx, to_indices) in &self.one_to_many {
+            for &to_idx in to_indices {
+                f(from_idx, to_idx);
+            }
+        }
+    }
+}
+
+impl<T, U> OneToMany<T, U> {
+    /// Returns the inverse relation, from `U` to `T`.
+    pub fn invert(&self) -> ManyToOne<U, T> {
+        ManyToOne {
+            many_to_one: self.many_to_one.clone(),
+            one_to_many: self.one_to_many.clone(),
+        }
+    }
+
+    /// Returns a copy of the relation containing only the links to
+    /// the given `targets`.
+    pub fn restrict_to_targets(&self, targets: &IdxSet<U>) -> Self {
+        let mut restricted = OneToMany::default();
+        for &to in targets {
+            if let Some(&from) = self.many_to_one.get(&to) {
+                restricted.add_link(Link(from, to));
+            }
+        }
+        restricted
+    }
+}
+
+impl<T, U> OneToMany<T, U>
+where
+    T: Id<T>,
+    U: Id<U>,
+{
+    /// Converts `relation`, e.g. the result of a join between the
+    /// objects of `one` and `many`, verifying that each `U` has at most
+    /// one corresponding `T`. Otherwise, an `Error::Anomalies` gives
+    /// the identifiers of each `U` with several sources, and of its
+    /// sources.
+    pub fn try_from_many_to_many(
+        relation: &ManyToMany<T, U>,
+        one: &CollectionWithId<T>,
+        many: &CollectionWithId<U>,
+        rel_name: &str,
+    ) -> Result<Self> {
+        let mut many_to_one = IdxMap::default();
+        let mut anomalies = Vec::new();
+        for (&to_idx, from_indices) in &relation.backward {
+            match from_indices.len() {
+                // a target without any source isn't in the relation
+                0 => {}
+                1 => {
+                    let from_idx = *from_indices.iter().next().unwrap();
+                    many_to_one.insert(to_idx, from_idx);
+                }
+                _ => anomalies.push(Anomaly::ConflictingParents {
+                    child_id: many[to_idx].id().to_owned(),
+                    parent_ids: from_indices
+                        .iter()
+                        .map(|&from_idx| one[from_idx].id().to_owned())
+                        .collect(),
+                }),
+            }
+        }
+        if !anomalies.is_empty() {
+            return Err(Error::Anomalies(rel_name.to_owned(), anomalies));
+        }
+        let one_to_many = relation
+            .forward
+            .iter()
+            .filter(|(_, to_indices)| !to_indices.is_empty())
+            .map(|(&from_idx, to_indices)| (from_idx, IdxSet::clone(to_indices)))
+            .collect();
+        Ok(OneToMany {
+            one_to_many,
+            many_to_one,
+        })
+    }
+}
+
+/// A many to one relation, i.e. a `T` has one corresponding `U`,
+/// and to one `U` corresponds many `T`. This is the inverse of a
+/// `OneToMany<U, T>`.
+#[derive(Debug)]
+pub struct ManyToOne<T, U> {
+    many_to_one: IdxMap<Idx<T>, Idx<U>>,
+    one_to_many: IdxMap<Idx<U>, IdxSet<T>>,
+}
+
+impl<T, U> Default for ManyToOne<T, U> {
+    fn default() -> Self {
+        ManyToOne {
+            many_to_one: Default::default(),
+            one_to_many: Default::default(),
+        }
+    }
+}
+
+impl<T, U> ManyToOne<T, U> {
+    /// Returns the inverse relation, from `U` to `T`.
+    pub fn invert(&self) -> OneToMany<U, T> {
+        OneToMany {
+            one_to_many: self.one_to_many.clone(),
+            many_to_one: self.many_to_one.clone(),
+        }
+    }
+}
+
+impl<T, U> ManyToOne<T, U> {
+    /// Adds the links of `other` to the relation, after remapping
+    /// their indices with `remaps`. For a `T` in both relations, the
+    /// target from `other` wins.
+    pub fn merge(&mut self, other: Self, remaps: &IdxRemaps)
+    where
+        T: 'static,
+        U: 'static,
+    {
+        for (from, to) in other.many_to_one {
+            let (from, to) = (remaps.apply(from), remaps.apply(to));
+            if let Some(previous) = self.many_to_one.insert(from, to) {
+                if let Some(sources) = self.one_to_many.get_mut(&previous) {
+                    sources.remove(&from);
+                    if sources.is_empty() {
+                        self.one_to_many.remove(&previous);
+                    }
+                }
+            }
+            self.one_to_many.entry(to).or_default().insert(from);
+        }
+    }
+
+    /// Rebuilds the internal storage to reclaim the memory lost after
+    /// a heavy mutation of the relation.
+    pub fn shrink(&mut self) {
+        shrink_map(&mut self.one_to_many);
+        self.many_to_one = std::mem::take(&mut self.many_to_one).into_iter().collect();
+    }
+}
+
+impl<T, U> Relation for ManyToOne<T, U> {
+    type From = T;
+    type To = U;
+    fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<T>> + '_> {
+        Box::new(self.many_to_one.keys().cloned())
+    }
+    fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<U>> + '_> {
+        Box::new(self.one_to_many.keys().cloned())
+    }
+    fn iter_links_by_target(&self) -> Box<dyn Iterator<Item = Link<T, U>> + '_> {
+        Box::new(self.one_to_many.iter().flat_map(|(&to_idx, from_indices)| {
+            from_indices
+                .iter()
+                .map(move |&from_idx| Link(from_idx, to_idx))
+        }))
+    }
+    fn len_hint(&self) -> usize {
+        self.many_to_one.len()
+    }
+    fn get_corresponding_forward(&self, from: &IdxSet<T>) -> IdxSet<U> {
+        from.iter()
+            .filter_map(|from_idx| self.many_to_one.get(from_idx))
+            .cloned()
+            .collect()
+    }
+    fn get_corresponding_backward(&self, from: &IdxSet<U>) -> IdxSet<T> {
+        get_corresponding(&self.one_to_many, from)
+    }
+    fn get_corresponding_forward_within(&self, from: &IdxSet<T>, allowed: &IdxSet<U>) -> IdxSet<U> {
+        from.iter()
+            .filter_map(|from_idx| self.many_to_one.get(from_idx))
+            .filter(|idx| allowed.contains(idx))
+            .cloned()
+            .collect()
+    }
+    fn get_corresponding_backward_within(
+        &self,
+        from: &IdxSet<U>,
+        allowed: &IdxSet<T>,
+    ) -> IdxSet<T> {
+        get_corresponding_within(&self.one_to_many, from, allowed)
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.many_to_one.get(&link.from()) == Some(&link.to())
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for (&from_idx, &to_idx) in &self.many_to_one {
+            f(from_idx, to_idx);
+        }
+    }
 }
 
 /// A many to many relation, i.e. a `T` can have multiple `U`, and
 /// vice versa.
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct ManyToMany<T, U> {
-    forward: BTreeMap<Idx<T>, IdxSet<U>>,
-    backward: BTreeMap<Idx<U>, IdxSet<T>>,
+    forward: IdxMap<Idx<T>, SharedIdxSet<U>>,
+    backward: IdxMap<Idx<U>, SharedIdxSet<T>>,
+}
+
+impl<T, U> Default for ManyToMany<T, U> {
+    fn default() -> Self {
+        ManyToMany {
+            forward: Default::default(),
+            backward: Default::default(),
+        }
+    }
 }
 
 impl<T, U> ManyToMany<T, U> {
+    /// Creates an empty relation, able to hold `n_from` sources and
+    /// `n_to` targets without reallocating with the hash storages.
+    pub fn with_capacity(n_from: usize, n_to: usize) -> Self {
+        ManyToMany {
+            forward: map_with_capacity(n_from),
+            backward: map_with_capacity(n_to),
+        }
+    }
+
+    /// Reserves the capacity for `additional_from` more sources and
+    /// `additional_to` more targets with the hash storages, e.g.
+    /// before adding many links.
+    pub fn reserve(&mut self, additional_from: usize, additional_to: usize) {
+        reserve(&mut self.forward, additional_from);
+        reserve(&mut self.backward, additional_to);
+    }
+
     /// Constructor from the forward relation.
     pub fn from_forward(forward: BTreeMap<Idx<T>, IdxSet<U>>) -> Self {
         let mut backward = BTreeMap::default();
@@ -114,19 +934,165 @@ impl<T, U> ManyToMany<T, U> {
                     .or_insert_with(IdxSet::default)
                     .insert(from_idx);
             });
-        ManyToMany { forward, backward }
+        ManyToMany {
+            forward: share_map(forward),
+            backward: share_map(backward),
+        }
+    }
+
+    /// Returns the forward relation in a map of any type, e.g. a
+    /// `BTreeMap` or a `HashMap` whatever the storage selected by the
+    /// features, the converse of `from_forward`.
+    pub fn to_forward<M>(&self) -> M
+    where
+        M: FromIterator<(Idx<T>, IdxSet<U>)>,
+    {
+        self.forward
+            .iter()
+            .map(|(&from_idx, to_indices)| (from_idx, IdxSet::clone(to_indices)))
+            .collect()
+    }
+
+    /// Constructs the complete relation between `from` and `to`,
+    /// i.e. every `T` of `from` is linked to every `U` of `to`.
+    ///
+    /// The sets of indices are shared between the objects, thus the
+    /// relation is stored in a space proportional to the sizes of
+    /// `from` and `to`.
+    pub fn cartesian(from: &IdxSet<T>, to: &IdxSet<U>) -> Self {
+        if from.is_empty() || to.is_empty() {
+            return ManyToMany::default();
+        }
+        let shared_from = Arc::new(from.clone());
+        let shared_to = Arc::new(to.clone());
+        ManyToMany {
+            forward: from.iter().map(|&idx| (idx, shared_to.clone())).collect(),
+            backward: to.iter().map(|&idx| (idx, shared_from.clone())).collect(),
+        }
+    }
+
+    /// Constructs the relation linking every `T` of `ts` to every `U`
+    /// of `us` satisfying `predicate`, e.g. the stops within 100 m of
+    /// each other.
+    ///
+    /// The predicate is called on every pair of objects, see
+    /// `from_grouping_blocked` to avoid it on large collections.
+    pub fn from_grouping<F>(
+        ts: &CollectionWithId<T>,
+        us: &CollectionWithId<U>,
+        predicate: F,
+    ) -> Self
+    where
+        F: Fn(&T, &U) -> bool,
+    {
+        let mut forward: BTreeMap<Idx<T>, IdxSet<U>> = BTreeMap::default();
+        for (t_idx, t) in ts.iter() {
+            let to: IdxSet<U> = us
+                .iter()
+                .filter(|(_, u)| predicate(t, u))
+                .map(|(u_idx, _)| u_idx)
+                .collect();
+            if !to.is_empty() {
+                forward.insert(t_idx, to);
+            }
+        }
+        ManyToMany::from_forward(forward)
+    }
+
+    /// Constructs the relation linking every `T` of `ts` to every `U`
+    /// of `us` satisfying `predicate`, only testing the pairs sharing
+    /// at least one blocking key, e.g. the cells of a grid around the
+    /// stops.
+    ///
+    /// The predicate may be called several times on a pair sharing
+    /// several keys.
+    pub fn from_grouping_blocked<K, KT, KU, IT, IU, F>(
+        ts: &CollectionWithId<T>,
+        us: &CollectionWithId<U>,
+        t_keys: KT,
+        u_keys: KU,
+        predicate: F,
+    ) -> Self
+    where
+        K: Ord,
+        KT: Fn(&T) -> IT,
+        KU: Fn(&U) -> IU,
+        IT: IntoIterator<Item = K>,
+        IU: IntoIterator<Item = K>,
+        F: Fn(&T, &U) -> bool,
+    {
+        let mut blocks: BTreeMap<K, Vec<Idx<U>>> = BTreeMap::default();
+        for (u_idx, u) in us.iter() {
+            for key in u_keys(u) {
+                blocks.entry(key).or_default().push(u_idx);
+            }
+        }
+        let mut forward: BTreeMap<Idx<T>, IdxSet<U>> = BTreeMap::default();
+        for (t_idx, t) in ts.iter() {
+            for key in t_keys(t) {
+                let block = match blocks.get(&key) {
+                    Some(block) => block,
+                    None => continue,
+                };
+                for &u_idx in block {
+                    if predicate(t, &us[u_idx]) {
+                        forward.entry(t_idx).or_default().insert(u_idx);
+                    }
+                }
+            }
+        }
+        ManyToMany::from_forward(forward)
+    }
+
+    /// Constructs the relation linking the objects of `ts` and `us`
+    /// with equal join keys, like a SQL inner join.
+    ///
+    /// The objects with the same key share their sets of indices, as
+    /// in `cartesian`.
+    pub fn from_join<K, KT, KU>(
+        ts: &CollectionWithId<T>,
+        us: &CollectionWithId<U>,
+        t_key: KT,
+        u_key: KU,
+    ) -> Self
+    where
+        K: Ord,
+        KT: Fn(&T) -> K,
+        KU: Fn(&U) -> K,
+    {
+        let mut t_groups: BTreeMap<K, IdxSet<T>> = BTreeMap::default();
+        for (t_idx, t) in ts.iter() {
+            t_groups.entry(t_key(t)).or_default().insert(t_idx);
+        }
+        let mut u_groups: BTreeMap<K, IdxSet<U>> = BTreeMap::default();
+        for (u_idx, u) in us.iter() {
+            u_groups.entry(u_key(u)).or_default().insert(u_idx);
+        }
+        let mut relation = ManyToMany::default();
+        for (key, t_group) in t_groups {
+            if let Some(u_group) = u_groups.remove(&key) {
+                let group = ManyToMany::cartesian(&t_group, &u_group);
+                relation.forward.extend(group.forward);
+                relation.backward.extend(group.backward);
+            }
+        }
+        relation
     }
 
     /// Constructor from 2 chained relations, i.e. from the relations
     /// `A->B` and `B->C`, constructs the relation `A->C`.
+    ///
+    /// Like `from_relations_sink` and `from_relations_source`, every
+    /// source of `r1` is kept, even without any corresponding `C`, so
+    /// that the sources of the relation are the same as the ones of
+    /// `r1`; see `remove_empty_entries` to remove them.
     pub fn from_relations_chain<R1, R2>(r1: &R1, r2: &R2) -> Self
     where
         R1: Relation<From = T>,
         R2: Relation<From = R1::To, To = U>,
     {
         let forward = r1
-            .get_from()
-            .into_iter()
+            .iter_from()
             .map(|idx| {
                 let from = Some(idx).into_iter().collect();
                 let tmp = r1.get_corresponding_forward(&from);
@@ -144,8 +1110,7 @@ impl<T, U> ManyToMany<T, U> {
         R2: Relation<From = U, To = R1::To>,
     {
         let forward = r1
-            .get_from()
-            .into_iter()
+            .iter_from()
             .map(|idx| {
                 let from = Some(idx).into_iter().collect();
                 let tmp = r1.get_corresponding_forward(&from);
@@ -164,8 +1129,7 @@ impl<T, U> ManyToMany<T, U> {
         R2: Relation<From = R1::From, To = U>,
     {
         let forward = r1
-            .get_to()
-            .into_iter()
+            .iter_to()
             .map(|idx| {
                 let from = Some(idx).into_iter().collect();
                 let tmp = r1.get_corresponding_backward(&from);
@@ -174,16 +1138,468 @@ impl<T, U> ManyToMany<T, U> {
             .collect();
         Self::from_forward(forward)
     }
+
+    /// Reassembles the relation from shards, e.g. the ones returned
+    /// by `Relation::shard`, containing the links of all of them.
+    pub fn from_shards<R>(shards: &[R]) -> Self
+    where
+        R: Relation<From = T, To = U>,
+    {
+        let mut forward: BTreeMap<Idx<T>, IdxSet<U>> = BTreeMap::default();
+        for shard in shards {
+            shard.for_each_link(|from_idx, to_idx| {
+                forward.entry(from_idx).or_default().insert(to_idx);
+            });
+        }
+        Self::from_forward(forward)
+    }
+
+    /// Add a link between 2 indices.
+    pub fn add_link(&mut self, link: impl Into<Link<T, U>>) {
+        let Link(from, to) = link.into();
+        Arc::make_mut(self.forward.entry(from).or_default()).insert(to);
+        Arc::make_mut(self.backward.entry(to).or_default()).insert(from);
+    }
+
+    /// Adds the given links.
+    ///
+    /// The consecutive links of a source are inserted at once, thus
+    /// links sorted by source are added faster than one by one.
+    pub fn extend_links<L: Into<Link<T, U>>>(&mut self, links: impl IntoIterator<Item = L>) {
+        let mut links = links.into_iter().map(Into::into).peekable();
+        while let Some(Link(from, to)) = links.next() {
+            let mut targets = vec![to];
+            while let Some(link) = links.next_if(|link| link.from() == from) {
+                targets.push(link.to());
+            }
+            for &to in &targets {
+                Arc::make_mut(self.backward.entry(to).or_default()).insert(from);
+            }
+            Arc::make_mut(self.forward.entry(from).or_default()).extend(targets);
+        }
+    }
+
+    /// Removes a link, returning `true` if it was in the relation.
+    ///
+    /// The objects left without any link are removed from the
+    /// relation.
+    pub fn remove_link(&mut self, link: impl Into<Link<T, U>>) -> bool {
+        let Link(from, to) = link.into();
+        remove_shared(&mut self.forward, from, to) && remove_shared(&mut self.backward, to, from)
+    }
+
+    /// Applies the removals then the additions of `delta`, and
+    /// returns the changes actually made.
+    pub fn apply_delta(&mut self, delta: Delta<T, U>) -> Delta<T, U> {
+        let mut applied = Delta::default();
+        for link in delta.removed {
+            if self.remove_link(link) {
+                applied.removed.push(link);
+            }
+        }
+        for link in delta.added {
+            if !self.contains_link(link) {
+                self.add_link(link);
+                applied.added.push(link);
+            }
+        }
+        applied
+    }
+
+    /// Updates the relation built by `from_relations_chain(r1, r2)`
+    /// after the given deltas have been applied to `r1` and `r2`, and
+    /// returns the changes of the relation.
+    pub fn update_chain<R1, R2>(
+        &mut self,
+        r1: &R1,
+        r2: &R2,
+        delta1: &Delta<T, R1::To>,
+        delta2: &Delta<R1::To, U>,
+    ) -> Delta<T, U>
+    where
+        R1: Relation<From = T>,
+        R2: Relation<From = R1::To, To = U>,
+    {
+        let mut added = Vec::new();
+        for link in &delta1.added {
+            for to_idx in r2.get_corresponding_forward(&singleton(link.to())) {
+                added.push(Link(link.from(), to_idx));
+            }
+        }
+        for link in &delta2.added {
+            for from_idx in r1.get_corresponding_backward(&singleton(link.from())) {
+                added.push(Link(from_idx, link.to()));
+            }
+        }
+        let removed_from = delta1.removed.iter().map(|link| link.from());
+        let removed_to = delta2.removed.iter().map(|link| link.to());
+        self.update_derived(added, removed_from, removed_to, |from_idx, to_idx| {
+            let tmp = r1.get_corresponding_forward(&singleton(from_idx));
+            !r2.get_corresponding_forward_within(&tmp, &singleton(to_idx))
+                .is_empty()
+        })
+    }
+
+    /// Updates the relation built by `from_relations_sink(r1, r2)`
+    /// after the given deltas have been applied to `r1` and `r2`, and
+    /// returns the changes of the relation.
+    pub fn update_sink<R1, R2>(
+        &mut self,
+        r1: &R1,
+        r2: &R2,
+        delta1: &Delta<T, R1::To>,
+        delta2: &Delta<U, R1::To>,
+    ) -> Delta<T, U>
+    where
+        R1: Relation<From = T>,
+        R2: Relation<From = U, To = R1::To>,
+    {
+        let mut added = Vec::new();
+        for link in &delta1.added {
+            for to_idx in r2.get_corresponding_backward(&singleton(link.to())) {
+                added.push(Link(link.from(), to_idx));
+            }
+        }
+        for link in &delta2.added {
+            for from_idx in r1.get_corresponding_backward(&singleton(link.to())) {
+                added.push(Link(from_idx, link.from()));
+            }
+        }
+        let removed_from = delta1.removed.iter().map(|link| link.from());
+        let removed_to = delta2.removed.iter().map(|link| link.from());
+        self.update_derived(added, removed_from, removed_to, |from_idx, to_idx| {
+            let tmp = r1.get_corresponding_forward(&singleton(from_idx));
+            !r2.get_corresponding_forward_within(&singleton(to_idx), &tmp)
+                .is_empty()
+        })
+    }
+
+    /// Updates the relation built by `from_relations_source(r1, r2)`
+    /// after the given deltas have been applied to `r1` and `r2`, and
+    /// returns the changes of the relation.
+    pub fn update_source<R1, R2>(
+        &mut self,
+        r1: &R1,
+        r2: &R2,
+        delta1: &Delta<R1::From, T>,
+        delta2: &Delta<R1::From, U>,
+    ) -> Delta<T, U>
+    where
+        R1: Relation<To = T>,
+        R2: Relation<From = R1::From, To = U>,
+    {
+        let mut added = Vec::new();
+        for link in &delta1.added {
+            for to_idx in r2.get_corresponding_forward(&singleton(link.from())) {
+                added.push(Link(link.to(), to_idx));
+            }
+        }
+        for link in &delta2.added {
+            for from_idx in r1.get_corresponding_forward(&singleton(link.from())) {
+                added.push(Link(from_idx, link.to()));
+            }
+        }
+        let removed_from = delta1.removed.iter().map(|link| link.to());
+        let removed_to = delta2.removed.iter().map(|link| link.to());
+        self.update_derived(added, removed_from, removed_to, |from_idx, to_idx| {
+            let tmp = r1.get_corresponding_backward(&singleton(from_idx));
+            !r2.get_corresponding_backward_within(&singleton(to_idx), &tmp)
+                .is_empty()
+        })
+    }
+
+    /// Removes the links of the sources `removed_from` and of the
+    /// targets `removed_to` that are not `derivable` anymore, then
+    /// adds the `added` links.
+    fn update_derived(
+        &mut self,
+        added: Links<T, U>,
+        removed_from: impl Iterator<Item = Idx<T>>,
+        removed_to: impl Iterator<Item = Idx<U>>,
+        derivable: impl Fn(Idx<T>, Idx<U>) -> bool,
+    ) -> Delta<T, U> {
+        let mut candidates = BTreeSet::new();
+        for from_idx in removed_from {
+            candidates.extend(shared_indices(&self.forward, from_idx).map(|to| Link(from_idx, to)));
+        }
+        for to_idx in removed_to {
+            candidates
+                .extend(shared_indices(&self.backward, to_idx).map(|from| Link(from, to_idx)));
+        }
+        let removed = candidates
+            .into_iter()
+            .filter(|link| !derivable(link.from(), link.to()))
+            .collect();
+        self.apply_delta(Delta { added, removed })
+    }
+
+    /// Returns the set of the `U` linked from `from`, if any, without
+    /// cloning it.
+    pub fn forward_of(&self, from: Idx<T>) -> Option<&IdxSet<U>> {
+        self.forward.get(&from).map(|to| &**to)
+    }
+
+    /// Returns the set of the `T` linked to `to`, if any, without
+    /// cloning it.
+    pub fn backward_of(&self, to: Idx<U>) -> Option<&IdxSet<T>> {
+        self.backward.get(&to).map(|from| &**from)
+    }
+
+    /// Returns an iterator over the sources common to the relation and
+    /// `other`, with the objects they are linked to in both, e.g. to
+    /// compute co-occurrences without building intermediate sets.
+    pub fn zip_sources<'a, V>(
+        &'a self,
+        other: &'a ManyToMany<T, V>,
+    ) -> impl Iterator<Item = (Idx<T>, &'a IdxSet<U>, &'a IdxSet<V>)> + 'a {
+        self.forward.iter().filter_map(move |(&from, to)| {
+            other
+                .forward
+                .get(&from)
+                .map(|other_to| (from, &**to, &**other_to))
+        })
+    }
+
+    /// Returns, for each source, its targets grouped by `key`, e.g. the
+    /// routes of each line grouped by direction.
+    pub fn group_targets_by<K: Ord>(
+        &self,
+        key: impl Fn(Idx<U>) -> K,
+    ) -> BTreeMap<Idx<T>, BTreeMap<K, IdxSet<U>>> {
+        self.forward
+            .iter()
+            .map(|(&from, to)| {
+                let mut groups: BTreeMap<K, IdxSet<U>> = BTreeMap::default();
+                for &to_idx in to.iter() {
+                    groups.entry(key(to_idx)).or_default().insert(to_idx);
+                }
+                (from, groups)
+            })
+            .collect()
+    }
+
+    /// Returns the relation with its targets mapped by `f`, e.g. to fold
+    /// duplicated objects into one, several targets of a source mapped
+    /// to the same object giving a single link.
+    pub fn map_targets<V>(&self, f: impl Fn(Idx<U>) -> Idx<V>) -> ManyToMany<T, V> {
+        let mut mapped = ManyToMany::default();
+        mapped.extend_links(self.links().map(|link| Link(link.from(), f(link.to()))));
+        mapped
+    }
+
+    /// Returns the relation with its sources mapped by `f`, several
+    /// sources of a target mapped to the same object giving a single
+    /// link.
+    pub fn map_sources<V>(&self, f: impl Fn(Idx<T>) -> Idx<V>) -> ManyToMany<V, U> {
+        let mut mapped = ManyToMany::default();
+        mapped.extend_links(self.links().map(|link| Link(f(link.from()), link.to())));
+        mapped
+    }
+
+    /// Returns a guard to inspect and edit the `U` linked from `from`
+    /// in place, the relation being updated when the guard is
+    /// dropped.
+    pub fn entry(&mut self, from: Idx<T>) -> ManyToManyEntry<'_, T, U> {
+        ManyToManyEntry::new(self, from)
+    }
+
+    /// Returns an iterator over the links of the relation, sorted by
+    /// source with the default `BTreeMap` storage.
+    pub fn links(&self) -> impl Iterator<Item = Link<T, U>> + '_ {
+        self.forward
+            .iter()
+            .flat_map(|(&from, to_indices)| to_indices.iter().map(move |&to| Link(from, to)))
+    }
+
+    /// Adds the links of `other` to the relation, after remapping
+    /// their indices with `remaps`.
+    pub fn merge(&mut self, other: Self, remaps: &IdxRemaps)
+    where
+        T: 'static,
+        U: 'static,
+    {
+        for Link(from, to) in other.links() {
+            self.add_link(Link(remaps.apply(from), remaps.apply(to)));
+        }
+    }
+
+    /// Returns the inverse relation, from `U` to `T`. The sets of
+    /// indices are shared with this relation.
+    pub fn invert(&self) -> ManyToMany<U, T> {
+        ManyToMany {
+            forward: self.backward.clone(),
+            backward: self.forward.clone(),
+        }
+    }
+
+    /// Returns the inverse relation, from `U` to `T`, moving the maps
+    /// of this relation instead of copying them, unlike `invert`.
+    pub fn transpose(self) -> ManyToMany<U, T> {
+        ManyToMany {
+            forward: self.backward,
+            backward: self.forward,
+        }
+    }
+
+    /// Returns a copy of the relation containing only the links to
+    /// the given `targets`. The sets of sources of the targets are
+    /// shared with the relation.
+    pub fn restrict_to_targets(&self, targets: &IdxSet<U>) -> Self {
+        let backward: IdxMap<_, _> = targets
+            .iter()
+            .filter_map(|&to| self.backward.get(&to).map(|from| (to, from.clone())))
+            .collect();
+        let mut forward: BTreeMap<Idx<T>, IdxSet<U>> = BTreeMap::default();
+        for (&to, from) in &backward {
+            for &from_idx in from.iter() {
+                forward.entry(from_idx).or_default().insert(to);
+            }
+        }
+        ManyToMany {
+            forward: share_map(forward),
+            backward,
+        }
+    }
+
+    /// Removes the objects without any link, e.g. the ones kept by
+    /// `from_relations_chain`.
+    pub fn remove_empty_entries(&mut self) {
+        self.forward.retain(|_, indices| !indices.is_empty());
+        self.backward.retain(|_, indices| !indices.is_empty());
+    }
+
+    /// Rebuilds the internal storage to reclaim the memory lost after
+    /// a heavy mutation of the relation.
+    pub fn shrink(&mut self) {
+        shrink_shared_map(&mut self.forward);
+        shrink_shared_map(&mut self.backward);
+    }
+
+    /// Replaces the sets of indices of the relation by the equal
+    /// sets already known by the `interner`, so that the sets shared
+    /// by several relations are stored only once.
+    pub fn intern(&mut self, interner: &mut IdxSetInterner)
+    where
+        T: 'static,
+        U: 'static,
+    {
+        for indices in self.forward.values_mut() {
+            *indices = interner.intern(indices.clone());
+        }
+        for indices in self.backward.values_mut() {
+            *indices = interner.intern(indices.clone());
+        }
+    }
+}
+
+impl<T, U> From<&OneToMany<T, U>> for ManyToMany<T, U> {
+    fn from(relation: &OneToMany<T, U>) -> Self {
+        ManyToMany {
+            forward: share_map(relation.one_to_many.clone()),
+            backward: relation
+                .many_to_one
+                .iter()
+                .map(|(&to_idx, &from_idx)| (to_idx, Arc::new(singleton(from_idx))))
+                .collect(),
+        }
+    }
+}
+
+impl<T, U> From<BTreeMap<Idx<T>, IdxSet<U>>> for ManyToMany<T, U> {
+    fn from(forward: BTreeMap<Idx<T>, IdxSet<U>>) -> Self {
+        ManyToMany::from_forward(forward)
+    }
+}
+
+impl<T, U, S> From<HashMap<Idx<T>, IdxSet<U>, S>> for ManyToMany<T, U> {
+    fn from(forward: HashMap<Idx<T>, IdxSet<U>, S>) -> Self {
+        ManyToMany::from_forward(forward.into_iter().collect())
+    }
+}
+
+impl<T, U> From<OneToMany<T, U>> for ManyToMany<T, U> {
+    /// Converts the relation, reusing the sets of children as the
+    /// forward sets.
+    fn from(relation: OneToMany<T, U>) -> Self {
+        let backward = relation
+            .many_to_one
+            .into_iter()
+            .map(|(to_idx, from_idx)| (to_idx, Arc::new(singleton(from_idx))))
+            .collect();
+        ManyToMany {
+            forward: share_map(relation.one_to_many),
+            backward,
+        }
+    }
+}
+
+impl<T> ManyToMany<T, T> {
+    /// For a relation between objects of the same type, returns an
+    /// iterator over the objects linked from `idx`.
+    pub fn successors(&self, idx: Idx<T>) -> impl Iterator<Item = Idx<T>> + '_ {
+        shared_indices(&self.forward, idx)
+    }
+
+    /// For a relation between objects of the same type, returns an
+    /// iterator over the objects linked to `idx`.
+    pub fn predecessors(&self, idx: Idx<T>) -> impl Iterator<Item = Idx<T>> + '_ {
+        shared_indices(&self.backward, idx)
+    }
+
+    /// For a relation between objects of the same type, removes the
+    /// links from an object to itself, and returns their number.
+    pub fn remove_self_links(&mut self) -> usize {
+        let loops: Vec<_> = self
+            .forward
+            .iter()
+            .filter(|(idx, indices)| indices.contains(idx))
+            .map(|(&idx, _)| idx)
+            .collect();
+        for &idx in &loops {
+            self.remove_link(Link(idx, idx));
+        }
+        loops.len()
+    }
+
+    /// For a relation between objects of the same type, adds the
+    /// reverse of every link, so that `a` is linked to `b` if and only
+    /// if `b` is linked to `a`.
+    ///
+    /// The forward and backward sets are then equal, and are shared.
+    pub fn symmetrize(&mut self) {
+        let mut linked: BTreeMap<Idx<T>, IdxSet<T>> = BTreeMap::default();
+        for (&idx, indices) in self.forward.iter().chain(&self.backward) {
+            linked
+                .entry(idx)
+                .or_default()
+                .extend(indices.iter().cloned());
+        }
+        self.forward = share_map(linked);
+        self.backward = self.forward.clone();
+    }
 }
 
 impl<T, U> Relation for ManyToMany<T, U> {
     type From = T;
     type To = U;
-    fn get_from(&self) -> IdxSet<T> {
-        self.forward.keys().cloned().collect()
+    fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<T>> + '_> {
+        Box::new(self.forward.keys().cloned())
     }
-    fn get_to(&self) -> IdxSet<U> {
-        self.backward.keys().cloned().collect()
+    fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<U>> + '_> {
+        Box::new(self.backward.keys().cloned())
+    }
+    fn iter_links_by_target(&self) -> Box<dyn Iterator<Item = Link<T, U>> + '_> {
+        Box::new(self.backward.iter().flat_map(|(&to_idx, from_indices)| {
+            from_indices
+                .iter()
+                .map(move |&from_idx| Link(from_idx, to_idx))
+        }))
+    }
+    fn len_hint(&self) -> usize {
+        self.forward
+            .values()
+            .map(|to_indices| to_indices.len())
+            .sum()
     }
     fn get_corresponding_forward(&self, from: &IdxSet<T>) -> IdxSet<U> {
         get_corresponding(&self.forward, from)
@@ -191,11 +1607,396 @@ impl<T, U> Relation for ManyToMany<T, U> {
     fn get_corresponding_backward(&self, from: &IdxSet<U>) -> IdxSet<T> {
         get_corresponding(&self.backward, from)
     }
+    fn get_corresponding_forward_within(&self, from: &IdxSet<T>, allowed: &IdxSet<U>) -> IdxSet<U> {
+        get_corresponding_within(&self.forward, from, allowed)
+    }
+    fn get_corresponding_backward_within(
+        &self,
+        from: &IdxSet<U>,
+        allowed: &IdxSet<T>,
+    ) -> IdxSet<T> {
+        get_corresponding_within(&self.backward, from, allowed)
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.forward
+            .get(&link.from())
+            .is_some_and(|to_indices| to_indices.contains(&link.to()))
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for (&from_idx, to_indices) in &self.forward {
+            for &to_idx in to_indices.iter() {
+                f(from_idx, to_idx);
+            }
+        }
+    }
+}
+
+/// Deduplicates equal sets of indices, so that they are stored
+/// only once. A single interner can be used for all the relations
+/// of a model, whatever the types of their objects.
+#[derive(Default)]
+pub struct IdxSetInterner {
+    sets: HashMap<TypeId, Box<dyn Any>>,
 }
 
-fn get_corresponding<T, U>(map: &BTreeMap<Idx<T>, IdxSet<U>>, from: &IdxSet<T>) -> IdxSet<U> {
+impl IdxSetInterner {
+    /// Returns the shared set equal to `indices`, storing it if it
+    /// was not already known.
+    pub fn intern<T: 'static>(&mut self, indices: SharedIdxSet<T>) -> SharedIdxSet<T> {
+        let sets = self
+            .sets
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(BTreeSet::<SharedIdxSet<T>>::new()))
+            .downcast_mut::<BTreeSet<SharedIdxSet<T>>>()
+            .expect("sets are stored by type");
+        match sets.get(&indices) {
+            Some(shared) => shared.clone(),
+            None => {
+                sets.insert(indices.clone());
+                indices
+            }
+        }
+    }
+}
+
+pub(crate) fn get_corresponding<T, U, S>(map: &IdxMap<Idx<T>, S>, from: &IdxSet<T>) -> IdxSet<U>
+where
+    S: Borrow<IdxSet<U>>,
+{
     from.iter()
         .filter_map(|from_idx| map.get(from_idx))
+        .flat_map(|indices| indices.borrow().iter().cloned())
+        .collect()
+}
+
+pub(crate) fn get_corresponding_within<T, U, S>(
+    map: &IdxMap<Idx<T>, S>,
+    from: &IdxSet<T>,
+    allowed: &IdxSet<U>,
+) -> IdxSet<U>
+where
+    S: Borrow<IdxSet<U>>,
+{
+    from.iter()
+        .filter_map(|from_idx| map.get(from_idx))
+        .flat_map(|indices| indices.borrow().iter().cloned())
+        .filter(|idx| allowed.contains(idx))
+        .collect()
+}
+
+fn singleton<T>(idx: Idx<T>) -> IdxSet<T> {
+    Some(idx).into_iter().collect()
+}
+
+fn shared_indices<T, U>(
+    map: &IdxMap<Idx<T>, SharedIdxSet<U>>,
+    idx: Idx<T>,
+) -> impl Iterator<Item = Idx<U>> + '_ {
+    map.get(&idx)
+        .into_iter()
         .flat_map(|indices| indices.iter().cloned())
+}
+
+fn share_map<T, U>(
+    map: impl IntoIterator<Item = (Idx<T>, IdxSet<U>)>,
+) -> IdxMap<Idx<T>, SharedIdxSet<U>> {
+    map.into_iter()
+        .map(|(idx, indices)| (idx, Arc::new(indices)))
         .collect()
 }
+
+pub(crate) fn shrink_map<T, U>(map: &mut IdxMap<Idx<T>, IdxSet<U>>) {
+    // collecting sorted items bulk-builds densely packed trees
+    *map = std::mem::take(map)
+        .into_iter()
+        .map(|(idx, indices)| (idx, indices.into_iter().collect()))
+        .collect();
+}
+
+fn remove_shared<T, U>(
+    map: &mut IdxMap<Idx<T>, SharedIdxSet<U>>,
+    key: Idx<T>,
+    value: Idx<U>,
+) -> bool {
+    let indices = match map.get_mut(&key) {
+        Some(indices) if indices.contains(&value) => indices,
+        _ => return false,
+    };
+    Arc::make_mut(indices).remove(&value);
+    if indices.is_empty() {
+        map.remove(&key);
+    }
+    true
+}
+
+fn shrink_shared_map<T, U>(map: &mut IdxMap<Idx<T>, SharedIdxSet<U>>) {
+    *map = std::mem::take(map)
+        .into_iter()
+        .map(|(idx, indices)| match Arc::try_unwrap(indices) {
+            Ok(indices) => (idx, Arc::new(indices.into_iter().collect())),
+            // shared sets are left untouched
+            Err(shared) => (idx, shared),
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // `a2` has the feline `f2`, without any cat
+    fn chained_relations() -> (
+        CollectionWithId<Animal>,
+        OneToMany<Animal, Feline>,
+        OneToMany<Feline, Cat>,
+    ) {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let cats = cats(&[("c1", "f1")]);
+        let animals_to_felines = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        let felines_to_cats = OneToMany::new(&felines, &cats, "felines_to_cats").unwrap();
+        (animals, animals_to_felines, felines_to_cats)
+    }
+
+    // implemented as before `iter_from` and `iter_to` were introduced
+    struct LegacyRelation(OneToMany<Animal, Feline>);
+
+    impl Relation for LegacyRelation {
+        type From = Animal;
+        type To = Feline;
+
+        fn get_from(&self) -> IdxSet<Animal> {
+            self.0.iter_from().collect()
+        }
+        fn get_to(&self) -> IdxSet<Feline> {
+            self.0.iter_to().collect()
+        }
+        fn get_corresponding_forward(&self, from: &IdxSet<Animal>) -> IdxSet<Feline> {
+            self.0.get_corresponding_forward(from)
+        }
+        fn get_corresponding_backward(&self, from: &IdxSet<Feline>) -> IdxSet<Animal> {
+            self.0.get_corresponding_backward(from)
+        }
+    }
+
+    #[test]
+    fn iter_from_defaults_to_get_from() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1")]);
+        let relation = LegacyRelation(OneToMany::new(&animals, &felines, "a_to_f").unwrap());
+        let a1 = animals.get_idx("a1").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        assert_eq!(vec![a1], relation.iter_from().collect::<Vec<_>>());
+        assert_eq!(vec![f1], relation.iter_to().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn from_relations_chain_keeps_sources_without_targets() {
+        let (animals, animals_to_felines, felines_to_cats) = chained_relations();
+        let mut chain = ManyToMany::from_relations_chain(&animals_to_felines, &felines_to_cats);
+        let a2 = animals.get_idx("a2").unwrap();
+        assert_eq!(
+            animals_to_felines.iter_from().collect::<IdxSet<_>>(),
+            chain.iter_from().collect::<IdxSet<_>>()
+        );
+        assert!(chain
+            .get_corresponding_forward(&IdxSet::from([a2]))
+            .is_empty());
+        assert_eq!(1, chain.len_hint());
+        chain.remove_empty_entries();
+        assert!(chain.iter_from().all(|idx| idx != a2));
+    }
+
+    #[test]
+    fn try_from_many_to_many() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        // `a2` is kept without any feline
+        let forward = BTreeMap::from([(a1, IdxSet::from([f1])), (a2, IdxSet::default())]);
+        let relation = ManyToMany::from_forward(forward);
+        let one_to_many =
+            OneToMany::try_from_many_to_many(&relation, &animals, &felines, "animals_to_felines")
+                .unwrap();
+        assert_eq!(Some(a1), one_to_many.parent_of(f1));
+        assert!(one_to_many.iter_from().eq(vec![a1]));
+    }
+
+    #[test]
+    fn try_from_many_to_many_with_several_sources() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1")]);
+        let mut relation = ManyToMany::default();
+        let f1 = felines.get_idx("f1").unwrap();
+        for (_, animal) in &animals {
+            relation.add_link(Link(animals.get_idx(&animal.id).unwrap(), f1));
+        }
+        let error =
+            OneToMany::try_from_many_to_many(&relation, &animals, &felines, "animals_to_felines")
+                .unwrap_err();
+        match error {
+            Error::Anomalies(rel_name, anomalies) => {
+                assert_eq!("animals_to_felines", rel_name);
+                assert_eq!(
+                    vec![Anomaly::ConflictingParents {
+                        child_id: "f1".to_owned(),
+                        parent_ids: vec!["a1".to_owned(), "a2".to_owned()],
+                    }],
+                    anomalies
+                );
+            }
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    #[test]
+    fn new_strict() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a1")]);
+        let relation =
+            OneToMany::new_strict(&animals, &felines, vec![], "animals_to_felines").unwrap();
+        let a1 = animals.get_idx("a1").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        assert_eq!(Some(a1), relation.parent_of(f1));
+        assert_eq!(Some(a1), relation.parent_of(f2));
+        // an additional link agreeing with the child is accepted
+        let relation =
+            OneToMany::new_strict(&animals, &felines, vec![("a1", "f2")], "animals_to_felines")
+                .unwrap();
+        assert_eq!(2, relation.iter_to().count());
+    }
+
+    #[test]
+    fn new_strict_reports_every_anomaly() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a3"), ("f3", "a2")]);
+        let extra_links = vec![("a1", "f4"), ("a1", "f3")];
+        let error = OneToMany::new_strict(&animals, &felines, extra_links, "animals_to_felines")
+            .unwrap_err();
+        match error {
+            Error::Anomalies(rel_name, anomalies) => {
+                assert_eq!("animals_to_felines", rel_name);
+                assert_eq!(
+                    vec![
+                        Anomaly::UnknownParent {
+                            child_id: "f2".to_owned(),
+                            parent_id: "a3".to_owned(),
+                        },
+                        Anomaly::UnknownChild("f4".to_owned()),
+                        Anomaly::ConflictingParents {
+                            child_id: "f3".to_owned(),
+                            parent_ids: vec!["a1".to_owned(), "a2".to_owned()],
+                        },
+                    ],
+                    anomalies
+                );
+            }
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    #[test]
+    fn add_link_replaces_the_previous_parent() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let mut relation = OneToMany::default();
+        relation.add_link(Link(a1, f1));
+        assert_eq!(Some(a1), relation.parent_of(f1));
+        relation.add_link((a2, f1));
+        assert_eq!(Some(a2), relation.parent_of(f1));
+        // `a1` has no child anymore
+        assert!(relation.iter_from().eq(vec![a2]));
+        assert!(!relation.contains_link(Link(a1, f1)));
+    }
+
+    #[test]
+    fn try_add_link() {
+        let larger_animals = animals(&["a1", "a2", "a3"]);
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let mut relation = OneToMany::default();
+        relation.try_add_link(a1, f1, &animals, &felines).unwrap();
+        assert_eq!(Some(a1), relation.parent_of(f1));
+
+        match relation.try_add_link(a2, f1, &animals, &felines) {
+            Err(Error::InconsistentLink(child_id, parent_id, referenced_id)) => {
+                assert_eq!(
+                    ("f1", "a2", "a1"),
+                    (&*child_id, &*parent_id, &*referenced_id)
+                );
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        // an index of a larger collection
+        let a3 = larger_animals.get_idx("a3").unwrap();
+        match relation.try_add_link(a3, f1, &animals, &felines) {
+            Err(Error::IndexOutOfBounds(2, 2)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        // the failed links are not added
+        assert!(relation.iter_from().eq(vec![a1]));
+    }
+
+    #[test]
+    fn many_to_one_merge_and_shrink() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        let mut relation = OneToMany::new(&animals, &felines, "animals_to_felines")
+            .unwrap()
+            .invert();
+        let mut other = ManyToOne::default();
+        other.many_to_one.insert(f1, a2);
+        other.one_to_many.insert(a2, IdxSet::from([f1]));
+        relation.merge(other, &IdxRemaps::default());
+        relation.shrink();
+        assert_eq!(vec![Link(f1, a2), Link(f2, a2)], relation.links_sorted());
+        assert!(relation.iter_to().eq(vec![a2]));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn sample_subrelation() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let animals = animals(&["a1", "a2"]);
+        let ids: Vec<_> = (0..10)
+            .map(|i| (format!("f{}", i), if i % 2 == 0 { "a1" } else { "a2" }))
+            .collect();
+        let ids: Vec<_> = ids
+            .iter()
+            .map(|(id, animal_id)| (id.as_str(), *animal_id))
+            .collect();
+        let felines = felines(&ids);
+        let relation = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+        let sample = relation.sample_subrelation(0.3, &mut rng);
+        assert_eq!(3, sample.iter_to().count());
+        assert!(sample.is_subrelation_of(&relation));
+        // the sample is a consistent relation
+        for feline_idx in sample.iter_to() {
+            let animal_idx = sample.parent_of(feline_idx).unwrap();
+            assert!(sample
+                .get_corresponding_forward(&IdxSet::from([animal_idx]))
+                .contains(&feline_idx));
+        }
+        let sample = relation.sample_subrelation(1., &mut rng);
+        assert_eq!(10, sample.iter_to().count());
+        assert!(relation.is_subrelation_of(&sample));
+        let sample = relation.sample_subrelation(0., &mut rng);
+        assert_eq!(0, sample.iter_from().count());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{IdxSet, ManyToMany, Relation};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use typed_index_collection::{CollectionWithId, Id, Idx};
+
+type Remap<T> = Box<dyn Fn(Idx<T>) -> Idx<T>>;
+
+/// A set of functions remapping the indices of objects, one for each
+/// type of object, used to merge relations built over different
+/// collections.
+///
+/// The indices of the types without any remapping are left
+/// untouched.
+#[derive(Default)]
+pub struct IdxRemaps {
+    remaps: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl IdxRemaps {
+    /// Sets the function remapping the indices of `T`, replacing the
+    /// previous one if any.
+    pub fn insert<T: 'static>(&mut self, remap: impl Fn(Idx<T>) -> Idx<T> + 'static) -> &mut Self {
+        let remap: Remap<T> = Box::new(remap);
+        self.remaps.insert(TypeId::of::<T>(), Box::new(remap));
+        self
+    }
+
+    /// Sets the remapping of the indices of `T` from a table of
+    /// indices. The indices absent from the table are left untouched.
+    pub fn insert_table<T: 'static>(&mut self, table: HashMap<Idx<T>, Idx<T>>) -> &mut Self {
+        self.insert(move |idx| table.get(&idx).cloned().unwrap_or(idx))
+    }
+
+    /// Returns the remapped index of `idx`.
+    pub fn apply<T: 'static>(&self, idx: Idx<T>) -> Idx<T> {
+        let remap = match self.remaps.get(&TypeId::of::<T>()) {
+            Some(remap) => remap,
+            None => return idx,
+        };
+        let remap = remap
+            .downcast_ref::<Remap<T>>()
+            .expect("remaps are stored by type");
+        remap(idx)
+    }
+}
+
+type Table<T> = HashMap<Idx<T>, Idx<T>>;
+
+/// Translation of the indices of objects from a model to another,
+/// the objects being matched by their identifiers, e.g. to compare
+/// the relations of 2 models built from different data.
+///
+/// Unlike `IdxRemaps`, the indices of the types without any table
+/// have no translation.
+#[derive(Default)]
+pub struct IdxMapper {
+    tables: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl IdxMapper {
+    /// Sets the translation of the indices of `T`, from the indices of
+    /// `from` to the indices of the objects of `to` with the same
+    /// identifiers, replacing the previous one if any.
+    pub fn insert<T>(&mut self, from: &CollectionWithId<T>, to: &CollectionWithId<T>) -> &mut Self
+    where
+        T: Id<T> + 'static,
+    {
+        let table: Table<T> = from
+            .iter()
+            .filter_map(|(idx, obj)| Some((idx, to.get_idx(obj.id())?)))
+            .collect();
+        self.tables.insert(TypeId::of::<T>(), Box::new(table));
+        self
+    }
+
+    /// Returns the translation of `idx`, or `None` if the object is
+    /// absent from the other model or if the indices of `T` have no
+    /// translation.
+    pub fn map<T: 'static>(&self, idx: Idx<T>) -> Option<Idx<T>> {
+        let table = self
+            .tables
+            .get(&TypeId::of::<T>())?
+            .downcast_ref::<Table<T>>()
+            .expect("tables are stored by type");
+        table.get(&idx).cloned()
+    }
+
+    /// Returns the translation of the objects of `set`, the objects
+    /// without translation being skipped.
+    pub fn map_set<T: 'static>(&self, set: &IdxSet<T>) -> IdxSet<T> {
+        set.iter().filter_map(|&idx| self.map(idx)).collect()
+    }
+
+    /// Returns the translation of the links of `relation`, the links
+    /// with an object without translation being skipped.
+    pub fn map_relation<R>(&self, relation: &R) -> ManyToMany<R::From, R::To>
+    where
+        R: Relation,
+        R::From: 'static,
+        R::To: 'static,
+    {
+        let mut links = Vec::new();
+        relation.for_each_link(|from_idx, to_idx| {
+            if let (Some(from_idx), Some(to_idx)) = (self.map(from_idx), self.map(to_idx)) {
+                links.push((from_idx, to_idx));
+            }
+        });
+        links.sort_unstable();
+        let mut mapped = ManyToMany::default();
+        mapped.extend_links(links);
+        mapped
+    }
+}
@@ -0,0 +1,344 @@
+use crate::{IdxSet, Link, ManyToMany, Relation};
+use std::collections::BTreeMap;
+use std::ops::Bound::{Excluded, Unbounded};
+use typed_index_collection::Idx;
+
+/// A many to many relation recording the revision at which each link
+/// was added, so that the caches built from the relation can be
+/// updated from the changes since their last revision, without
+/// comparing the whole relation.
+///
+/// Each change of the relation increments the revision.
+#[derive(Debug)]
+pub struct RevisionedManyToMany<T, U> {
+    relation: ManyToMany<T, U>,
+    revision: u64,
+    revisions: BTreeMap<Link<T, U>, u64>,
+    added: BTreeMap<u64, Link<T, U>>,
+    // sorted by revision of removal, with the revision at which the
+    // link was added
+    removed: Vec<(u64, Link<T, U>, u64)>,
+}
+
+impl<T, U> Default for RevisionedManyToMany<T, U> {
+    fn default() -> Self {
+        RevisionedManyToMany {
+            relation: Default::default(),
+            revision: Default::default(),
+            revisions: Default::default(),
+            added: Default::default(),
+            removed: Default::default(),
+        }
+    }
+}
+
+impl<T, U> RevisionedManyToMany<T, U> {
+    /// Returns the revision of the last change of the relation, 0 if
+    /// it was never changed.
+    pub fn current_revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Adds a link, returning `true` if it was not in the relation.
+    pub fn add_link(&mut self, link: impl Into<Link<T, U>>) -> bool {
+        let link = link.into();
+        if self.revisions.contains_key(&link) {
+            return false;
+        }
+        self.revision += 1;
+        self.revisions.insert(link, self.revision);
+        self.added.insert(self.revision, link);
+        self.relation.add_link(link);
+        true
+    }
+
+    /// Removes a link, returning `true` if it was in the relation.
+    pub fn remove_link(&mut self, link: impl Into<Link<T, U>>) -> bool {
+        let link = link.into();
+        let revision = match self.revisions.remove(&link) {
+            Some(revision) => revision,
+            None => return false,
+        };
+        self.added.remove(&revision);
+        self.revision += 1;
+        self.removed.push((self.revision, link, revision));
+        self.relation.remove_link(link);
+        true
+    }
+
+    /// Returns the revision at which `link` was added, if it is in the
+    /// relation.
+    pub fn revision_of(&self, link: Link<T, U>) -> Option<u64> {
+        self.revisions.get(&link).copied()
+    }
+
+    /// Returns an iterator over the links added after `revision` and
+    /// still in the relation, with the revision at which they were
+    /// added, sorted by revision.
+    pub fn links_since(&self, revision: u64) -> impl Iterator<Item = (Link<T, U>, u64)> + '_ {
+        self.added
+            .range((Excluded(revision), Unbounded))
+            .map(|(&revision, &link)| (link, revision))
+    }
+
+    /// Returns an iterator over the links removed after `revision`,
+    /// sorted by revision. A link removed then added again appears
+    /// both here and in `links_since`.
+    pub fn removed_since(&self, revision: u64) -> impl Iterator<Item = Link<T, U>> + '_ {
+        let start = self
+            .removed
+            .partition_point(|&(removal, _, _)| removal <= revision);
+        self.removed[start..].iter().map(|&(_, link, _)| link)
+    }
+
+    /// Forgets the links removed up to `revision`, e.g. once every
+    /// cache is up to date with it, to reclaim their memory. The views
+    /// returned by `at` for a revision before `revision` then miss
+    /// these links.
+    pub fn truncate_removed(&mut self, revision: u64) {
+        let end = self
+            .removed
+            .partition_point(|&(removal, _, _)| removal <= revision);
+        self.removed.drain(..end);
+    }
+
+    /// Returns the relation without the revisions.
+    pub fn relation(&self) -> &ManyToMany<T, U> {
+        &self.relation
+    }
+
+    /// Returns a view of the relation as it was at `revision`, e.g. to
+    /// compare the model before and after an update, without keeping
+    /// a copy of the relation.
+    ///
+    /// The view goes through the links removed after `revision`, thus
+    /// it is slower for older revisions.
+    pub fn at(&self, revision: u64) -> RelationAt<'_, T, U> {
+        RelationAt {
+            relation: self,
+            revision,
+        }
+    }
+}
+
+/// A view of a `RevisionedManyToMany` as it was at a revision, as
+/// returned by `RevisionedManyToMany::at`.
+pub struct RelationAt<'a, T, U> {
+    relation: &'a RevisionedManyToMany<T, U>,
+    revision: u64,
+}
+
+impl<'a, T, U> RelationAt<'a, T, U> {
+    /// Returns the revision of the view.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns an iterator over the links removed after the revision
+    /// of the view, but already added at this revision.
+    fn removed_links(&self) -> impl Iterator<Item = Link<T, U>> + 'a {
+        let revision = self.revision;
+        let removed = &self.relation.removed;
+        let start = removed.partition_point(|&(removal, _, _)| removal <= revision);
+        removed[start..]
+            .iter()
+            .filter(move |&&(_, _, added)| added <= revision)
+            .map(|&(_, link, _)| link)
+    }
+
+    /// Returns `true` if `link` is in the relation and was already
+    /// added at the revision of the view.
+    fn is_current(&self, link: Link<T, U>) -> bool {
+        self.relation
+            .revision_of(link)
+            .is_some_and(|added| added <= self.revision)
+    }
+}
+
+impl<T, U> Relation for RelationAt<'_, T, U> {
+    type From = T;
+    type To = U;
+    fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<T>> + '_> {
+        let mut sources = IdxSet::default();
+        self.for_each_link(|from, _| {
+            sources.insert(from);
+        });
+        Box::new(sources.into_iter())
+    }
+    fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<U>> + '_> {
+        let mut targets = IdxSet::default();
+        self.for_each_link(|_, to| {
+            targets.insert(to);
+        });
+        Box::new(targets.into_iter())
+    }
+    fn get_corresponding_forward(&self, from: &IdxSet<T>) -> IdxSet<U> {
+        let mut targets: IdxSet<U> = from
+            .iter()
+            .flat_map(|&from_idx| {
+                self.relation
+                    .relation
+                    .forward_of(from_idx)
+                    .into_iter()
+                    .flatten()
+                    .filter(move |&&to_idx| self.is_current(Link(from_idx, to_idx)))
+                    .cloned()
+            })
+            .collect();
+        targets.extend(
+            self.removed_links()
+                .filter(|link| from.contains(&link.from()))
+                .map(|link| link.to()),
+        );
+        targets
+    }
+    fn get_corresponding_backward(&self, from: &IdxSet<U>) -> IdxSet<T> {
+        let mut sources: IdxSet<T> = from
+            .iter()
+            .flat_map(|&to_idx| {
+                self.relation
+                    .relation
+                    .backward_of(to_idx)
+                    .into_iter()
+                    .flatten()
+                    .filter(move |&&from_idx| self.is_current(Link(from_idx, to_idx)))
+                    .cloned()
+            })
+            .collect();
+        sources.extend(
+            self.removed_links()
+                .filter(|link| from.contains(&link.to()))
+                .map(|link| link.from()),
+        );
+        sources
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.is_current(link) || self.removed_links().any(|removed| removed == link)
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for (&link, &added) in &self.relation.revisions {
+            if added <= self.revision {
+                f(link.from(), link.to());
+            }
+        }
+        for link in self.removed_links() {
+            f(link.from(), link.to());
+        }
+    }
+}
+
+impl<T, U> Relation for RevisionedManyToMany<T, U> {
+    type From = T;
+    type To = U;
+    forward_relation!(
+        relation,
+        iter_from,
+        iter_to,
+        get_corresponding_forward,
+        get_corresponding_backward,
+        get_corresponding_forward_within,
+        get_corresponding_backward_within
+    );
+    fn len_hint(&self) -> usize {
+        self.revisions.len()
+    }
+    fn contains_link(&self, link: Link<T, U>) -> bool {
+        self.revisions.contains_key(&link)
+    }
+    fn for_each_link(&self, mut f: impl FnMut(Idx<T>, Idx<U>)) {
+        for link in self.revisions.keys() {
+            f(link.from(), link.to());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn links_at<T, U>(relation: &RevisionedManyToMany<T, U>, revision: u64) -> Vec<Link<T, U>> {
+        let mut links = Vec::new();
+        relation
+            .at(revision)
+            .for_each_link(|from, to| links.push(Link(from, to)));
+        links
+    }
+
+    #[test]
+    fn link_removed_and_added_again() {
+        let animals = animals(&["a1"]);
+        let felines = felines(&[("f1", "a1")]);
+        let link = Link(
+            animals.get_idx("a1").unwrap(),
+            felines.get_idx("f1").unwrap(),
+        );
+        let mut relation = RevisionedManyToMany::default();
+        assert!(relation.add_link(link));
+        assert!(relation.remove_link(link));
+        assert!(relation.add_link(link));
+        assert_eq!(3, relation.current_revision());
+        assert_eq!(Some(3), relation.revision_of(link));
+        assert!(relation.at(1).contains_link(link));
+        assert!(!relation.at(2).contains_link(link));
+        assert!(relation.at(3).contains_link(link));
+        assert_eq!(vec![(link, 3)], relation.links_since(1).collect::<Vec<_>>());
+        assert_eq!(vec![link], relation.removed_since(1).collect::<Vec<_>>());
+        assert!(relation.removed_since(2).next().is_none());
+    }
+
+    #[test]
+    fn at_does_not_return_duplicates() {
+        let animals = animals(&["a1"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a1")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let link = Link(a1, felines.get_idx("f1").unwrap());
+        let other = Link(a1, felines.get_idx("f2").unwrap());
+        let mut relation = RevisionedManyToMany::default();
+        relation.add_link(link); // 1
+        relation.add_link(other); // 2
+        relation.remove_link(link); // 3
+        relation.add_link(link); // 4
+        relation.remove_link(link); // 5
+        relation.add_link(link); // 6
+        let expected = [
+            vec![],
+            vec![link],
+            vec![link, other],
+            vec![other],
+            vec![link, other],
+            vec![other],
+            vec![link, other],
+        ];
+        for (revision, expected) in expected.iter().enumerate() {
+            let mut links = links_at(&relation, revision as u64);
+            links.sort();
+            assert_eq!(expected, &links, "at revision {}", revision);
+            let view = relation.at(revision as u64);
+            let targets = view.get_corresponding_forward(&Some(a1).into_iter().collect());
+            assert_eq!(expected.len(), targets.len(), "at revision {}", revision);
+        }
+    }
+
+    #[test]
+    fn truncate_removed_drops_the_history() {
+        let animals = animals(&["a1"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a1")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let link = Link(a1, felines.get_idx("f1").unwrap());
+        let other = Link(a1, felines.get_idx("f2").unwrap());
+        let mut relation = RevisionedManyToMany::default();
+        relation.add_link(link); // 1
+        relation.add_link(other); // 2
+        relation.remove_link(link); // 3
+        relation.remove_link(other); // 4
+        relation.truncate_removed(3);
+        assert_eq!(vec![other], relation.removed_since(0).collect::<Vec<_>>());
+        // the view misses the forgotten removal only
+        assert_eq!(vec![other], links_at(&relation, 2));
+        assert!(relation.relation().links().next().is_none());
+        relation.truncate_removed(relation.current_revision());
+        assert!(relation.removed_since(0).next().is_none());
+        assert!(links_at(&relation, 2).is_empty());
+    }
+}
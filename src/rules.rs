@@ -0,0 +1,434 @@
+//! A small rule engine deriving relations from other relations.
+//!
+//! A derived relation is declared as a conjunction of relations, in
+//! a Datalog-like syntax, e.g. `grandparent(X, Z) :- parent(X, Y),
+//! parent(Y, Z)`. The rules can be recursive, and are evaluated
+//! until a fixpoint is reached, using the semi-naive evaluation: at
+//! each iteration, only the links found at the previous iteration are
+//! joined with the other relations.
+//!
+//! All the relations link objects of the same type.
+//!
+//! ```
+//! # use relational_types::{rules::Program, ManyToMany};
+//! # struct Person;
+//! # fn load_parents() -> ManyToMany<Person, Person> { ManyToMany::default() }
+//! let parents = load_parents();
+//! let mut program = Program::default();
+//! program.relation("parent", &parents);
+//! program.rule("ancestor(X, Y) :- parent(X, Y)").unwrap();
+//! program.rule("ancestor(X, Z) :- parent(X, Y), ancestor(Y, Z)").unwrap();
+//! let ancestors = program.evaluate().remove("ancestor").unwrap();
+//! ```
+
+use crate::{Error, Link, ManyToMany, Relation};
+use std::collections::{BTreeMap, BTreeSet};
+use typed_index_collection::Idx;
+
+type Facts<T> = BTreeSet<Link<T, T>>;
+
+/// The links of a relation, indexed by source and by target to join
+/// them with the other atoms of a rule. The indexes are updated with
+/// the links, instead of being rebuilt at each iteration.
+struct IndexedFacts<T> {
+    links: Facts<T>,
+    forward: BTreeMap<Idx<T>, Vec<Idx<T>>>,
+    backward: BTreeMap<Idx<T>, Vec<Idx<T>>>,
+}
+
+impl<T> Default for IndexedFacts<T> {
+    fn default() -> Self {
+        IndexedFacts {
+            links: Default::default(),
+            forward: Default::default(),
+            backward: Default::default(),
+        }
+    }
+}
+
+impl<T> IndexedFacts<T> {
+    fn insert(&mut self, link: Link<T, T>) {
+        if self.links.insert(link) {
+            self.forward.entry(link.from()).or_default().push(link.to());
+            self.backward
+                .entry(link.to())
+                .or_default()
+                .push(link.from());
+        }
+    }
+}
+
+impl<T> Extend<Link<T, T>> for IndexedFacts<T> {
+    fn extend<I: IntoIterator<Item = Link<T, T>>>(&mut self, links: I) {
+        for link in links {
+            self.insert(link);
+        }
+    }
+}
+
+/// A set of base relations and of rules deriving new relations.
+pub struct Program<T> {
+    facts: BTreeMap<String, Facts<T>>,
+    rules: Vec<Rule>,
+}
+
+impl<T> Default for Program<T> {
+    fn default() -> Self {
+        Program {
+            facts: BTreeMap::default(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+struct Atom {
+    relation: String,
+    from: usize,
+    to: usize,
+}
+
+struct Rule {
+    head: Atom,
+    body: Vec<Atom>,
+    nb_variables: usize,
+}
+
+impl<T> Program<T> {
+    /// Adds the links of `relation` to the base relation named
+    /// `name`.
+    pub fn relation<R>(&mut self, name: &str, relation: &R) -> &mut Self
+    where
+        R: Relation<From = T, To = T>,
+    {
+        let facts = self.facts.entry(name.to_owned()).or_default();
+        relation.for_each_link(|from_idx, to_idx| {
+            facts.insert(Link(from_idx, to_idx));
+        });
+        self
+    }
+
+    /// Adds a rule, e.g. `grandparent(X, Z) :- parent(X, Y),
+    /// parent(Y, Z)`.
+    ///
+    /// Every variable of the head must appear in the body.
+    pub fn rule(&mut self, rule: &str) -> Result<&mut Self, Error> {
+        let invalid = |reason: &str| Error::InvalidRule(rule.to_owned(), reason.to_owned());
+        let mut split = rule.splitn(2, ":-");
+        let (head, body) = match (split.next(), split.next()) {
+            (Some(head), Some(body)) => (head, body),
+            _ => return Err(invalid("`:-` is missing")),
+        };
+        let mut variables = Vec::new();
+        let mut body_atoms = Vec::new();
+        for atom in body.split(')').map(|atom| atom.trim()) {
+            if atom.is_empty() {
+                continue;
+            }
+            let atom = match atom.strip_prefix(',') {
+                Some(atom) if !body_atoms.is_empty() => atom,
+                None if body_atoms.is_empty() => atom,
+                _ => return Err(invalid("the atoms of the body must be separated by `,`")),
+            };
+            let atom = parse_atom(atom, &mut variables, true)
+                .ok_or_else(|| invalid("the body must be atoms like `relation(X, Y)`"))?;
+            body_atoms.push(atom);
+        }
+        if body_atoms.is_empty() {
+            return Err(invalid("the body is empty"));
+        }
+        let head = head.trim();
+        let head = head
+            .strip_suffix(')')
+            .and_then(|head| parse_atom(head, &mut variables, false))
+            .ok_or_else(|| {
+                invalid(
+                    "the head must be an atom like `relation(X, Y)` using variables of the body",
+                )
+            })?;
+        self.facts.entry(head.relation.clone()).or_default();
+        self.rules.push(Rule {
+            head,
+            body: body_atoms,
+            nb_variables: variables.len(),
+        });
+        Ok(self)
+    }
+
+    /// Evaluates the rules until no more link can be derived, and
+    /// returns the relations defined by the heads of the rules.
+    pub fn evaluate(&self) -> BTreeMap<String, ManyToMany<T, T>> {
+        // the links known before the last iteration, the ones found by
+        // the last iteration, and both
+        let mut old: BTreeMap<&str, IndexedFacts<T>> = BTreeMap::default();
+        let mut deltas: BTreeMap<&str, IndexedFacts<T>> = BTreeMap::default();
+        let mut facts: BTreeMap<&str, IndexedFacts<T>> = BTreeMap::default();
+        // at the first iteration, every link is new
+        for (name, links) in &self.facts {
+            old.entry(name).or_default();
+            deltas
+                .entry(name)
+                .or_default()
+                .extend(links.iter().cloned());
+            facts.entry(name).or_default().extend(links.iter().cloned());
+        }
+        while deltas.values().any(|delta| !delta.links.is_empty()) {
+            let mut new_facts: BTreeMap<&str, IndexedFacts<T>> = BTreeMap::default();
+            for rule in &self.rules {
+                let head = rule.head.relation.as_str();
+                for position in 0..rule.body.len() {
+                    let links = rule.evaluate(&old, &deltas, &facts, position);
+                    let known = &facts[head].links;
+                    new_facts
+                        .entry(head)
+                        .or_default()
+                        .extend(links.into_iter().filter(|link| !known.contains(link)));
+                }
+            }
+            for (name, delta) in deltas {
+                old.entry(name).or_default().extend(delta.links);
+            }
+            for (name, links) in &new_facts {
+                facts
+                    .get_mut(name)
+                    .expect("heads are known relations")
+                    .extend(links.links.iter().cloned());
+            }
+            deltas = new_facts;
+        }
+        self.rules
+            .iter()
+            .map(|rule| rule.head.relation.as_str())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|name| {
+                let mut forward: BTreeMap<Idx<T>, BTreeSet<Idx<T>>> = BTreeMap::default();
+                for link in &facts[name].links {
+                    forward.entry(link.from()).or_default().insert(link.to());
+                }
+                (name.to_owned(), ManyToMany::from_forward(forward))
+            })
+            .collect()
+    }
+}
+
+impl Rule {
+    /// Evaluates the body, using only the new links for the atom at
+    /// `position`. To find each combination of links once, the atoms
+    /// before use all the links, and the ones after the old links
+    /// only.
+    fn evaluate<T>(
+        &self,
+        old: &BTreeMap<&str, IndexedFacts<T>>,
+        deltas: &BTreeMap<&str, IndexedFacts<T>>,
+        facts: &BTreeMap<&str, IndexedFacts<T>>,
+        position: usize,
+    ) -> Vec<Link<T, T>> {
+        let empty = IndexedFacts::default();
+        let no_indices = Vec::new();
+        let mut bindings: Vec<Vec<Option<Idx<T>>>> = vec![vec![None; self.nb_variables]];
+        for (i, atom) in self.body.iter().enumerate() {
+            let source = match i.cmp(&position) {
+                std::cmp::Ordering::Less => facts,
+                std::cmp::Ordering::Equal => deltas,
+                std::cmp::Ordering::Greater => old,
+            };
+            let indexed = source.get(atom.relation.as_str()).unwrap_or(&empty);
+            let mut next_bindings = Vec::new();
+            for binding in bindings {
+                let candidates: Vec<Link<T, T>> = match (binding[atom.from], binding[atom.to]) {
+                    (Some(from_idx), Some(to_idx)) => Some(Link(from_idx, to_idx))
+                        .filter(|link| indexed.links.contains(link))
+                        .into_iter()
+                        .collect(),
+                    (Some(from_idx), None) => indexed
+                        .forward
+                        .get(&from_idx)
+                        .unwrap_or(&no_indices)
+                        .iter()
+                        .map(|&to_idx| Link(from_idx, to_idx))
+                        .collect(),
+                    (None, Some(to_idx)) => indexed
+                        .backward
+                        .get(&to_idx)
+                        .unwrap_or(&no_indices)
+                        .iter()
+                        .map(|&from_idx| Link(from_idx, to_idx))
+                        .collect(),
+                    (None, None) => indexed.links.iter().cloned().collect(),
+                };
+                for Link(from_idx, to_idx) in candidates {
+                    // an atom like `r(X, X)` only matches loops
+                    if atom.from == atom.to && from_idx != to_idx {
+                        continue;
+                    }
+                    let mut binding = binding.clone();
+                    binding[atom.from] = Some(from_idx);
+                    binding[atom.to] = Some(to_idx);
+                    next_bindings.push(binding);
+                }
+            }
+            bindings = next_bindings;
+        }
+        bindings
+            .into_iter()
+            .map(|binding| {
+                Link(
+                    binding[self.head.from].expect("head variables are bound by the body"),
+                    binding[self.head.to].expect("head variables are bound by the body"),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Parses an atom like `relation(X, Y`, the closing parenthesis being
+/// already removed. The unknown variables are added to `variables` if
+/// `new_variables` is `true`.
+fn parse_atom(atom: &str, variables: &mut Vec<String>, new_variables: bool) -> Option<Atom> {
+    let mut split = atom.splitn(2, '(');
+    let relation = split.next()?.trim();
+    let mut arguments = split.next()?.split(',').map(str::trim);
+    let (from, to) = match (arguments.next(), arguments.next(), arguments.next()) {
+        (Some(from), Some(to), None) => (from, to),
+        _ => return None,
+    };
+    let is_identifier =
+        |name: &str| !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if !is_identifier(relation) || !is_identifier(from) || !is_identifier(to) {
+        return None;
+    }
+    let mut variable = |name: &str| match variables.iter().position(|v| v == name) {
+        Some(position) => Some(position),
+        None if new_variables => {
+            variables.push(name.to_owned());
+            Some(variables.len() - 1)
+        }
+        None => None,
+    };
+    Some(Atom {
+        relation: relation.to_owned(),
+        from: variable(from)?,
+        to: variable(to)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use typed_index_collection::CollectionWithId;
+
+    // the animals `a0`, `a1`, … linked by the `links` between their
+    // numbers
+    fn relation(
+        animals: &CollectionWithId<Animal>,
+        links: &[(usize, usize)],
+    ) -> ManyToMany<Animal, Animal> {
+        let mut relation = ManyToMany::default();
+        for &(from, to) in links {
+            relation.add_link(Link(
+                animals.get_idx(&format!("a{}", from)).unwrap(),
+                animals.get_idx(&format!("a{}", to)).unwrap(),
+            ));
+        }
+        relation
+    }
+
+    fn links(
+        animals: &CollectionWithId<Animal>,
+        relation: &ManyToMany<Animal, Animal>,
+    ) -> Vec<(String, String)> {
+        relation
+            .links_sorted()
+            .into_iter()
+            .map(|link| {
+                (
+                    animals[link.from()].id.clone(),
+                    animals[link.to()].id.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn pairs(pairs: &[(usize, usize)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(from, to)| (format!("a{}", from), format!("a{}", to)))
+            .collect()
+    }
+
+    #[test]
+    fn recursive_rules() {
+        let animals = animals(&["a0", "a1", "a2", "a3"]);
+        let parents = relation(&animals, &[(0, 1), (1, 2), (2, 3)]);
+        let mut program = Program::default();
+        program.relation("parent", &parents);
+        program.rule("ancestor(X, Y) :- parent(X, Y)").unwrap();
+        program
+            .rule("ancestor(X, Z) :- ancestor(X, Y), ancestor(Y, Z)")
+            .unwrap();
+        let ancestors = program.evaluate().remove("ancestor").unwrap();
+        assert_eq!(
+            pairs(&[(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]),
+            links(&animals, &ancestors)
+        );
+    }
+
+    #[test]
+    fn rules_with_loops() {
+        let animals = animals(&["a0", "a1", "a2"]);
+        let parents = relation(&animals, &[(0, 0), (0, 1), (1, 2)]);
+        let mut program = Program::default();
+        program.relation("parent", &parents);
+        program
+            .rule("child_of_looping(X, Y) :- parent(X, X), parent(X, Y)")
+            .unwrap();
+        program.rule("itself(X, X) :- parent(X, Y)").unwrap();
+        let mut derived = program.evaluate();
+        let children_of_looping = derived.remove("child_of_looping").unwrap();
+        assert_eq!(
+            pairs(&[(0, 0), (0, 1)]),
+            links(&animals, &children_of_looping)
+        );
+        let itself = derived.remove("itself").unwrap();
+        assert_eq!(pairs(&[(0, 0), (1, 1)]), links(&animals, &itself));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let mut program = Program::<Animal>::default();
+        for (rule, reason) in [
+            ("ancestor(X, Y)", "`:-` is missing"),
+            ("ancestor(X, Y) :- ", "the body is empty"),
+            (
+                "ancestor(X, Z) :- parent(X, Y) parent(Y, Z)",
+                "the atoms of the body must be separated by `,`",
+            ),
+            (
+                "ancestor(X, Y) :- parent(X, Y, Z)",
+                "the body must be atoms like `relation(X, Y)`",
+            ),
+            (
+                "ancestor(X, Y) :- parent(X Y)",
+                "the body must be atoms like `relation(X, Y)`",
+            ),
+            (
+                "ancestor(X, Z) :- parent(X, Y)",
+                "the head must be an atom like `relation(X, Y)` using variables of the body",
+            ),
+            (
+                "ancestor :- parent(X, Y)",
+                "the head must be an atom like `relation(X, Y)` using variables of the body",
+            ),
+        ] {
+            match program.rule(rule) {
+                Err(Error::InvalidRule(invalid_rule, invalid_reason)) => {
+                    assert_eq!(rule, invalid_rule);
+                    assert_eq!(reason, invalid_reason, "{}", rule);
+                }
+                _ => panic!("{} is valid", rule),
+            }
+        }
+        assert!(program.rules.is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+//! The maps storing the relations, selected by the cargo features:
+//! `storage-btree` (default) for a `BTreeMap`, `storage-hash` for a
+//! `HashMap`, and `storage-hash-ahash` for a `HashMap` using the
+//! faster `ahash` hasher. When several are enabled, the hash maps
+//! win, and `ahash` wins over the default hasher, thus enabling
+//! `storage-hash` is enough to switch, without disabling the default
+//! features. One of them must be enabled.
+//!
+//! Only the maps follow the features: the sets of indices stay
+//! `IdxSet`s, i.e. `BTreeSet`s, whatever the storage, so that the
+//! results of the traversals are sorted and the public API doesn't
+//! change with the features. A `ManyToMany` is converted from and to
+//! the maps of the other storages with `From` and `to_forward`, e.g.
+//! to exchange relations with a crate built with another storage.
+
+use typed_index_collection::Idx;
+
+#[cfg(not(any(
+    feature = "storage-btree",
+    feature = "storage-hash",
+    feature = "storage-hash-ahash"
+)))]
+compile_error!(
+    "one of the `storage-btree`, `storage-hash` and `storage-hash-ahash` features must be enabled"
+);
+
+/// The map used internally by the relations to store the objects
+/// linked to each object.
+#[cfg(not(any(feature = "storage-hash", feature = "storage-hash-ahash")))]
+pub type IdxMap<K, V> = std::collections::BTreeMap<K, V>;
+
+/// The map used internally by the relations to store the objects
+/// linked to each object.
+#[cfg(all(feature = "storage-hash", not(feature = "storage-hash-ahash")))]
+pub type IdxMap<K, V> = std::collections::HashMap<K, V>;
+
+/// The map used internally by the relations to store the objects
+/// linked to each object.
+#[cfg(feature = "storage-hash-ahash")]
+pub type IdxMap<K, V> = std::collections::HashMap<K, V, ahash::RandomState>;
+
+/// Returns an empty map able to hold `capacity` objects without
+/// reallocating, if the storage supports it.
+#[cfg(not(any(feature = "storage-hash", feature = "storage-hash-ahash")))]
+pub(crate) fn map_with_capacity<K, V>(_capacity: usize) -> IdxMap<K, V> {
+    IdxMap::new()
+}
+
+/// Returns an empty map able to hold `capacity` objects without
+/// reallocating, if the storage supports it.
+#[cfg(any(feature = "storage-hash", feature = "storage-hash-ahash"))]
+pub(crate) fn map_with_capacity<K, V>(capacity: usize) -> IdxMap<K, V> {
+    IdxMap::with_capacity_and_hasher(capacity, Default::default())
+}
+
+/// Reserves the capacity for `additional` more objects, if the
+/// storage supports it.
+#[cfg(not(any(feature = "storage-hash", feature = "storage-hash-ahash")))]
+pub(crate) fn reserve<T, V>(_map: &mut IdxMap<Idx<T>, V>, _additional: usize) {}
+
+/// Reserves the capacity for `additional` more objects, if the
+/// storage supports it.
+#[cfg(any(feature = "storage-hash", feature = "storage-hash-ahash"))]
+pub(crate) fn reserve<T, V>(map: &mut IdxMap<Idx<T>, V>, additional: usize) {
+    map.reserve(additional);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+    use crate::{IdxSet, Link, ManyToMany, Relation};
+    use std::collections::{BTreeMap, HashMap};
+
+    // run with each storage feature by the continuous integration
+    #[test]
+    fn results_do_not_depend_on_the_storage() {
+        let animals = animals(&["a1", "a2", "a3"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a1"), ("f3", "a1")]);
+        let mut relation = ManyToMany::with_capacity(3, 3);
+        relation.reserve(3, 3);
+        let mut links = Vec::new();
+        for (animal_idx, _) in animals.iter() {
+            for (feline_idx, _) in felines.iter() {
+                links.push(Link(animal_idx, feline_idx));
+            }
+        }
+        for &link in links.iter().rev() {
+            relation.add_link(link);
+        }
+        assert_eq!(links, relation.links_sorted());
+        let all_animals: IdxSet<_> = animals.iter().map(|(idx, _)| idx).collect();
+        let all_felines: IdxSet<_> = felines.iter().map(|(idx, _)| idx).collect();
+        assert_eq!(
+            all_felines,
+            relation.get_corresponding_forward(&all_animals)
+        );
+        assert_eq!(
+            all_animals,
+            relation.get_corresponding_backward(&all_felines)
+        );
+    }
+
+    #[test]
+    fn conversions_between_storages() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a1")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let felines_of_a1: IdxSet<_> = felines.iter().map(|(idx, _)| idx).collect();
+        let mut btree_map = BTreeMap::new();
+        btree_map.insert(a1, felines_of_a1);
+        let hash_map: HashMap<_, _> = btree_map.clone().into_iter().collect();
+
+        let from_btree_map = ManyToMany::from(btree_map.clone());
+        let from_hash_map = ManyToMany::from(hash_map.clone());
+        assert_eq!(from_btree_map.links_sorted(), from_hash_map.links_sorted());
+        assert_eq!(btree_map, from_hash_map.to_forward::<BTreeMap<_, _>>());
+        assert_eq!(hash_map, from_btree_map.to_forward::<HashMap<_, _>>());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::ManyToMany;
+use std::collections::BTreeSet;
+use std::iter::FromIterator;
+use typed_index_collection::Idx;
+
+/// A triple of linked objects.
+pub type Triple<A, B, C> = (Idx<A>, Idx<B>, Idx<C>);
+
+/// A relation linking objects of 3 types at once, e.g. an `Operator`
+/// running a `Line` on a `Network`, which would be lost by storing
+/// only the pairwise relations: an operator running 2 lines on 2
+/// networks doesn't necessarily run both lines on both networks.
+///
+/// The pairwise relations are available as `ManyToMany`s through
+/// `project_ab`, `project_bc` and `project_ac`, e.g. to be used as
+/// relations of a model.
+#[derive(Debug)]
+pub struct Ternary<A, B, C> {
+    triples: BTreeSet<Triple<A, B, C>>,
+}
+
+impl<A, B, C> Default for Ternary<A, B, C> {
+    fn default() -> Self {
+        Ternary {
+            triples: Default::default(),
+        }
+    }
+}
+
+impl<A, B, C> Ternary<A, B, C> {
+    /// Adds a triple, returning `true` if it was not in the relation.
+    pub fn add_triple(&mut self, a: Idx<A>, b: Idx<B>, c: Idx<C>) -> bool {
+        self.triples.insert((a, b, c))
+    }
+
+    /// Removes a triple, returning `true` if it was in the relation.
+    pub fn remove_triple(&mut self, a: Idx<A>, b: Idx<B>, c: Idx<C>) -> bool {
+        self.triples.remove(&(a, b, c))
+    }
+
+    /// Returns `true` if the relation contains the given triple.
+    pub fn contains_triple(&self, a: Idx<A>, b: Idx<B>, c: Idx<C>) -> bool {
+        self.triples.contains(&(a, b, c))
+    }
+
+    /// Returns an iterator over the triples, sorted by `A`, then by
+    /// `B`, then by `C`.
+    pub fn triples(&self) -> impl Iterator<Item = Triple<A, B, C>> + '_ {
+        self.triples.iter().cloned()
+    }
+
+    /// Returns the number of triples.
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    /// Returns `true` if the relation contains no triple.
+    pub fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+    }
+
+    /// Returns the relation between the `A`s and the `B`s.
+    pub fn project_ab(&self) -> ManyToMany<A, B> {
+        let mut relation = ManyToMany::default();
+        // the triples are sorted by `A`
+        relation.extend_links(self.triples().map(|(a, b, _)| (a, b)));
+        relation
+    }
+
+    /// Returns the relation between the `B`s and the `C`s.
+    pub fn project_bc(&self) -> ManyToMany<B, C> {
+        let mut links: Vec<_> = self.triples().map(|(_, b, c)| (b, c)).collect();
+        links.sort_unstable();
+        let mut relation = ManyToMany::default();
+        relation.extend_links(links);
+        relation
+    }
+
+    /// Returns the relation between the `A`s and the `C`s.
+    pub fn project_ac(&self) -> ManyToMany<A, C> {
+        let mut relation = ManyToMany::default();
+        relation.extend_links(self.triples().map(|(a, _, c)| (a, c)));
+        relation
+    }
+}
+
+impl<A, B, C> FromIterator<Triple<A, B, C>> for Ternary<A, B, C> {
+    fn from_iter<I: IntoIterator<Item = Triple<A, B, C>>>(triples: I) -> Self {
+        Ternary {
+            triples: triples.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::{IdxSet, Link, Relation};
+
+    #[test]
+    fn projections() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let cats = cats(&[("c1", "f1"), ("c2", "f2")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let a2 = animals.get_idx("a2").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        let c1 = cats.get_idx("c1").unwrap();
+        let c2 = cats.get_idx("c2").unwrap();
+        // sorted by animal, the felines are not sorted
+        let ternary: Ternary<Animal, Feline, Cat> = vec![(a1, f2, c1), (a1, f2, c2), (a2, f1, c2)]
+            .into_iter()
+            .collect();
+        assert_eq!(3, ternary.len());
+        assert!(ternary.contains_triple(a1, f2, c2));
+
+        assert_eq!(
+            vec![Link(a1, f2), Link(a2, f1)],
+            ternary.project_ab().links_sorted()
+        );
+        let bc = ternary.project_bc();
+        assert_eq!(
+            vec![Link(f1, c2), Link(f2, c1), Link(f2, c2)],
+            bc.links_sorted()
+        );
+        let only_c2: IdxSet<Cat> = Some(c2).into_iter().collect();
+        let felines_of_c2: IdxSet<Feline> = vec![f1, f2].into_iter().collect();
+        assert_eq!(felines_of_c2, bc.get_corresponding_backward(&only_c2));
+        assert_eq!(
+            vec![Link(a1, c1), Link(a1, c2), Link(a2, c2)],
+            ternary.project_ac().links_sorted()
+        );
+    }
+}
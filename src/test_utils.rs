@@ -0,0 +1,47 @@
+// the identifiers of the parents are given by fields of other names
+#![allow(clippy::misnamed_getters)]
+
+use typed_index_collection::{impl_id, CollectionWithId};
+
+#[derive(Debug)]
+pub struct Animal {
+    pub id: String,
+}
+impl_id!(Animal);
+
+#[derive(Debug)]
+pub struct Feline {
+    pub id: String,
+    pub animal_id: String,
+}
+impl_id!(Feline);
+impl_id!(Feline, Animal, animal_id);
+
+#[derive(Debug)]
+pub struct Cat {
+    pub id: String,
+    pub feline_id: String,
+}
+impl_id!(Cat);
+impl_id!(Cat, Feline, feline_id);
+
+pub fn animals(ids: &[&str]) -> CollectionWithId<Animal> {
+    let animals = ids.iter().map(|id| Animal { id: id.to_string() });
+    CollectionWithId::new(animals.collect()).unwrap()
+}
+
+pub fn felines(ids: &[(&str, &str)]) -> CollectionWithId<Feline> {
+    let felines = ids.iter().map(|&(id, animal_id)| Feline {
+        id: id.to_string(),
+        animal_id: animal_id.to_string(),
+    });
+    CollectionWithId::new(felines.collect()).unwrap()
+}
+
+pub fn cats(ids: &[(&str, &str)]) -> CollectionWithId<Cat> {
+    let cats = ids.iter().map(|&(id, feline_id)| Cat {
+        id: id.to_string(),
+        feline_id: feline_id.to_string(),
+    });
+    CollectionWithId::new(cats.collect()).unwrap()
+}
@@ -0,0 +1,124 @@
+use crate::{IdxSet, Relation};
+use std::borrow::Cow;
+use typed_index_collection::Idx;
+
+/// A non-owning view over a relation, restricted to a set of source
+/// objects.
+///
+/// As it implements `Relation`, a view can be used to build a
+/// `ManyToMany` from only a part of a model, without copying the
+/// underlying relation.
+pub struct RelationView<'a, T, U> {
+    relation: &'a dyn Relation<From = T, To = U>,
+    sources: Cow<'a, IdxSet<T>>,
+}
+
+impl<'a, T, U> RelationView<'a, T, U> {
+    /// Constructs a view of `relation` restricted to the `sources`.
+    pub fn new(relation: &'a dyn Relation<From = T, To = U>, sources: &'a IdxSet<T>) -> Self {
+        RelationView {
+            relation,
+            sources: Cow::Borrowed(sources),
+        }
+    }
+
+    /// Constructs a view of `relation` restricted to the `sources`,
+    /// taking ownership of the set of sources.
+    pub fn from_owned(relation: &'a dyn Relation<From = T, To = U>, sources: IdxSet<T>) -> Self {
+        RelationView {
+            relation,
+            sources: Cow::Owned(sources),
+        }
+    }
+
+    /// Returns the sources the view is restricted to.
+    pub fn sources(&self) -> &IdxSet<T> {
+        &self.sources
+    }
+
+    fn restrict(&self, from: &IdxSet<T>) -> IdxSet<T> {
+        from.intersection(&self.sources).cloned().collect()
+    }
+}
+
+impl<'a, T, U> Relation for RelationView<'a, T, U> {
+    type From = T;
+    type To = U;
+    fn iter_from(&self) -> Box<dyn Iterator<Item = Idx<T>> + '_> {
+        Box::new(
+            self.relation
+                .iter_from()
+                .filter(move |idx| self.sources.contains(idx)),
+        )
+    }
+    fn iter_to(&self) -> Box<dyn Iterator<Item = Idx<U>> + '_> {
+        Box::new(
+            self.relation
+                .get_corresponding_forward(&self.sources)
+                .into_iter(),
+        )
+    }
+    fn get_corresponding_forward(&self, from: &IdxSet<T>) -> IdxSet<U> {
+        self.relation
+            .get_corresponding_forward(&self.restrict(from))
+    }
+    fn get_corresponding_forward_within(&self, from: &IdxSet<T>, allowed: &IdxSet<U>) -> IdxSet<U> {
+        self.relation
+            .get_corresponding_forward_within(&self.restrict(from), allowed)
+    }
+    fn get_corresponding_backward(&self, from: &IdxSet<U>) -> IdxSet<T> {
+        self.restrict(&self.relation.get_corresponding_backward(from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::{Link, ManyToMany, OneToMany};
+
+    #[test]
+    fn view_is_restricted_to_its_sources() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a1"), ("f3", "a2")]);
+        let cats = cats(&[("c1", "f1"), ("c3", "f3")]);
+        let a1 = animals.get_idx("a1").unwrap();
+        let f1 = felines.get_idx("f1").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        let f3 = felines.get_idx("f3").unwrap();
+        let c1 = cats.get_idx("c1").unwrap();
+        let animals_to_felines = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        let felines_to_cats = OneToMany::new(&felines, &cats, "felines_to_cats").unwrap();
+        let sources: IdxSet<Animal> = Some(a1).into_iter().collect();
+        let view = RelationView::new(&animals_to_felines, &sources);
+
+        assert_eq!(vec![a1], view.iter_from().collect::<Vec<_>>());
+        let felines_of_a1: IdxSet<Feline> = vec![f1, f2].into_iter().collect();
+        assert_eq!(felines_of_a1, view.iter_to().collect::<IdxSet<_>>());
+        let all_animals: IdxSet<Animal> = animals.iter().map(|(idx, _)| idx).collect();
+        assert_eq!(felines_of_a1, view.get_corresponding_forward(&all_animals));
+        let allowed: IdxSet<Feline> = vec![f2, f3].into_iter().collect();
+        assert_eq!(
+            Some(f2).into_iter().collect::<IdxSet<_>>(),
+            view.get_corresponding_forward_within(&all_animals, &allowed)
+        );
+        let some_felines: IdxSet<Feline> = vec![f1, f3].into_iter().collect();
+        assert_eq!(sources, view.get_corresponding_backward(&some_felines));
+
+        // a part of the model, without copying the relation
+        let chain = ManyToMany::from_relations_chain(&view, &felines_to_cats);
+        assert_eq!(vec![Link(a1, c1)], chain.links_sorted());
+    }
+
+    #[test]
+    fn owned_view() {
+        let animals = animals(&["a1", "a2"]);
+        let felines = felines(&[("f1", "a1"), ("f2", "a2")]);
+        let a2 = animals.get_idx("a2").unwrap();
+        let f2 = felines.get_idx("f2").unwrap();
+        let relation = OneToMany::new(&animals, &felines, "animals_to_felines").unwrap();
+        let view = RelationView::from_owned(&relation, Some(a2).into_iter().collect());
+        assert_eq!(&Some(a2).into_iter().collect::<IdxSet<_>>(), view.sources());
+        assert_eq!(vec![f2], view.iter_to().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,102 @@
+//! WebAssembly bindings, running the traversals of a model from its
+//! `RelationsArchive` in JavaScript.
+//!
+//! ```js
+//! const world = new ArchivedWorld(bytes, 1);
+//! const owners = world.traverse("brands_to_bikes,-owners_to_bikes", [0]);
+//! world.addPath("Brand", "Owner", "brands_to_bikes,-owners_to_bikes");
+//! const sameOwners = world.getCorresponding("Brand", "Owner", [0]);
+//! ```
+//!
+//! The paths between the types can also be added in Rust by
+//! `ArchivedWorld::with_schema`, from the `schema` generated by the
+//! `GetCorresponding` derive.
+
+use crate::{Migrations, RelationsArchive, WorldSchema};
+use std::collections::{BTreeSet, HashMap};
+use wasm_bindgen::prelude::*;
+
+/// The relations of a model read from a `RelationsArchive`, the
+/// objects being given by their raw indices.
+#[wasm_bindgen]
+pub struct ArchivedWorld {
+    archive: RelationsArchive,
+    // the path followed from a type to another, in the syntax of
+    // `RelationsArchive::traverse_path`
+    paths: HashMap<(String, String), String>,
+}
+
+impl ArchivedWorld {
+    /// Adds the paths of `schema` between its types, as generated by
+    /// the `GetCorresponding` derive, for `getCorresponding`.
+    pub fn with_schema(mut self, schema: &WorldSchema) -> Self {
+        for query in &schema.queries {
+            self.add_path(query.from, query.to, &query.path());
+        }
+        self
+    }
+}
+
+#[wasm_bindgen]
+impl ArchivedWorld {
+    /// Reads the archive written by `RelationsArchive::write`, for the
+    /// version `version` of the model.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8], version: u32) -> Result<ArchivedWorld, JsError> {
+        let archive = RelationsArchive::read(bytes, &Migrations::new(version))?;
+        Ok(ArchivedWorld {
+            archive,
+            paths: HashMap::new(),
+        })
+    }
+
+    /// Returns the names of the relations, in the order of the archive.
+    #[wasm_bindgen(js_name = relationNames)]
+    pub fn relation_names(&self) -> Vec<JsValue> {
+        self.archive
+            .relations
+            .iter()
+            .map(|relation| JsValue::from_str(&relation.name))
+            .collect()
+    }
+
+    /// Returns the raw indices reached from `from` by following the
+    /// relations of `path`, as `RelationsArchive::traverse_path`.
+    pub fn traverse(&self, path: &str, from: &[u32]) -> Result<Vec<u32>, JsError> {
+        let from: Vec<usize> = from.iter().map(|&idx| idx as usize).collect();
+        let reached = self
+            .archive
+            .traverse_path(path, &from)
+            .ok_or_else(|| JsError::new(&format!("unknown relation in path {}", path)))?;
+        Ok(reached.into_iter().map(|idx| idx as u32).collect())
+    }
+
+    /// Sets the path followed from the type `from_type` to the type
+    /// `to_type` by `getCorresponding`, in the syntax of `traverse`.
+    #[wasm_bindgen(js_name = addPath)]
+    pub fn add_path(&mut self, from_type: &str, to_type: &str, path: &str) {
+        self.paths
+            .insert((from_type.to_owned(), to_type.to_owned()), path.to_owned());
+    }
+
+    /// Returns the raw indices of the objects of type `to_type`
+    /// corresponding to the objects of type `from_type` of raw indices
+    /// `from`, sorted, following the path set by `addPath`.
+    #[wasm_bindgen(js_name = getCorresponding)]
+    pub fn get_corresponding(
+        &self,
+        from_type: &str,
+        to_type: &str,
+        from: &[u32],
+    ) -> Result<Vec<u32>, JsError> {
+        if from_type == to_type {
+            let from: BTreeSet<u32> = from.iter().copied().collect();
+            return Ok(from.into_iter().collect());
+        }
+        let path = self
+            .paths
+            .get(&(from_type.to_owned(), to_type.to_owned()))
+            .ok_or_else(|| JsError::new(&format!("no path from {} to {}", from_type, to_type)))?;
+        self.traverse(path, from)
+    }
+}
@@ -0,0 +1,415 @@
+#[cfg(feature = "log")]
+use crate::{Error, LoggedChange};
+use crate::{IdxSet, Relation};
+use std::any::{type_name, Any};
+
+/// Description of a relation of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RelationInfo {
+    /// Name of the relation, usually the name of the field.
+    pub name: &'static str,
+    /// Name of the type of the source objects.
+    pub from: &'static str,
+    /// Name of the type of the target objects.
+    pub to: &'static str,
+}
+
+/// A model made of relations, whatever the types of their objects,
+/// so that generic tools like exporters or validators can be written
+/// once for all the models.
+///
+/// It is implemented by the `GetCorresponding` derive.
+pub trait RelationalWorld {
+    /// Returns the description of every relation of the model, in the
+    /// order of declaration.
+    fn relations(&self) -> &'static [RelationInfo];
+
+    /// Returns the links of the relation named `name`, as pairs of
+    /// raw indices, or `None` if the model has no such relation.
+    fn links_of(&self, name: &str) -> Option<Vec<(usize, usize)>>;
+
+    /// Calls `f` with the raw indices of each link of the relation
+    /// named `name`, without collecting them, and returns `false` if
+    /// the model has no such relation. The `GetCorresponding` derive
+    /// visits the links with `Relation::for_each_link`, the default
+    /// implementation goes through `links_of`.
+    fn for_each_link_of(&self, name: &str, f: &mut dyn FnMut(usize, usize)) -> bool {
+        match self.links_of(name) {
+            Some(links) => {
+                for (from, to) in links {
+                    f(from, to);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the names of the relations, in the order of
+    /// declaration.
+    fn relation_names(&self) -> Vec<&'static str> {
+        self.relations().iter().map(|info| info.name).collect()
+    }
+
+    /// Writes every link of the model to `writer` in JSON Lines, a
+    /// `LinkRecord` per line with the identifiers of the objects given
+    /// by `collections`, e.g. to audit a large model with text tools.
+    /// The links are visited by `for_each_link_of` and each record is
+    /// written as soon as its link is visited, without collecting the
+    /// links, thus `writer` should be buffered.
+    ///
+    /// Fails with an `InvalidData` error if the identifiers of a link
+    /// are unknown to `collections`.
+    #[cfg(feature = "serde")]
+    fn export_links_jsonl<W, C>(&self, mut writer: W, collections: &C) -> std::io::Result<()>
+    where
+        Self: Sized,
+        W: std::io::Write,
+        C: LinkIds,
+    {
+        for info in self.relations() {
+            let mut result = Ok(());
+            self.for_each_link_of(info.name, &mut |from, to| {
+                // the links after an error are skipped
+                if result.is_ok() {
+                    result = write_link_record(&mut writer, collections, info.name, (from, to));
+                }
+            });
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Checks the `invariants` on the relations of the model, and
+    /// returns the broken ones with the objects breaking them.
+    #[cfg(feature = "analysis")]
+    fn check_invariants(
+        &self,
+        invariants: &[crate::invariants::Invariant],
+    ) -> crate::invariants::InvariantsReport {
+        crate::invariants::check(self, invariants)
+    }
+}
+
+/// Writes the `LinkRecord` of the `link` of the relation named
+/// `relation` as a line of JSON.
+#[cfg(feature = "serde")]
+fn write_link_record<W, C>(
+    mut writer: W,
+    collections: &C,
+    relation: &str,
+    link: (usize, usize),
+) -> std::io::Result<()>
+where
+    W: std::io::Write,
+    C: LinkIds,
+{
+    let (from, to) = collections.link_ids(relation, link).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("unknown objects {:?} in relation {}", link, relation),
+        )
+    })?;
+    let record = LinkRecord {
+        relation: relation.to_owned(),
+        from,
+        to,
+    };
+    serde_json::to_writer(&mut writer, &record)?;
+    writer.write_all(b"\n")
+}
+
+/// A link of a model, with the identifiers of its objects, as written
+/// by `RelationalWorld::export_links_jsonl`.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LinkRecord {
+    /// Name of the relation.
+    pub relation: String,
+    /// Identifier of the source object.
+    pub from: String,
+    /// Identifier of the target object.
+    pub to: String,
+}
+
+/// Identifiers of the objects linked by the relations of a model,
+/// used to describe links given as raw indices.
+///
+/// It is implemented for the collections of a model by the
+/// `BuildRelations` derive.
+pub trait LinkIds {
+    /// Returns the identifiers of the objects of the `link` of the
+    /// relation named `relation`, or `None` if they are unknown.
+    fn link_ids(&self, relation: &str, link: (usize, usize)) -> Option<(String, String)>;
+}
+
+/// Indices of the objects linked by the relations of a model, given
+/// by their identifiers, the converse of `LinkIds`.
+///
+/// It is implemented for the collections of a model by the
+/// `BuildRelations` derive.
+#[cfg(feature = "log")]
+pub trait LinkIndices {
+    /// Returns the change adding the link between the objects of
+    /// identifiers `link` to the relation named `relation`, or an
+    /// error if the relation or the identifiers are unknown.
+    fn link_change(&self, relation: &str, link: (&str, &str)) -> Result<LoggedChange, Error>;
+}
+
+/// A model whose relations can be changed by the changes recorded by
+/// a `WorldLog`.
+///
+/// It is implemented by the `GetCorresponding` derive, changing the
+/// `OneToMany` and `ManyToMany` relations which are not delegated.
+#[cfg(feature = "log")]
+pub trait ReplayLog {
+    /// Applies `change` to the relation it names.
+    fn apply_change(&mut self, change: &LoggedChange) -> Result<(), Error>;
+
+    /// Adds the links read from `reader` in JSON Lines, a `LinkRecord`
+    /// per line as written by `RelationalWorld::export_links_jsonl`,
+    /// the identifiers being resolved by `collections`, e.g. to edit a
+    /// model with text tools.
+    ///
+    /// The lines which can't be added are skipped, and returned with
+    /// their number, starting from 1, and their error. Only the errors
+    /// of `reader` stop the import.
+    #[cfg(feature = "serde")]
+    fn import_links_jsonl<R, C>(
+        &mut self,
+        reader: R,
+        collections: &C,
+    ) -> std::io::Result<Vec<(usize, Error)>>
+    where
+        Self: Sized,
+        R: std::io::BufRead,
+        C: LinkIndices,
+    {
+        let mut failures = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let imported = serde_json::from_str::<LinkRecord>(&line)
+                .map_err(|error| Error::InvalidRecord(error.to_string()))
+                .and_then(|record| {
+                    collections.link_change(&record.relation, (&record.from, &record.to))
+                })
+                .and_then(|change| self.apply_change(&change));
+            if let Err(error) = imported {
+                failures.push((number + 1, error));
+            }
+        }
+        Ok(failures)
+    }
+}
+
+/// A relation whose types are erased, so that runtime tooling can
+/// handle any relation of a model.
+///
+/// It is implemented for every `Relation`, and returned by the
+/// `relation_between` method generated by the `GetCorresponding`
+/// derive.
+pub trait DynRelation {
+    /// Returns the name of the type of the source objects.
+    fn source_type_name(&self) -> &'static str;
+
+    /// Returns the name of the type of the target objects.
+    fn target_type_name(&self) -> &'static str;
+
+    /// Returns the links of the relation, as pairs of raw indices.
+    fn raw_links(&self) -> Vec<(usize, usize)>;
+
+    /// Returns the relation as `Any`, to downcast it to its concrete
+    /// type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<R> DynRelation for R
+where
+    R: Relation + 'static,
+{
+    fn source_type_name(&self) -> &'static str {
+        type_name::<R::From>()
+    }
+
+    fn target_type_name(&self) -> &'static str {
+        type_name::<R::To>()
+    }
+
+    fn raw_links(&self) -> Vec<(usize, usize)> {
+        let mut links = Vec::new();
+        self.for_each_link(|from_idx, to_idx| links.push((from_idx.get(), to_idx.get())));
+        links
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A model able to return the objects corresponding to a set of
+/// objects, so that generic wrappers like `InstrumentedWorld` can
+/// run the traversals of any model.
+///
+/// It is implemented by the `GetCorresponding` derive for every pair
+/// of types linked by the model.
+pub trait Traverse<T, U> {
+    /// Returns the set of `U` indices corresponding to the `from` set.
+    fn traverse(&self, from: &IdxSet<T>) -> IdxSet<U>;
+}
+
+/// Description of the types and relations of a model, e.g. to
+/// generate matching types and queries in other languages.
+///
+/// It is returned by the `schema` method generated by the
+/// `GetCorresponding` derive.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WorldSchema {
+    /// Names of the types linked by the relations, in the order of
+    /// first appearance.
+    pub types: Vec<&'static str>,
+    /// The relations, in the order of declaration.
+    pub relations: Vec<RelationSchema>,
+    /// The path followed between every pair of distinct connected
+    /// types.
+    pub queries: Vec<QuerySchema>,
+}
+
+impl WorldSchema {
+    /// Serializes the schema in JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("schemas are always serializable")
+    }
+}
+
+/// Description of a relation in a `WorldSchema`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RelationSchema {
+    /// Name of the relation.
+    pub name: &'static str,
+    /// Type of the relation, e.g. `OneToMany`.
+    pub kind: &'static str,
+    /// Name of the type of the source objects.
+    pub from: &'static str,
+    /// Name of the type of the target objects.
+    pub to: &'static str,
+    /// Weight of the relation, in both directions.
+    pub weight: f64,
+}
+
+/// Description of the traversal from a type to another in a
+/// `WorldSchema`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct QuerySchema {
+    /// Name of the type of the source objects.
+    pub from: &'static str,
+    /// Name of the type of the target objects.
+    pub to: &'static str,
+    /// The relations followed, in order.
+    pub hops: Vec<HopSchema>,
+}
+
+impl QuerySchema {
+    /// Returns the hops in the syntax of
+    /// `RelationsArchive::traverse_path`, e.g.
+    /// `"brands_to_bikes,-owners_to_bikes"`, to run the traversal from
+    /// the archive of the model, e.g. in the bindings.
+    pub fn path(&self) -> String {
+        let hops: Vec<String> = self
+            .hops
+            .iter()
+            .map(|hop| {
+                if hop.forward {
+                    hop.relation.to_owned()
+                } else {
+                    format!("-{}", hop.relation)
+                }
+            })
+            .collect();
+        hops.join(",")
+    }
+}
+
+/// A relation followed by a `QuerySchema`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HopSchema {
+    /// Name of the relation.
+    pub relation: &'static str,
+    /// `true` if the relation is followed from its sources to its
+    /// targets.
+    pub forward: bool,
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    // a model whose links can only be visited one by one
+    struct World;
+
+    impl RelationalWorld for World {
+        fn relations(&self) -> &'static [RelationInfo] {
+            &[RelationInfo {
+                name: "animals_to_felines",
+                from: "Animal",
+                to: "Feline",
+            }]
+        }
+
+        fn links_of(&self, _: &str) -> Option<Vec<(usize, usize)>> {
+            panic!("the links are collected")
+        }
+
+        fn for_each_link_of(&self, name: &str, f: &mut dyn FnMut(usize, usize)) -> bool {
+            if name != "animals_to_felines" {
+                return false;
+            }
+            for link in [(0, 0), (0, 1), (1, 2)] {
+                f(link.0, link.1);
+            }
+            true
+        }
+    }
+
+    // the identifiers of the objects of raw indices below `nb_objects`
+    struct Ids {
+        nb_objects: usize,
+    }
+
+    impl LinkIds for Ids {
+        fn link_ids(&self, _: &str, (from, to): (usize, usize)) -> Option<(String, String)> {
+            if from < self.nb_objects && to < self.nb_objects {
+                Some((format!("a{}", from), format!("f{}", to)))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn export_links_jsonl_streams_the_links() {
+        let mut output = Vec::new();
+        World
+            .export_links_jsonl(&mut output, &Ids { nb_objects: 3 })
+            .unwrap();
+        assert_eq!(3, output.iter().filter(|&&byte| byte == b'\n').count());
+    }
+
+    #[test]
+    fn export_links_jsonl_stops_at_unknown_objects() {
+        let mut output = Vec::new();
+        let error = World
+            .export_links_jsonl(&mut output, &Ids { nb_objects: 2 })
+            .unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+        assert_eq!(2, output.iter().filter(|&&byte| byte == b'\n').count());
+    }
+}